@@ -56,8 +56,8 @@ macro_rules! impl_decode_target {
                                 DecodeError::LiteralFound { .. } => {
                                     assert!(index < output.len());
                                 }
-                                DecodeError::Failed => {
-                                    panic!("Expected `Ok` or `Incomplete`, got `Failed`");
+                                DecodeError::Failed(failure) => {
+                                    panic!("Expected `Ok` or `Incomplete`, got `Failed({failure:?})`");
                                 }
                             },
                         }
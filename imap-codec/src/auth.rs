@@ -1,7 +1,3 @@
-#[cfg(not(feature = "quirk_crlf_relaxed"))]
-use abnf_core::streaming::crlf;
-#[cfg(feature = "quirk_crlf_relaxed")]
-use abnf_core::streaming::crlf_relaxed as crlf;
 use imap_types::auth::{AuthMechanism, AuthenticateData};
 use nom::{
     branch::alt,
@@ -13,6 +9,7 @@ use nom::{
 use crate::{
     core::{atom, base64},
     decode::IMAPResult,
+    quirks::crlf,
 };
 
 // ----- Unsorted IMAP parsers -----
@@ -10,6 +10,7 @@ use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
     combinator::{map, opt},
+    error::context,
     multi::{many0, many1, separated_list0, separated_list1},
     sequence::{delimited, preceded, tuple},
 };
@@ -18,6 +19,7 @@ use crate::{
     core::{nil, nstring, number, string},
     decode::{IMAPErrorKind, IMAPParseError, IMAPResult},
     envelope::envelope,
+    quirks,
 };
 
 /// `body = "(" (body-type-1part / body-type-mpart) ")"`
@@ -34,6 +36,7 @@ fn body_limited(input: &[u8], remaining_recursions: usize) -> IMAPResult<&[u8],
     if remaining_recursions == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
@@ -43,10 +46,13 @@ fn body_limited(input: &[u8], remaining_recursions: usize) -> IMAPResult<&[u8],
     let body_type_mpart =
         |input| body_type_mpart_limited(input, remaining_recursions.saturating_sub(1));
 
-    delimited(
-        tag(b"("),
-        alt((body_type_1part, body_type_mpart)),
-        tag(b")"),
+    context(
+        "body",
+        delimited(
+            tag(b"("),
+            alt((body_type_1part, body_type_mpart)),
+            tag(b")"),
+        ),
     )(input)
 }
 
@@ -66,6 +72,7 @@ fn body_type_1part_limited(
     if remaining_recursions == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
@@ -123,6 +130,7 @@ fn body_type_msg_limited(
     if remaining_recursions == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
@@ -275,21 +283,18 @@ pub(crate) fn body_fld_enc(input: &[u8]) -> IMAPResult<&[u8], IString> {
 /// The following erroneous messages were observed:
 ///
 /// * A negative number, specifically `-1`, in Dovecot.
-#[allow(clippy::needless_return)]
 pub(crate) fn body_fld_octets(input: &[u8]) -> IMAPResult<&[u8], u32> {
-    #[cfg(not(feature = "quirk_rectify_numbers"))]
-    return number(input);
-
-    #[cfg(feature = "quirk_rectify_numbers")]
-    {
-        return alt((
-            number,
-            map(tuple((tag("-"), number)), |(_, _)| {
-                log::warn!("Rectified negative number to 0");
-                0
-            }),
-        ))(input);
+    if !quirks::current().rectify_numbers {
+        return number(input);
     }
+
+    alt((
+        number,
+        map(tuple((tag("-"), number)), |(_, _)| {
+            log::warn!("Rectified negative number to 0");
+            0
+        }),
+    ))(input)
 }
 
 #[inline]
@@ -407,6 +412,7 @@ fn body_extension_limited(
     if remaining_recursion == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
@@ -437,6 +443,7 @@ fn body_type_mpart_limited(
     if remaining_recursion == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
@@ -581,7 +588,10 @@ mod tests {
     };
 
     use super::*;
-    use crate::testing::{kat_inverse_response, known_answer_test_encode};
+    use crate::{
+        testing::{kat_inverse_response, known_answer_test_encode},
+        Quirks,
+    };
 
     #[test]
     fn test_parse_media_basic() {
@@ -763,18 +773,16 @@ mod tests {
         assert_eq!(body_fld_octets(b"0)").unwrap().1, 0);
         assert_eq!(body_fld_octets(b"1)").unwrap().1, 1);
 
-        #[cfg(not(feature = "quirk_rectify_numbers"))]
-        {
+        quirks::with(Quirks::none(), || {
             assert!(dbg!(body_fld_octets(b"-0)")).is_err());
             assert!(body_fld_octets(b"-1)").is_err());
             assert!(body_fld_octets(b"-999999)").is_err());
-        }
+        });
 
-        #[cfg(feature = "quirk_rectify_numbers")]
-        {
+        quirks::with(Quirks::all(), || {
             assert_eq!(body_fld_octets(b"-0)").unwrap().1, 0);
             assert_eq!(body_fld_octets(b"-1)").unwrap().1, 0);
             assert_eq!(body_fld_octets(b"-999999)").unwrap().1, 0);
-        }
+        });
     }
 }
@@ -1,6 +1,8 @@
 pub mod decode;
 pub mod encode;
 
+use crate::{encode::LiteralPolicy, Quirks};
+
 /// Codec for greetings.
 #[derive(Clone, Debug, Default, PartialEq)]
 // We use `#[non_exhaustive]` to prevent users from using struct literal syntax.
@@ -8,27 +10,39 @@ pub mod encode;
 // This allows to add configuration options later. For example, the
 // codec could transparently replace all literals with non-sync literals.
 #[non_exhaustive]
-pub struct GreetingCodec;
+pub struct GreetingCodec {
+    quirks: Quirks,
+}
 
 /// Codec for commands.
 #[derive(Clone, Debug, Default, PartialEq)]
 #[non_exhaustive]
-pub struct CommandCodec;
+pub struct CommandCodec {
+    quirks: Quirks,
+    literal_policy: LiteralPolicy,
+}
 
 /// Codec for authenticate data lines.
 #[derive(Clone, Debug, Default, PartialEq)]
 #[non_exhaustive]
-pub struct AuthenticateDataCodec;
+pub struct AuthenticateDataCodec {
+    quirks: Quirks,
+}
 
 /// Codec for responses.
 #[derive(Clone, Debug, Default, PartialEq)]
 #[non_exhaustive]
-pub struct ResponseCodec;
+pub struct ResponseCodec {
+    quirks: Quirks,
+    lenient: bool,
+}
 
 /// Codec for idle dones.
 #[derive(Clone, Debug, Default, PartialEq)]
 #[non_exhaustive]
-pub struct IdleDoneCodec;
+pub struct IdleDoneCodec {
+    quirks: Quirks,
+}
 
 macro_rules! impl_codec_new {
     ($codec:ty) => {
@@ -37,6 +51,17 @@ macro_rules! impl_codec_new {
             pub fn new() -> Self {
                 Self::default()
             }
+
+            /// Use the given quirks for decoding.
+            pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+                self.quirks = quirks;
+                self
+            }
+
+            /// Returns the quirks used for decoding.
+            pub fn quirks(&self) -> Quirks {
+                self.quirks
+            }
         }
     };
 }
@@ -47,6 +72,41 @@ impl_codec_new!(AuthenticateDataCodec);
 impl_codec_new!(ResponseCodec);
 impl_codec_new!(IdleDoneCodec);
 
+impl CommandCodec {
+    /// Use the given literal policy for encoding.
+    ///
+    /// See [`LiteralPolicy::from_capabilities`] to derive a policy from the server's capabilities.
+    pub fn with_literal_policy(mut self, literal_policy: LiteralPolicy) -> Self {
+        self.literal_policy = literal_policy;
+        self
+    }
+
+    /// Returns the literal policy used for encoding.
+    pub fn literal_policy(&self) -> LiteralPolicy {
+        self.literal_policy
+    }
+}
+
+impl ResponseCodec {
+    /// Decode unknown or malformed untagged responses as [`Response::Unparsed`] instead of failing.
+    ///
+    /// The raw response (including all literals) is kept, e.g., for logging. A malformed tagged
+    /// response is recovered as status with `tag SP ("OK" / "NO" / "BAD")`, so that the command
+    /// is still completed. The rest of the line is kept as text (or substituted with `...` when
+    /// it isn't valid text). Other tagged responses still fail.
+    ///
+    /// [`Response::Unparsed`]: imap_types::response::Response::Unparsed
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Returns whether unknown or malformed untagged responses are decoded as `Response::Unparsed`.
+    pub fn lenient(&self) -> bool {
+        self.lenient
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroU32;
@@ -63,7 +123,10 @@ mod tests {
 
     use super::*;
     use crate::{
-        decode::{CommandDecodeError, Decoder, GreetingDecodeError, ResponseDecodeError},
+        decode::{
+            CommandDecodeError, DecodeFailure, DecodeFailureKind, Decoder, GreetingDecodeError,
+            ResponseDecodeError,
+        },
         testing::{
             kat_inverse_authenticate_data, kat_inverse_command, kat_inverse_done,
             kat_inverse_greeting, kat_inverse_response,
@@ -197,8 +260,22 @@ mod tests {
             (b"* OK .".as_ref(), Err(GreetingDecodeError::Incomplete)),
             (b"* OK .\r".as_ref(), Err(GreetingDecodeError::Incomplete)),
             // Failed
-            (b"**".as_ref(), Err(GreetingDecodeError::Failed)),
-            (b"* NO x\r\n".as_ref(), Err(GreetingDecodeError::Failed)),
+            (
+                b"**".as_ref(),
+                Err(GreetingDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: Some("greeting"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"* NO x\r\n".as_ref(),
+                Err(GreetingDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("greeting"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
         ];
 
         for (test, expected) in tests {
@@ -247,8 +324,22 @@ mod tests {
                 Err(CommandDecodeError::Incomplete),
             ),
             // Failed
-            (b"* noop\r\n".as_ref(), Err(CommandDecodeError::Failed)),
-            (b"A  noop\r\n".as_ref(), Err(CommandDecodeError::Failed)),
+            (
+                b"* noop\r\n".as_ref(),
+                Err(CommandDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: Some("tag"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"A  noop\r\n".as_ref(),
+                Err(CommandDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("command"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
         ];
 
         for (test, expected) in tests {
@@ -290,9 +381,20 @@ mod tests {
             // Failed
             (
                 b"*  search 1 2 3\r\n".as_ref(),
-                Err(ResponseDecodeError::Failed),
+                Err(ResponseDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("response-data"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"A search\r\n".as_ref(),
+                Err(ResponseDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("response-tagged"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
             ),
-            (b"A search\r\n".as_ref(), Err(ResponseDecodeError::Failed)),
         ];
 
         for (test, expected) in tests {
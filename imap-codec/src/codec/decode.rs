@@ -19,13 +19,17 @@ use imap_types::{
     response::{Greeting, Response},
     IntoStatic,
 };
-use nom::error::{ErrorKind, FromExternalError, ParseError};
+use nom::{
+    error::{ContextError, ErrorKind, FromExternalError, ParseError},
+    InputLength,
+};
 
 use crate::{
     auth::authenticate_data,
     command::command,
     extensions::idle::idle_done,
-    response::{greeting, response},
+    quirks,
+    response::{greeting, response, response_lenient},
     AuthenticateDataCodec, CommandCodec, GreetingCodec, IdleDoneCodec, ResponseCodec,
 };
 
@@ -35,9 +39,10 @@ pub(crate) type IMAPResult<'a, I, O> = Result<(I, O), nom::Err<IMAPParseError<'a
 /// An extended version of [`nom::error::Error`].
 #[derive(Debug)]
 pub(crate) struct IMAPParseError<'a, I> {
-    #[allow(unused)]
     pub input: I,
     pub kind: IMAPErrorKind<'a>,
+    /// The innermost (named) ABNF rule that failed.
+    pub rule: Option<&'static str>,
 }
 
 /// An extended version of [`nom::error::ErrorKind`].
@@ -56,18 +61,54 @@ pub(crate) enum IMAPErrorKind<'a> {
     Nom(#[allow(dead_code)] ErrorKind),
 }
 
-impl<'a, I> ParseError<I> for IMAPParseError<'a, I> {
+impl<'a, I: InputLength> ParseError<I> for IMAPParseError<'a, I> {
     fn from_error_kind(input: I, kind: ErrorKind) -> Self {
         Self {
             input,
             kind: IMAPErrorKind::Nom(kind),
+            rule: None,
         }
     }
 
-    fn append(input: I, kind: ErrorKind, _: Self) -> Self {
-        Self {
-            input,
-            kind: IMAPErrorKind::Nom(kind),
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        // Keep the innermost error. It points to where the input actually diverged.
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        // Keep the error of the alternative that got furthest (or the first one on a tie).
+        if self.input.input_len() <= other.input.input_len() {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+impl<'a, I> ContextError<I> for IMAPParseError<'a, I> {
+    fn add_context(_: I, rule: &'static str, mut other: Self) -> Self {
+        // Keep the innermost rule.
+        other.rule.get_or_insert(rule);
+        other
+    }
+}
+
+impl<'a> IMAPParseError<'a, &[u8]> {
+    /// Converts the error into the public [`DecodeFailure`].
+    ///
+    /// `input` is the complete input passed to the decoder.
+    pub(crate) fn failure(&self, input: &[u8]) -> DecodeFailure {
+        DecodeFailure {
+            offset: input.len().saturating_sub(self.input.len()),
+            rule: self.rule,
+            kind: match self.kind {
+                IMAPErrorKind::BadNumber => DecodeFailureKind::BadNumber,
+                IMAPErrorKind::BadBase64 => DecodeFailureKind::BadBase64,
+                IMAPErrorKind::BadDateTime => DecodeFailureKind::BadDateTime,
+                IMAPErrorKind::LiteralContainsNull => DecodeFailureKind::LiteralContainsNull,
+                IMAPErrorKind::RecursionLimitExceeded => DecodeFailureKind::RecursionLimitExceeded,
+                IMAPErrorKind::Literal { .. } | IMAPErrorKind::Nom(_) => DecodeFailureKind::Syntax,
+            },
         }
     }
 }
@@ -77,6 +118,7 @@ impl<'a, I> FromExternalError<I, ParseIntError> for IMAPParseError<'a, I> {
         Self {
             input,
             kind: IMAPErrorKind::BadNumber,
+            rule: None,
         }
     }
 }
@@ -86,6 +128,7 @@ impl<'a, I> FromExternalError<I, TryFromIntError> for IMAPParseError<'a, I> {
         Self {
             input,
             kind: IMAPErrorKind::BadNumber,
+            rule: None,
         }
     }
 }
@@ -95,6 +138,7 @@ impl<'a, I> FromExternalError<I, base64::DecodeError> for IMAPParseError<'a, I>
         Self {
             input,
             kind: IMAPErrorKind::BadBase64,
+            rule: None,
         }
     }
 }
@@ -122,6 +166,38 @@ pub trait Decoder {
     }
 }
 
+/// Details about a failed decoding.
+///
+/// Note: The details are best effort. When the decoder tried alternatives, e.g., different
+/// commands, the details describe the alternative that got furthest.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct DecodeFailure {
+    /// Byte offset (relative to the decoded input) where the decoding failed.
+    pub offset: usize,
+    /// The innermost ABNF rule that failed (if known), e.g., `"date-time"`.
+    pub rule: Option<&'static str>,
+    /// The kind of the failure.
+    pub kind: DecodeFailureKind,
+}
+
+/// Kind of a [`DecodeFailure`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum DecodeFailureKind {
+    /// The input doesn't match the grammar.
+    Syntax,
+    /// A number is invalid, e.g., too large.
+    BadNumber,
+    /// A base64 string is invalid.
+    BadBase64,
+    /// A date-time is invalid, e.g., `31-Feb-2024`.
+    BadDateTime,
+    /// A literal contains a NUL byte.
+    LiteralContainsNull,
+    /// The nesting of, e.g., body structures is too deep.
+    RecursionLimitExceeded,
+}
+
 /// Error during greeting decoding.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum GreetingDecodeError {
@@ -129,7 +205,7 @@ pub enum GreetingDecodeError {
     Incomplete,
 
     /// Decoding failed.
-    Failed,
+    Failed(DecodeFailure),
 }
 
 impl IntoStatic for GreetingDecodeError {
@@ -196,7 +272,7 @@ pub enum CommandDecodeError<'a> {
     },
 
     /// Decoding failed.
-    Failed,
+    Failed(DecodeFailure),
 }
 
 impl<'a> IntoStatic for CommandDecodeError<'a> {
@@ -212,7 +288,7 @@ impl<'a> IntoStatic for CommandDecodeError<'a> {
                     mode,
                 }
             }
-            CommandDecodeError::Failed => CommandDecodeError::Failed,
+            CommandDecodeError::Failed(failure) => CommandDecodeError::Failed(failure),
         }
     }
 }
@@ -224,7 +300,7 @@ pub enum AuthenticateDataDecodeError {
     Incomplete,
 
     /// Decoding failed.
-    Failed,
+    Failed(DecodeFailure),
 }
 
 impl IntoStatic for AuthenticateDataDecodeError {
@@ -255,7 +331,7 @@ pub enum ResponseDecodeError {
     },
 
     /// Decoding failed.
-    Failed,
+    Failed(DecodeFailure),
 }
 
 impl IntoStatic for ResponseDecodeError {
@@ -273,7 +349,7 @@ pub enum IdleDoneDecodeError {
     Incomplete,
 
     /// Decoding failed.
-    Failed,
+    Failed(DecodeFailure),
 }
 
 impl IntoStatic for IdleDoneDecodeError {
@@ -294,10 +370,12 @@ impl Decoder for GreetingCodec {
         &self,
         input: &'a [u8],
     ) -> Result<(&'a [u8], Self::Message<'a>), Self::Error<'static>> {
-        match greeting(input) {
+        match quirks::with(self.quirks(), || greeting(input)) {
             Ok((rem, grt)) => Ok((rem, grt)),
             Err(nom::Err::Incomplete(_)) => Err(GreetingDecodeError::Incomplete),
-            Err(nom::Err::Failure(_)) | Err(nom::Err::Error(_)) => Err(GreetingDecodeError::Failed),
+            Err(nom::Err::Failure(error)) | Err(nom::Err::Error(error)) => {
+                Err(GreetingDecodeError::Failed(error.failure(input)))
+            }
         }
     }
 }
@@ -310,22 +388,22 @@ impl Decoder for CommandCodec {
         &self,
         input: &'a [u8],
     ) -> Result<(&'a [u8], Self::Message<'a>), Self::Error<'a>> {
-        match command(input) {
+        match quirks::with(self.quirks(), || command(input)) {
             Ok((rem, cmd)) => Ok((rem, cmd)),
             Err(nom::Err::Incomplete(_)) => Err(CommandDecodeError::Incomplete),
             Err(nom::Err::Failure(error)) => match error {
                 IMAPParseError {
-                    input: _,
                     kind: IMAPErrorKind::Literal { tag, length, mode },
+                    ..
                 } => Err(CommandDecodeError::LiteralFound {
                     // Unwrap: We *must* receive a `tag` during command parsing.
                     tag: tag.expect("Expected `Some(tag)` in `IMAPErrorKind::Literal`, got `None`"),
                     length,
                     mode,
                 }),
-                _ => Err(CommandDecodeError::Failed),
+                error => Err(CommandDecodeError::Failed(error.failure(input))),
             },
-            Err(nom::Err::Error(_)) => Err(CommandDecodeError::Failed),
+            Err(nom::Err::Error(error)) => Err(CommandDecodeError::Failed(error.failure(input))),
         }
     }
 }
@@ -338,7 +416,7 @@ impl Decoder for ResponseCodec {
         &self,
         input: &'a [u8],
     ) -> Result<(&'a [u8], Self::Message<'a>), Self::Error<'static>> {
-        match response(input) {
+        match quirks::with(self.quirks(), || response(input)) {
             Ok((rem, rsp)) => Ok((rem, rsp)),
             Err(nom::Err::Incomplete(_)) => Err(ResponseDecodeError::Incomplete),
             Err(nom::Err::Error(error) | nom::Err::Failure(error)) => match error {
@@ -346,7 +424,14 @@ impl Decoder for ResponseCodec {
                     kind: IMAPErrorKind::Literal { length, .. },
                     ..
                 } => Err(ResponseDecodeError::LiteralFound { length }),
-                _ => Err(ResponseDecodeError::Failed),
+                error if self.lenient() => {
+                    match quirks::with(self.quirks(), || response_lenient(input)) {
+                        Ok((rem, rsp)) => Ok((rem, rsp)),
+                        Err(nom::Err::Incomplete(_)) => Err(ResponseDecodeError::Incomplete),
+                        Err(_) => Err(ResponseDecodeError::Failed(error.failure(input))),
+                    }
+                }
+                error => Err(ResponseDecodeError::Failed(error.failure(input))),
             },
         }
     }
@@ -360,11 +445,11 @@ impl Decoder for AuthenticateDataCodec {
         &self,
         input: &'a [u8],
     ) -> Result<(&'a [u8], Self::Message<'a>), Self::Error<'static>> {
-        match authenticate_data(input) {
+        match quirks::with(self.quirks(), || authenticate_data(input)) {
             Ok((rem, rsp)) => Ok((rem, rsp)),
             Err(nom::Err::Incomplete(_)) => Err(AuthenticateDataDecodeError::Incomplete),
-            Err(nom::Err::Failure(_)) | Err(nom::Err::Error(_)) => {
-                Err(AuthenticateDataDecodeError::Failed)
+            Err(nom::Err::Failure(error)) | Err(nom::Err::Error(error)) => {
+                Err(AuthenticateDataDecodeError::Failed(error.failure(input)))
             }
         }
     }
@@ -378,10 +463,12 @@ impl Decoder for IdleDoneCodec {
         &self,
         input: &'a [u8],
     ) -> Result<(&'a [u8], Self::Message<'a>), Self::Error<'static>> {
-        match idle_done(input) {
+        match quirks::with(self.quirks(), || idle_done(input)) {
             Ok((rem, rsp)) => Ok((rem, rsp)),
             Err(nom::Err::Incomplete(_)) => Err(IdleDoneDecodeError::Incomplete),
-            Err(nom::Err::Failure(_)) | Err(nom::Err::Error(_)) => Err(IdleDoneDecodeError::Failed),
+            Err(nom::Err::Failure(error)) | Err(nom::Err::Error(error)) => {
+                Err(IdleDoneDecodeError::Failed(error.failure(input)))
+            }
         }
     }
 }
@@ -392,14 +479,18 @@ mod tests {
 
     use imap_types::{
         command::{Command, CommandBody},
-        core::{IString, Literal, NString, Vec1},
+        core::{IString, Literal, NString, Tag, Text, Vec1},
         extensions::idle::IdleDone,
         fetch::MessageDataItem,
         mailbox::Mailbox,
-        response::{Data, Greeting, GreetingKind, Response},
+        response::{
+            Data, Greeting, GreetingKind, Response, Status, StatusBody, StatusKind, Tagged,
+            Unparsed,
+        },
     };
 
     use super::*;
+    use crate::Quirks;
 
     #[test]
     fn test_decode_greeting() {
@@ -435,8 +526,22 @@ mod tests {
             (b"* OK .".as_ref(), Err(GreetingDecodeError::Incomplete)),
             (b"* OK .\r".as_ref(), Err(GreetingDecodeError::Incomplete)),
             // Failed
-            (b"**".as_ref(), Err(GreetingDecodeError::Failed)),
-            (b"* NO x\r\n".as_ref(), Err(GreetingDecodeError::Failed)),
+            (
+                b"**".as_ref(),
+                Err(GreetingDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: Some("greeting"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"* NO x\r\n".as_ref(),
+                Err(GreetingDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("greeting"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
         ];
 
         for (test, expected) in tests {
@@ -515,8 +620,22 @@ mod tests {
                 Err(CommandDecodeError::Incomplete),
             ),
             // Failed
-            (b"* noop\r\n".as_ref(), Err(CommandDecodeError::Failed)),
-            (b"A  noop\r\n".as_ref(), Err(CommandDecodeError::Failed)),
+            (
+                b"* noop\r\n".as_ref(),
+                Err(CommandDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: Some("tag"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"A  noop\r\n".as_ref(),
+                Err(CommandDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("command"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
         ];
 
         for (test, expected) in tests {
@@ -592,19 +711,35 @@ mod tests {
             // Failed
             (
                 b"VGVzdA== \r\n".as_ref(),
-                Err(AuthenticateDataDecodeError::Failed),
+                Err(AuthenticateDataDecodeError::Failed(DecodeFailure {
+                    offset: 8,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
             ),
             (
                 b" VGVzdA== \r\n".as_ref(),
-                Err(AuthenticateDataDecodeError::Failed),
+                Err(AuthenticateDataDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
             ),
             (
                 b" V GVzdA== \r\n".as_ref(),
-                Err(AuthenticateDataDecodeError::Failed),
+                Err(AuthenticateDataDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
             ),
             (
                 b" V GVzdA= \r\n".as_ref(),
-                Err(AuthenticateDataDecodeError::Failed),
+                Err(AuthenticateDataDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
             ),
         ];
 
@@ -633,10 +768,38 @@ mod tests {
             (b"done".as_ref(), Err(IdleDoneDecodeError::Incomplete)),
             (b"done\r".as_ref(), Err(IdleDoneDecodeError::Incomplete)),
             // Failed
-            (b"donee\r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
-            (b" done\r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
-            (b"done \r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
-            (b" done \r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
+            (
+                b"donee\r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 4,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b" done\r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"done \r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 4,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b" done \r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
         ];
 
         for (test, expected) in tests {
@@ -704,9 +867,20 @@ mod tests {
             // Failed
             (
                 b"*  search 1 2 3\r\n".as_ref(),
-                Err(ResponseDecodeError::Failed),
+                Err(ResponseDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("response-data"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"A search\r\n".as_ref(),
+                Err(ResponseDecodeError::Failed(DecodeFailure {
+                    offset: 2,
+                    rule: Some("response-tagged"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
             ),
-            (b"A search\r\n".as_ref(), Err(ResponseDecodeError::Failed)),
         ];
 
         for (test, expected) in tests {
@@ -720,4 +894,177 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_decode_response_lenient() {
+        let codec = ResponseCodec::new().with_lenient(true);
+        assert!(codec.lenient());
+
+        let tests = [
+            (
+                b"* XVENDOR foo\r\n?".as_ref(),
+                Ok((
+                    b"?".as_ref(),
+                    Response::Unparsed(Unparsed::unvalidated(b"* XVENDOR foo\r\n".as_ref())),
+                )),
+            ),
+            (
+                b"* XVENDOR {3}\r\na\r\n (x {2+}\r\nbc)\r\n".as_ref(),
+                Ok((
+                    b"".as_ref(),
+                    Response::Unparsed(Unparsed::unvalidated(
+                        b"* XVENDOR {3}\r\na\r\n (x {2+}\r\nbc)\r\n".as_ref(),
+                    )),
+                )),
+            ),
+            // Tagged responses are recovered as status (to complete the command).
+            (
+                b"A1 OK [ALERT\r\n".as_ref(),
+                Ok((
+                    b"".as_ref(),
+                    Response::Status(Status::Tagged(Tagged {
+                        tag: Tag::try_from("A1").unwrap(),
+                        body: StatusBody {
+                            kind: StatusKind::Ok,
+                            code: None,
+                            text: Text::try_from("[ALERT").unwrap(),
+                        },
+                    })),
+                )),
+            ),
+            // The text is substituted when missing or invalid.
+            (
+                b"A2 no\r\n".as_ref(),
+                Ok((
+                    b"".as_ref(),
+                    Response::Status(Status::Tagged(Tagged {
+                        tag: Tag::try_from("A2").unwrap(),
+                        body: StatusBody {
+                            kind: StatusKind::No,
+                            code: None,
+                            text: Text::try_from("...").unwrap(),
+                        },
+                    })),
+                )),
+            ),
+            (
+                b"A3 BAD [X] \xff\r\n".as_ref(),
+                Ok((
+                    b"".as_ref(),
+                    Response::Status(Status::Tagged(Tagged {
+                        tag: Tag::try_from("A3").unwrap(),
+                        body: StatusBody {
+                            kind: StatusKind::Bad,
+                            code: None,
+                            text: Text::try_from("...").unwrap(),
+                        },
+                    })),
+                )),
+            ),
+            // Parsable responses are not affected.
+            (
+                b"* 1 EXISTS\r\n".as_ref(),
+                Ok((b"".as_ref(), Response::Data(Data::Exists(1)))),
+            ),
+            // Incomplete
+            (b"* XVENDOR".as_ref(), Err(ResponseDecodeError::Incomplete)),
+            (
+                b"* XVENDOR {3}\r\nab".as_ref(),
+                Err(ResponseDecodeError::Incomplete),
+            ),
+            // Failed
+            (
+                b"+\r\n".as_ref(),
+                Err(ResponseDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: Some("continue-req"),
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let got = codec.decode(test);
+            dbg!((String::from_utf8_lossy(test), &expected, &got));
+            assert_eq!(expected, got);
+        }
+
+        assert!(ResponseCodec::new().decode(b"* XVENDOR foo\r\n").is_err());
+
+        // Other tagged responses are not recovered.
+        assert!(matches!(
+            codec.decode(b"A1 XVENDOR {3}\r\nabc\r\n"),
+            Err(ResponseDecodeError::Failed(_))
+        ));
+        assert!(matches!(
+            codec.decode(b"A1 OKAY\r\n"),
+            Err(ResponseDecodeError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_with_quirks() {
+        let strict = ResponseCodec::new().with_quirks(Quirks::none());
+        let lenient = ResponseCodec::new().with_quirks(Quirks::all());
+
+        for input in [
+            b"* STATUS INBOX (MESSAGES 1) \r\n".as_ref(),
+            b"* OK [ALERT]\r\n",
+            b"* 1 FETCH (BODYSTRUCTURE (\"TEXT\" \"PLAIN\" NIL NIL NIL \"7BIT\" -1 1))\r\n",
+            b"* 1 FETCH (ENVELOPE (NIL NIL ( (NIL NIL \"a\" \"b\")) NIL NIL NIL NIL NIL NIL NIL))\r\n",
+            b"* SEARCH 1\n",
+        ] {
+            assert!(strict.decode(input).is_err());
+            assert!(lenient.decode(input).is_ok());
+        }
+
+        let mut quirks = Quirks::none();
+        quirks.crlf_relaxed = true;
+        let codec = CommandCodec::new().with_quirks(quirks);
+        assert_eq!(codec.quirks(), quirks);
+        assert_eq!(
+            codec.decode(b"A1 NOOP\n"),
+            Ok((b"".as_ref(), Command::new("A1", CommandBody::Noop).unwrap()))
+        );
+        assert!(CommandCodec::new()
+            .with_quirks(Quirks::none())
+            .decode(b"A1 NOOP\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_decode_failure() {
+        let tests = [
+            (
+                b"A1 APPEND INBOX \"31-Feb-2024 00:00:00 +0000\" {1+}\r\nx\r\n".as_ref(),
+                DecodeFailure {
+                    offset: 16,
+                    rule: Some("date-time"),
+                    kind: DecodeFailureKind::BadDateTime,
+                },
+            ),
+            (
+                b"A1 FETCH 99999999999 FLAGS\r\n",
+                DecodeFailure {
+                    offset: 9,
+                    rule: Some("number"),
+                    kind: DecodeFailureKind::BadNumber,
+                },
+            ),
+            (
+                b"A1 SELECT INBOX x\r\n",
+                DecodeFailure {
+                    offset: 15,
+                    rule: Some("command"),
+                    kind: DecodeFailureKind::Syntax,
+                },
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let got = CommandCodec::default().decode(test);
+            dbg!((std::str::from_utf8(test).unwrap(), &expected, &got));
+            assert_eq!(got, Err(CommandDecodeError::Failed(expected)));
+        }
+    }
 }
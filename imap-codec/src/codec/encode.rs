@@ -3,6 +3,9 @@
 //! To facilitates handling of literals, [Encoder::encode] returns an instance of [`Encoded`].
 //! The idea is that the encoder not only "dumps" the final serialization of a message but can be iterated over.
 //!
+//! To avoid the intermediate allocations of [`Encoded`], [Encoder::encode_into] and [Encoder::dump_into] write
+//! directly into an [`std::io::Write`], e.g., a reusable `Vec<u8>` or a `BufWriter`.
+//!
 //! # Example
 //!
 //! ```rust
@@ -70,7 +73,7 @@ use imap_types::{
     mailbox::{ListCharString, ListMailbox, Mailbox, MailboxOther},
     response::{
         Bye, Capability, Code, CodeOther, CommandContinuationRequest, Data, Greeting, GreetingKind,
-        Response, Status, StatusBody, StatusKind, Tagged,
+        Response, Status, StatusBody, StatusKind, Tagged, Unparsed,
     },
     search::SearchKey,
     sequence::{SeqOrUid, Sequence, SequenceSet},
@@ -91,6 +94,77 @@ pub trait Encoder {
     ///
     /// This will return an [`Encoded`] message.
     fn encode(&self, message: &Self::Message<'_>) -> Encoded;
+
+    /// Encode this message directly into a writer.
+    ///
+    /// Other than [`Encoder::encode`], this doesn't allocate intermediate [`Fragment`]s. Encoding
+    /// pauses after the announcement of a sync literal and returns [`Progress::Paused`]. After
+    /// receiving a continuation request, call this method again with the returned offset to
+    /// resume. Start with an `offset` of `0`.
+    ///
+    /// Note: Resuming encodes the message again but only writes the bytes after `offset`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use imap_codec::{
+    ///     encode::{Encoder, Progress},
+    ///     imap_types::command::{Command, CommandBody},
+    ///     CommandCodec,
+    /// };
+    ///
+    /// let codec = CommandCodec::default();
+    /// let cmd = Command::new("A", CommandBody::login("alice", "Pa²²W0rD").unwrap()).unwrap();
+    /// let mut out = Vec::new();
+    ///
+    /// let progress = codec.encode_into(&cmd, &mut out, 0).unwrap();
+    /// assert_eq!(progress, Progress::Paused { offset: 20 });
+    /// assert_eq!(out, b"A LOGIN alice {10}\r\n");
+    ///
+    /// // Wait for a continuation request ...
+    ///
+    /// let progress = codec.encode_into(&cmd, &mut out, 20).unwrap();
+    /// assert_eq!(progress, Progress::Done);
+    /// assert_eq!(out, "A LOGIN alice {10}\r\nPa²²W0rD\r\n".as_bytes());
+    /// ```
+    fn encode_into(
+        &self,
+        message: &Self::Message<'_>,
+        writer: &mut dyn Write,
+        offset: usize,
+    ) -> std::io::Result<Progress>;
+
+    /// Encode this message directly into a writer without pausing at literals.
+    ///
+    /// This is the allocation-free counterpart of `encode(message).dump()`.
+    fn dump_into(&self, message: &Self::Message<'_>, writer: &mut dyn Write)
+        -> std::io::Result<()>;
+
+    /// Compute the exact encoded length of this message without encoding it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use imap_codec::{
+    ///     encode::{EncodedLen, Encoder},
+    ///     imap_types::command::{Command, CommandBody},
+    ///     CommandCodec,
+    /// };
+    ///
+    /// let cmd = Command::new("A", CommandBody::login("alice", "Pa²²W0rD").unwrap()).unwrap();
+    ///
+    /// // `A LOGIN alice {10}\r\n` + `Pa²²W0rD` + `\r\n`
+    /// let len = CommandCodec::default().encoded_len(&cmd);
+    /// assert_eq!(
+    ///     len,
+    ///     EncodedLen {
+    ///         lines: 22,
+    ///         literals: 10
+    ///     }
+    /// );
+    /// assert_eq!(len.total(), 32);
+    /// ```
+    fn encoded_len(&self, message: &Self::Message<'_>) -> EncodedLen;
 }
 
 /// An encoded message.
@@ -157,43 +231,303 @@ pub enum Fragment {
     Literal { data: Vec<u8>, mode: LiteralMode },
 }
 
+/// Result of [`Encoder::encode_into`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Progress {
+    /// The message was written completely.
+    Done,
+
+    /// Encoding paused after the announcement of a sync literal.
+    ///
+    /// Wait for a continuation request and call [`Encoder::encode_into`] again with `offset`.
+    Paused { offset: usize },
+}
+
+/// Encoded length of a message as computed by [`Encoder::encoded_len`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EncodedLen {
+    /// Number of bytes in lines, including literal announcements and CRLFs.
+    pub lines: usize,
+    /// Number of bytes in literal data.
+    pub literals: usize,
+}
+
+impl EncodedLen {
+    /// Total number of bytes.
+    pub fn total(&self) -> usize {
+        self.lines + self.literals
+    }
+}
+
+/// Server support for non-synchronizing literals (RFC 7888).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum NonSyncLiterals {
+    /// Not supported, i.e., all literals must be sync literals.
+    Unsupported,
+    /// `LITERAL-`, i.e., non-sync literals up to 4096 bytes are allowed.
+    Minus,
+    /// `LITERAL+`, i.e., all literals can be non-sync literals.
+    Plus,
+}
+
+/// Literal policy of a [`CommandCodec`].
+///
+/// The default policy encodes all strings and literals as given.
+///
+/// # Example
+///
+/// ```rust
+/// use imap_codec::{
+///     encode::{Encoder, LiteralPolicy},
+///     imap_types::{
+///         command::{Command, CommandBody},
+///         core::{AString, Literal},
+///         response::Capability,
+///     },
+///     CommandCodec,
+/// };
+///
+/// let policy =
+///     LiteralPolicy::from_capabilities(&[Capability::Imap4Rev1, Capability::LiteralPlus]);
+/// let codec = CommandCodec::new().with_literal_policy(policy);
+///
+/// let username = AString::from(Literal::try_from("alice").unwrap());
+/// let cmd = Command::new("A", CommandBody::login(username, "Pa²²W0rD").unwrap()).unwrap();
+///
+/// assert_eq!(
+///     codec.encode(&cmd).dump(),
+///     "A LOGIN alice {10+}\r\nPa²²W0rD\r\n".as_bytes()
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LiteralPolicy {
+    non_sync_literals: Option<NonSyncLiterals>,
+    choose_representation: bool,
+}
+
+impl LiteralPolicy {
+    /// Maximum size of a non-sync literal with `LITERAL-`.
+    pub const LITERAL_MINUS_MAX: usize = 4096;
+
+    /// Create a policy derived from the server's capabilities.
+    ///
+    /// Sync literals are turned into non-sync literals as allowed by `LITERAL+` or `LITERAL-`
+    /// (and vice versa), and strings use the cheapest representation.
+    pub fn from_capabilities<'a, 'b: 'a>(
+        capabilities: impl IntoIterator<Item = &'a Capability<'b>>,
+    ) -> Self {
+        let mut non_sync_literals = NonSyncLiterals::Unsupported;
+
+        for capability in capabilities {
+            match capability {
+                Capability::LiteralPlus => non_sync_literals = NonSyncLiterals::Plus,
+                Capability::LiteralMinus if non_sync_literals == NonSyncLiterals::Unsupported => {
+                    non_sync_literals = NonSyncLiterals::Minus
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            non_sync_literals: Some(non_sync_literals),
+            choose_representation: true,
+        }
+    }
+
+    /// Turn sync literals into non-sync literals (and vice versa) as supported by the server.
+    ///
+    /// `None` keeps the mode of all literals.
+    pub fn with_non_sync_literals(mut self, non_sync_literals: Option<NonSyncLiterals>) -> Self {
+        self.non_sync_literals = non_sync_literals;
+        self
+    }
+
+    /// Returns the server support for non-sync literals (if any).
+    pub fn non_sync_literals(&self) -> Option<NonSyncLiterals> {
+        self.non_sync_literals
+    }
+
+    /// Encode [`AString`]s and [`IString`]s as atom, quoted, or literal, whatever is the cheapest.
+    pub fn with_choose_representation(mut self, choose_representation: bool) -> Self {
+        self.choose_representation = choose_representation;
+        self
+    }
+
+    /// Returns whether the cheapest representation of [`AString`]s and [`IString`]s is used.
+    pub fn choose_representation(&self) -> bool {
+        self.choose_representation
+    }
+
+    /// Returns the mode to use for a literal of the given length.
+    pub(crate) fn literal_mode(&self, len: usize, mode: LiteralMode) -> LiteralMode {
+        match self.non_sync_literals {
+            None => mode,
+            Some(NonSyncLiterals::Plus) => LiteralMode::NonSync,
+            Some(NonSyncLiterals::Minus) if len <= Self::LITERAL_MINUS_MAX => LiteralMode::NonSync,
+            Some(_) => LiteralMode::Sync,
+        }
+    }
+}
+
 //--------------------------------------------------------------------------------------------------
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub(crate) struct EncodeContext {
-    accumulator: Vec<u8>,
-    items: VecDeque<Fragment>,
+pub(crate) struct EncodeContext<'w> {
+    sink: Sink<'w>,
+    literal_policy: LiteralPolicy,
+}
+
+enum Sink<'w> {
+    /// Collect [`Fragment`]s (used by [`Encoder::encode`]).
+    Fragments {
+        accumulator: Vec<u8>,
+        items: VecDeque<Fragment>,
+    },
+    /// Write directly into a writer (used by [`Encoder::encode_into`] and [`Encoder::dump_into`]).
+    Writer {
+        writer: &'w mut dyn Write,
+        /// Number of bytes already written by a previous call.
+        skip: usize,
+        /// Number of bytes encoded so far.
+        position: usize,
+        /// Whether to pause after a sync literal announcement.
+        pause: bool,
+        paused: bool,
+    },
+    /// Only count bytes (used by [`Encoder::encoded_len`]).
+    Length { len: EncodedLen, in_literal: bool },
+}
+
+impl Default for EncodeContext<'_> {
+    fn default() -> Self {
+        Self {
+            sink: Sink::Fragments {
+                accumulator: Vec::new(),
+                items: VecDeque::new(),
+            },
+            literal_policy: LiteralPolicy::default(),
+        }
+    }
 }
 
-impl EncodeContext {
+impl<'w> EncodeContext<'w> {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn push_line(&mut self) {
-        self.items.push_back(Fragment::Line {
-            data: std::mem::take(&mut self.accumulator),
-        })
+    pub fn with_writer(writer: &'w mut dyn Write, skip: usize, pause: bool) -> Self {
+        Self {
+            sink: Sink::Writer {
+                writer,
+                skip,
+                position: 0,
+                pause,
+                paused: false,
+            },
+            literal_policy: LiteralPolicy::default(),
+        }
+    }
+
+    pub fn with_length() -> Self {
+        Self {
+            sink: Sink::Length {
+                len: EncodedLen::default(),
+                in_literal: false,
+            },
+            literal_policy: LiteralPolicy::default(),
+        }
+    }
+
+    pub fn with_literal_policy(mut self, literal_policy: LiteralPolicy) -> Self {
+        self.literal_policy = literal_policy;
+        self
+    }
+
+    pub fn literal_policy(&self) -> LiteralPolicy {
+        self.literal_policy
+    }
+
+    /// Ends the line announcing a literal.
+    ///
+    /// When writing directly and pausing is requested, this stops the encoding after the
+    /// announcement of a sync literal by returning an error. Use [`EncodeContext::paused`] to
+    /// tell it apart from an error of the writer.
+    pub fn push_announcement(&mut self, mode: LiteralMode) -> std::io::Result<()> {
+        match &mut self.sink {
+            Sink::Fragments { accumulator, items } => {
+                items.push_back(Fragment::Line {
+                    data: std::mem::take(accumulator),
+                });
+
+                Ok(())
+            }
+            Sink::Writer {
+                skip,
+                position,
+                pause,
+                paused,
+                ..
+            } => {
+                // Note: The announcement was written by this call when it ends after `skip`.
+                if *pause && mode == LiteralMode::Sync && *position > *skip {
+                    *paused = true;
+                    return Err(std::io::ErrorKind::Other.into());
+                }
+
+                Ok(())
+            }
+            Sink::Length { in_literal, .. } => {
+                *in_literal = true;
+
+                Ok(())
+            }
+        }
     }
 
     pub fn push_literal(&mut self, mode: LiteralMode) {
-        self.items.push_back(Fragment::Literal {
-            data: std::mem::take(&mut self.accumulator),
-            mode,
-        })
+        match &mut self.sink {
+            Sink::Fragments { accumulator, items } => items.push_back(Fragment::Literal {
+                data: std::mem::take(accumulator),
+                mode,
+            }),
+            Sink::Writer { .. } => {}
+            Sink::Length { in_literal, .. } => *in_literal = false,
+        }
+    }
+
+    /// Returns the number of encoded bytes if the encoding was paused.
+    pub fn paused(&self) -> Option<usize> {
+        match self.sink {
+            Sink::Writer {
+                position,
+                paused: true,
+                ..
+            } => Some(position),
+            _ => None,
+        }
     }
 
     pub fn into_items(self) -> VecDeque<Fragment> {
-        let Self {
-            accumulator,
-            mut items,
-        } = self;
+        match self.sink {
+            Sink::Fragments {
+                accumulator,
+                mut items,
+            } => {
+                if !accumulator.is_empty() {
+                    items.push_back(Fragment::Line { data: accumulator });
+                }
 
-        if !accumulator.is_empty() {
-            items.push_back(Fragment::Line { data: accumulator });
+                items
+            }
+            Sink::Writer { .. } | Sink::Length { .. } => VecDeque::new(),
         }
+    }
 
-        items
+    /// Returns the counted length (or a default when not counting).
+    pub fn len(&self) -> EncodedLen {
+        match self.sink {
+            Sink::Length { len, .. } => len,
+            _ => EncodedLen::default(),
+        }
     }
 
     #[cfg(test)]
@@ -212,36 +546,102 @@ impl EncodeContext {
     }
 }
 
-impl Write for EncodeContext {
+impl Write for EncodeContext<'_> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.accumulator.extend_from_slice(buf);
+        match &mut self.sink {
+            Sink::Fragments { accumulator, .. } => accumulator.extend_from_slice(buf),
+            Sink::Writer {
+                writer,
+                skip,
+                position,
+                ..
+            } => {
+                let start = *position;
+                *position += buf.len();
+
+                if *position > *skip {
+                    writer.write_all(&buf[skip.saturating_sub(start)..])?;
+                }
+            }
+            Sink::Length { len, in_literal } => {
+                if *in_literal {
+                    len.literals += buf.len();
+                } else {
+                    len.lines += buf.len();
+                }
+            }
+        }
+
         Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        Ok(())
+        match &mut self.sink {
+            Sink::Fragments { .. } | Sink::Length { .. } => Ok(()),
+            Sink::Writer { writer, .. } => writer.flush(),
+        }
     }
 }
 
 macro_rules! impl_encoder_for_codec {
     ($codec:ty, $message:ty) => {
+        impl_encoder_for_codec!($codec, $message, |_| LiteralPolicy::default());
+    };
+    ($codec:ty, $message:ty, $literal_policy:expr) => {
         impl Encoder for $codec {
             type Message<'a> = $message;
 
             fn encode(&self, message: &Self::Message<'_>) -> Encoded {
-                let mut encode_context = EncodeContext::new();
+                let mut encode_context =
+                    EncodeContext::new().with_literal_policy(($literal_policy)(self));
                 EncodeIntoContext::encode_ctx(message.borrow(), &mut encode_context).unwrap();
 
                 Encoded {
                     items: encode_context.into_items(),
                 }
             }
+
+            fn encode_into(
+                &self,
+                message: &Self::Message<'_>,
+                writer: &mut dyn Write,
+                offset: usize,
+            ) -> std::io::Result<Progress> {
+                let mut encode_context = EncodeContext::with_writer(writer, offset, true)
+                    .with_literal_policy(($literal_policy)(self));
+
+                match EncodeIntoContext::encode_ctx(message.borrow(), &mut encode_context) {
+                    Ok(()) => Ok(Progress::Done),
+                    Err(error) => match encode_context.paused() {
+                        Some(offset) => Ok(Progress::Paused { offset }),
+                        None => Err(error),
+                    },
+                }
+            }
+
+            fn dump_into(
+                &self,
+                message: &Self::Message<'_>,
+                writer: &mut dyn Write,
+            ) -> std::io::Result<()> {
+                let mut encode_context = EncodeContext::with_writer(writer, 0, false)
+                    .with_literal_policy(($literal_policy)(self));
+                EncodeIntoContext::encode_ctx(message.borrow(), &mut encode_context)
+            }
+
+            fn encoded_len(&self, message: &Self::Message<'_>) -> EncodedLen {
+                let mut encode_context =
+                    EncodeContext::with_length().with_literal_policy(($literal_policy)(self));
+                EncodeIntoContext::encode_ctx(message.borrow(), &mut encode_context).unwrap();
+
+                encode_context.len()
+            }
         }
     };
 }
 
 impl_encoder_for_codec!(GreetingCodec, Greeting<'a>);
-impl_encoder_for_codec!(CommandCodec, Command<'a>);
+impl_encoder_for_codec!(CommandCodec, Command<'a>, CommandCodec::literal_policy);
 impl_encoder_for_codec!(AuthenticateDataCodec, AuthenticateData<'a>);
 impl_encoder_for_codec!(ResponseCodec, Response<'a>);
 impl_encoder_for_codec!(IdleDoneCodec, IdleDone);
@@ -256,13 +656,13 @@ pub(crate) trait EncodeIntoContext {
 
 impl EncodeIntoContext for u32 {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
-        ctx.write_all(self.to_string().as_bytes())
+        write!(ctx, "{self}")
     }
 }
 
 impl EncodeIntoContext for u64 {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
-        ctx.write_all(self.to_string().as_bytes())
+        write!(ctx, "{self}")
     }
 }
 
@@ -382,6 +782,17 @@ impl<'a> EncodeIntoContext for CommandBody<'a> {
                 ctx.write_all(b" ")?;
                 mailbox_wildcard.encode_ctx(ctx)
             }
+            #[cfg(feature = "ext_xlist")]
+            CommandBody::XList {
+                reference,
+                mailbox_wildcard,
+            } => {
+                ctx.write_all(b"XLIST")?;
+                ctx.write_all(b" ")?;
+                reference.encode_ctx(ctx)?;
+                ctx.write_all(b" ")?;
+                mailbox_wildcard.encode_ctx(ctx)
+            }
             CommandBody::Status {
                 mailbox,
                 item_names,
@@ -477,6 +888,30 @@ impl<'a> EncodeIntoContext for CommandBody<'a> {
                 ctx.write_all(b" ")?;
                 join_serializable(search_criteria.as_ref(), b" ", ctx)
             }
+            CommandBody::ESearch {
+                source,
+                return_options,
+                charset,
+                criteria,
+            } => {
+                ctx.write_all(b"ESEARCH")?;
+                if let Some(source) = source {
+                    ctx.write_all(b" IN (")?;
+                    join_serializable(source.as_ref(), b" ", ctx)?;
+                    ctx.write_all(b")")?;
+                }
+                if !return_options.is_empty() {
+                    ctx.write_all(b" RETURN (")?;
+                    join_serializable(return_options, b" ", ctx)?;
+                    ctx.write_all(b")")?;
+                }
+                if let Some(charset) = charset {
+                    ctx.write_all(b" CHARSET ")?;
+                    charset.encode_ctx(ctx)?;
+                }
+                ctx.write_all(b" ")?;
+                join_serializable(criteria.as_ref(), b" ", ctx)
+            }
             CommandBody::Fetch {
                 sequence_set,
                 macro_or_item_names,
@@ -525,6 +960,40 @@ impl<'a> EncodeIntoContext for CommandBody<'a> {
                 join_serializable(flags, b" ", ctx)?;
                 ctx.write_all(b")")
             }
+            #[cfg(feature = "ext_gmail")]
+            CommandBody::StoreXGmLabels {
+                sequence_set,
+                kind,
+                response,
+                labels,
+                uid,
+            } => {
+                if *uid {
+                    ctx.write_all(b"UID STORE ")?;
+                } else {
+                    ctx.write_all(b"STORE ")?;
+                }
+
+                sequence_set.encode_ctx(ctx)?;
+                ctx.write_all(b" ")?;
+
+                match kind {
+                    StoreType::Add => ctx.write_all(b"+")?,
+                    StoreType::Remove => ctx.write_all(b"-")?,
+                    StoreType::Replace => {}
+                }
+
+                ctx.write_all(b"X-GM-LABELS")?;
+
+                match response {
+                    StoreResponse::Answer => {}
+                    StoreResponse::Silent => ctx.write_all(b".SILENT")?,
+                }
+
+                ctx.write_all(b" (")?;
+                join_serializable(labels, b" ", ctx)?;
+                ctx.write_all(b")")
+            }
             CommandBody::Copy {
                 sequence_set,
                 mailbox,
@@ -582,33 +1051,7 @@ impl<'a> EncodeIntoContext for CommandBody<'a> {
                 ctx.write_all(b"ID ")?;
 
                 match parameters {
-                    Some(parameters) => {
-                        if let Some((first, tail)) = parameters.split_first() {
-                            ctx.write_all(b"(")?;
-
-                            first.0.encode_ctx(ctx)?;
-                            ctx.write_all(b" ")?;
-                            first.1.encode_ctx(ctx)?;
-
-                            for parameter in tail {
-                                ctx.write_all(b" ")?;
-                                parameter.0.encode_ctx(ctx)?;
-                                ctx.write_all(b" ")?;
-                                parameter.1.encode_ctx(ctx)?;
-                            }
-
-                            ctx.write_all(b")")
-                        } else {
-                            #[cfg(not(feature = "quirk_id_empty_to_nil"))]
-                            {
-                                ctx.write_all(b"()")
-                            }
-                            #[cfg(feature = "quirk_id_empty_to_nil")]
-                            {
-                                ctx.write_all(b"NIL")
-                            }
-                        }
-                    }
+                    Some(parameters) => parameters.encode_ctx(ctx),
                     None => ctx.write_all(b"NIL"),
                 }
             }
@@ -675,6 +1118,18 @@ impl EncodeIntoContext for AuthenticateData<'_> {
 
 impl<'a> EncodeIntoContext for AString<'a> {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        if ctx.literal_policy().choose_representation() {
+            return match self {
+                AString::Atom(atom) => atom.encode_ctx(ctx),
+                AString::String(IString::Quoted(quoted)) => {
+                    encode_cheapest(quoted.inner().as_bytes(), true, LiteralMode::Sync, ctx)
+                }
+                AString::String(IString::Literal(literal)) => {
+                    encode_cheapest(literal.as_ref(), true, literal.mode(), ctx)
+                }
+            };
+        }
+
         match self {
             AString::Atom(atom) => atom.encode_ctx(ctx),
             AString::String(imap_str) => imap_str.encode_ctx(ctx),
@@ -697,22 +1152,52 @@ impl<'a> EncodeIntoContext for AtomExt<'a> {
 impl<'a> EncodeIntoContext for IString<'a> {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
         match self {
+            Self::Literal(val) if ctx.literal_policy().choose_representation() => {
+                encode_cheapest(val.as_ref(), false, val.mode(), ctx)
+            }
             Self::Literal(val) => val.encode_ctx(ctx),
             Self::Quoted(val) => val.encode_ctx(ctx),
         }
     }
 }
 
+/// Encodes `data` as atom (if `atom` is allowed), quoted, or literal, whatever is the cheapest.
+fn encode_cheapest(
+    data: &[u8],
+    atom: bool,
+    mode: LiteralMode,
+    ctx: &mut EncodeContext,
+) -> std::io::Result<()> {
+    if atom && AtomExt::validate(data).is_ok() {
+        return ctx.write_all(data);
+    }
+
+    // Note: Quoted strings are 7-bit and thus valid UTF-8.
+    if let Ok(quoted) = std::str::from_utf8(data) {
+        if Quoted::validate(quoted).is_ok() {
+            return Quoted::unvalidated(quoted).encode_ctx(ctx);
+        }
+    }
+
+    let mut literal = Literal::unvalidated(data);
+    literal.set_mode(mode);
+    literal.encode_ctx(ctx)
+}
+
 impl<'a> EncodeIntoContext for Literal<'a> {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
-        match self.mode() {
+        let mode = ctx
+            .literal_policy()
+            .literal_mode(self.as_ref().len(), self.mode());
+
+        match mode {
             LiteralMode::Sync => write!(ctx, "{{{}}}\r\n", self.as_ref().len())?,
             LiteralMode::NonSync => write!(ctx, "{{{}+}}\r\n", self.as_ref().len())?,
         }
 
-        ctx.push_line();
+        ctx.push_announcement(mode)?;
         ctx.write_all(self.as_ref())?;
-        ctx.push_literal(self.mode());
+        ctx.push_literal(mode);
 
         Ok(())
     }
@@ -914,6 +1399,11 @@ impl<'a> EncodeIntoContext for SearchKey<'a> {
                 join_serializable(search_keys.as_ref(), b" ", ctx)?;
                 ctx.write_all(b")")
             }
+            #[cfg(feature = "ext_gmail")]
+            SearchKey::XGmRaw(astring) => {
+                ctx.write_all(b"X-GM-RAW ")?;
+                astring.encode_ctx(ctx)
+            }
         }
     }
 }
@@ -1039,6 +1529,12 @@ impl<'a> EncodeIntoContext for MessageDataItemName<'a> {
                 join_serializable(section, b".", ctx)?;
                 ctx.write_all(b"]")
             }
+            #[cfg(feature = "ext_gmail")]
+            MessageDataItemName::XGmMsgId => ctx.write_all(b"X-GM-MSGID"),
+            #[cfg(feature = "ext_gmail")]
+            MessageDataItemName::XGmThrId => ctx.write_all(b"X-GM-THRID"),
+            #[cfg(feature = "ext_gmail")]
+            MessageDataItemName::XGmLabels => ctx.write_all(b"X-GM-LABELS"),
         }
     }
 }
@@ -1119,10 +1615,17 @@ impl<'a> EncodeIntoContext for Response<'a> {
             Response::CommandContinuationRequest(continue_request) => {
                 continue_request.encode_ctx(ctx)
             }
+            Response::Unparsed(unparsed) => unparsed.encode_ctx(ctx),
         }
     }
 }
 
+impl<'a> EncodeIntoContext for Unparsed<'a> {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        ctx.write_all(self.raw())
+    }
+}
+
 impl<'a> EncodeIntoContext for Greeting<'a> {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
         ctx.write_all(b"* ")?;
@@ -1232,11 +1735,11 @@ impl<'a> EncodeIntoContext for Code<'a> {
                 ctx.write_all(b"UNSEEN ")?;
                 seq.encode_ctx(ctx)
             }
-            // RFC 2221
+            // RFC 2221, RFC 2193
             #[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
             Code::Referral(url) => {
                 ctx.write_all(b"REFERRAL ")?;
-                ctx.write_all(url.as_bytes())
+                ctx.write_all(url.to_string().as_bytes())
             }
             Code::CompressionActive => ctx.write_all(b"COMPRESSIONACTIVE"),
             Code::OverQuota => ctx.write_all(b"OVERQUOTA"),
@@ -1266,6 +1769,23 @@ impl<'a> EncodeIntoContext for Code<'a> {
                 destination.encode_ctx(ctx)
             }
             Code::UidNotSticky => ctx.write_all(b"UIDNOTSTICKY"),
+            // RFC 5530
+            Code::Unavailable => ctx.write_all(b"UNAVAILABLE"),
+            Code::AuthenticationFailed => ctx.write_all(b"AUTHENTICATIONFAILED"),
+            Code::AuthorizationFailed => ctx.write_all(b"AUTHORIZATIONFAILED"),
+            Code::Expired => ctx.write_all(b"EXPIRED"),
+            Code::PrivacyRequired => ctx.write_all(b"PRIVACYREQUIRED"),
+            Code::ContactAdmin => ctx.write_all(b"CONTACTADMIN"),
+            Code::NoPerm => ctx.write_all(b"NOPERM"),
+            Code::InUse => ctx.write_all(b"INUSE"),
+            Code::ExpungeIssued => ctx.write_all(b"EXPUNGEISSUED"),
+            Code::Corruption => ctx.write_all(b"CORRUPTION"),
+            Code::ServerBug => ctx.write_all(b"SERVERBUG"),
+            Code::ClientBug => ctx.write_all(b"CLIENTBUG"),
+            Code::Cannot => ctx.write_all(b"CANNOT"),
+            Code::Limit => ctx.write_all(b"LIMIT"),
+            Code::AlreadyExists => ctx.write_all(b"ALREADYEXISTS"),
+            Code::NonExistent => ctx.write_all(b"NONEXISTENT"),
             Code::Other(unknown) => unknown.encode_ctx(ctx),
         }
     }
@@ -1328,6 +1848,26 @@ impl<'a> EncodeIntoContext for Data<'a> {
                 ctx.write_all(b" ")?;
                 mailbox.encode_ctx(ctx)?;
             }
+            #[cfg(feature = "ext_xlist")]
+            Data::XList {
+                items,
+                delimiter,
+                mailbox,
+            } => {
+                ctx.write_all(b"* XLIST (")?;
+                join_serializable(items, b" ", ctx)?;
+                ctx.write_all(b") ")?;
+
+                if let Some(delimiter) = delimiter {
+                    ctx.write_all(b"\"")?;
+                    delimiter.encode_ctx(ctx)?;
+                    ctx.write_all(b"\"")?;
+                } else {
+                    ctx.write_all(b"NIL")?;
+                }
+                ctx.write_all(b" ")?;
+                mailbox.encode_ctx(ctx)?;
+            }
             Data::Status { mailbox, items } => {
                 ctx.write_all(b"* STATUS ")?;
                 mailbox.encode_ctx(ctx)?;
@@ -1361,6 +1901,24 @@ impl<'a> EncodeIntoContext for Data<'a> {
                     }
                 }
             }
+            Data::ESearch {
+                correlator,
+                uid,
+                data,
+            } => {
+                ctx.write_all(b"* ESEARCH")?;
+                if let Some(correlator) = correlator {
+                    ctx.write_all(b" ")?;
+                    correlator.encode_ctx(ctx)?;
+                }
+                if *uid {
+                    ctx.write_all(b" UID")?;
+                }
+                for item in data {
+                    ctx.write_all(b" ")?;
+                    item.encode_ctx(ctx)?;
+                }
+            }
             Data::Flags(flags) => {
                 ctx.write_all(b"* FLAGS (")?;
                 join_serializable(flags, b" ", ctx)?;
@@ -1402,36 +1960,8 @@ impl<'a> EncodeIntoContext for Data<'a> {
                 ctx.write_all(b"* ID ")?;
 
                 match parameters {
-                    Some(parameters) => {
-                        if let Some((first, tail)) = parameters.split_first() {
-                            ctx.write_all(b"(")?;
-
-                            first.0.encode_ctx(ctx)?;
-                            ctx.write_all(b" ")?;
-                            first.1.encode_ctx(ctx)?;
-
-                            for parameter in tail {
-                                ctx.write_all(b" ")?;
-                                parameter.0.encode_ctx(ctx)?;
-                                ctx.write_all(b" ")?;
-                                parameter.1.encode_ctx(ctx)?;
-                            }
-
-                            ctx.write_all(b")")?;
-                        } else {
-                            #[cfg(not(feature = "quirk_id_empty_to_nil"))]
-                            {
-                                ctx.write_all(b"()")?;
-                            }
-                            #[cfg(feature = "quirk_id_empty_to_nil")]
-                            {
-                                ctx.write_all(b"NIL")?;
-                            }
-                        }
-                    }
-                    None => {
-                        ctx.write_all(b"NIL")?;
-                    }
+                    Some(parameters) => parameters.encode_ctx(ctx)?,
+                    None => ctx.write_all(b"NIL")?,
                 }
             }
             #[cfg(feature = "ext_metadata")]
@@ -1565,6 +2095,22 @@ impl<'a> EncodeIntoContext for MessageDataItem<'a> {
                 ctx.write_all(b"] ")?;
                 size.encode_ctx(ctx)
             }
+            #[cfg(feature = "ext_gmail")]
+            Self::XGmMsgId(msgid) => {
+                ctx.write_all(b"X-GM-MSGID ")?;
+                msgid.encode_ctx(ctx)
+            }
+            #[cfg(feature = "ext_gmail")]
+            Self::XGmThrId(thrid) => {
+                ctx.write_all(b"X-GM-THRID ")?;
+                thrid.encode_ctx(ctx)
+            }
+            #[cfg(feature = "ext_gmail")]
+            Self::XGmLabels(labels) => {
+                ctx.write_all(b"X-GM-LABELS (")?;
+                join_serializable(labels, b" ", ctx)?;
+                ctx.write_all(b")")
+            }
         }
     }
 }
@@ -1923,7 +2469,7 @@ mod tests {
     use imap_types::{
         auth::AuthMechanism,
         command::{Command, CommandBody},
-        core::{AString, Literal, NString, Vec1},
+        core::{AString, Literal, NString, Quoted, Vec1},
         fetch::MessageDataItem,
         response::{Data, Response},
         utils::escape_byte_string,
@@ -2041,9 +2587,164 @@ mod tests {
                 }]
                 .as_ref(),
             ),
+            (
+                Command::new(
+                    "A",
+                    CommandBody::login(
+                        b"\xCA\xFE".as_ref(),
+                        AString::from(Literal::unvalidated_non_sync(b"\xCA\xFE".as_ref())),
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                [
+                    Fragment::Line {
+                        data: b"A LOGIN {2}\r\n".to_vec(),
+                    },
+                    Fragment::Literal {
+                        data: b"\xCA\xFE".to_vec(),
+                        mode: LiteralMode::Sync,
+                    },
+                    Fragment::Line {
+                        data: b" {2+}\r\n".to_vec(),
+                    },
+                    Fragment::Literal {
+                        data: b"\xCA\xFE".to_vec(),
+                        mode: LiteralMode::NonSync,
+                    },
+                    Fragment::Line {
+                        data: b"\r\n".to_vec(),
+                    },
+                ]
+                .as_ref(),
+            ),
         ]);
     }
 
+    #[test]
+    fn test_literal_policy() {
+        let big = vec![0xCA; LiteralPolicy::LITERAL_MINUS_MAX + 1];
+
+        let tests: [(LiteralPolicy, Command, &[u8]); 9] = [
+            // Default: keep everything.
+            (
+                LiteralPolicy::default(),
+                Command::new(
+                    "A",
+                    CommandBody::login(Literal::try_from("alice").unwrap(), "\"pass\"").unwrap(),
+                )
+                .unwrap(),
+                b"A LOGIN {5}\r\nalice \"\\\"pass\\\"\"\r\n",
+            ),
+            // LITERAL+
+            (
+                LiteralPolicy::from_capabilities(&[Capability::LiteralPlus]),
+                Command::new("A", CommandBody::login("alice", big.as_slice()).unwrap()).unwrap(),
+                b"A LOGIN alice {4097+}\r\n",
+            ),
+            // LITERAL- (small)
+            (
+                LiteralPolicy::from_capabilities(&[Capability::LiteralMinus]),
+                Command::new("A", CommandBody::login("alice", b"\xCA".as_ref()).unwrap()).unwrap(),
+                b"A LOGIN alice {1+}\r\n\xCA\r\n",
+            ),
+            // LITERAL- (big)
+            (
+                LiteralPolicy::from_capabilities(&[Capability::LiteralMinus]),
+                Command::new(
+                    "A",
+                    CommandBody::login(
+                        "alice",
+                        AString::from(Literal::unvalidated_non_sync(big.as_slice())),
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                b"A LOGIN alice {4097}\r\n",
+            ),
+            // LITERAL+ and LITERAL-
+            (
+                LiteralPolicy::from_capabilities(&[
+                    Capability::LiteralPlus,
+                    Capability::LiteralMinus,
+                ]),
+                Command::new("A", CommandBody::login("alice", big.as_slice()).unwrap()).unwrap(),
+                b"A LOGIN alice {4097+}\r\n",
+            ),
+            // Unsupported
+            (
+                LiteralPolicy::from_capabilities(&[Capability::Imap4Rev1]),
+                Command::new(
+                    "A",
+                    CommandBody::login(
+                        "alice",
+                        AString::from(Literal::unvalidated_non_sync(b"\xCA".as_ref())),
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                b"A LOGIN alice {1}\r\n\xCA\r\n",
+            ),
+            // Choose representation
+            (
+                LiteralPolicy::default().with_choose_representation(true),
+                Command::new(
+                    "A",
+                    CommandBody::login(
+                        AString::from(Quoted::try_from("alice").unwrap()),
+                        AString::from(Literal::unvalidated_non_sync(b"pa ss".as_ref())),
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                b"A LOGIN alice \"pa ss\"\r\n",
+            ),
+            (
+                LiteralPolicy::default().with_choose_representation(true),
+                Command::new(
+                    "A",
+                    CommandBody::login(
+                        AString::from(Quoted::try_from("").unwrap()),
+                        AString::from(Literal::unvalidated_non_sync(b"\xCA".as_ref())),
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                b"A LOGIN \"\" {1+}\r\n\xCA\r\n",
+            ),
+            // Note: Mailbox names use `AString`, too.
+            (
+                LiteralPolicy::default().with_choose_representation(true),
+                Command::new(
+                    "A",
+                    CommandBody::select(AString::from(Literal::try_from("[Gmail]").unwrap()))
+                        .unwrap(),
+                )
+                .unwrap(),
+                b"A SELECT [Gmail]\r\n",
+            ),
+        ];
+
+        for (no, (policy, command, expected)) in tests.into_iter().enumerate() {
+            println!("# {no}");
+
+            let codec = CommandCodec::new().with_literal_policy(policy);
+            let got = codec.encode(&command).dump();
+            assert_eq!(&got[..expected.len()], expected);
+
+            // Fragments must agree with the announcement.
+            for fragment in codec.encode(&command) {
+                if let Fragment::Literal { data, mode } = fragment {
+                    let sync = format!("{{{}}}\r\n", data.len());
+                    let found = got.windows(sync.len()).any(|w| w == sync.as_bytes());
+                    assert_eq!(found, mode == LiteralMode::Sync);
+                }
+            }
+
+            assert_eq!(codec.encoded_len(&command).total(), got.len());
+        }
+    }
+
     #[test]
     fn test_encode_response() {
         kat_encoder::<ResponseCodec, Response<'_>, &[Fragment]>(&[
@@ -2108,6 +2809,56 @@ mod tests {
             let actions = actions.as_ref();
 
             assert_eq!(encoder.collect::<Vec<_>>(), actions);
+
+            // `encode_into` must pause at every sync literal and yield the same bytes.
+            let mut expected = Vec::new();
+            let mut expected_chunks = Vec::new();
+            for action in actions {
+                match action {
+                    Fragment::Line { data } => expected.extend_from_slice(data),
+                    Fragment::Literal { data, mode } => {
+                        if *mode == LiteralMode::Sync {
+                            expected_chunks.push(std::mem::take(&mut expected));
+                        }
+                        expected.extend_from_slice(data);
+                    }
+                }
+            }
+            expected_chunks.push(expected);
+
+            let mut got_chunks = Vec::new();
+            let mut offset = 0;
+            loop {
+                let mut out = Vec::new();
+                let progress = E::default().encode_into(obj, &mut out, offset).unwrap();
+                got_chunks.push(out);
+
+                match progress {
+                    Progress::Done => break,
+                    Progress::Paused { offset: next } => offset = next,
+                }
+            }
+
+            assert_eq!(got_chunks, expected_chunks);
+
+            let mut out = Vec::new();
+            E::default().dump_into(obj, &mut out).unwrap();
+            assert_eq!(out, expected_chunks.concat());
+
+            let literals = actions
+                .iter()
+                .map(|action| match action {
+                    Fragment::Line { .. } => 0,
+                    Fragment::Literal { data, .. } => data.len(),
+                })
+                .sum();
+            assert_eq!(
+                E::default().encoded_len(obj),
+                EncodedLen {
+                    lines: out.len() - literals,
+                    literals,
+                }
+            );
         }
     }
 }
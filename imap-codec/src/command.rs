@@ -1,9 +1,5 @@
 use std::borrow::Cow;
 
-#[cfg(not(feature = "quirk_crlf_relaxed"))]
-use abnf_core::streaming::crlf;
-#[cfg(feature = "quirk_crlf_relaxed")]
-use abnf_core::streaming::crlf_relaxed as crlf;
 use abnf_core::streaming::sp;
 use imap_types::{
     auth::AuthMechanism,
@@ -18,14 +14,19 @@ use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
     combinator::{map, opt, value},
+    error::context,
     multi::{separated_list0, separated_list1},
     sequence::{delimited, preceded, terminated, tuple},
 };
 
+#[cfg(feature = "ext_gmail")]
+use crate::extensions::gmail::store_x_gm_labels;
 #[cfg(feature = "ext_id")]
 use crate::extensions::id::id;
 #[cfg(feature = "ext_metadata")]
 use crate::extensions::metadata::{getmetadata, setmetadata};
+#[cfg(feature = "ext_xlist")]
+use crate::extensions::xlist::xlist;
 use crate::{
     auth::auth_type,
     core::{astring, base64, literal, tag_imap},
@@ -36,6 +37,7 @@ use crate::{
         compress::compress,
         enable::enable,
         idle::idle,
+        multisearch::esearch,
         quota::{getquota, getquotaroot, setquota},
         r#move::r#move,
         sort::sort,
@@ -45,6 +47,7 @@ use crate::{
     fetch::fetch_att,
     flag::{flag, flag_list},
     mailbox::{list_mailbox, mailbox},
+    quirks::crlf,
     search::search,
     sequence::sequence_set,
     status::status_att,
@@ -58,9 +61,12 @@ use crate::{
 ///                   ) CRLF`
 pub(crate) fn command(input: &[u8]) -> IMAPResult<&[u8], Command> {
     let mut parser_tag = terminated(tag_imap, sp);
-    let mut parser_body = terminated(
-        alt((command_any, command_auth, command_nonauth, command_select)),
-        crlf,
+    let mut parser_body = context(
+        "command",
+        terminated(
+            alt((command_any, command_auth, command_nonauth, command_select)),
+            crlf,
+        ),
     );
 
     let (remaining, obtained_tag) = parser_tag(input)?;
@@ -129,7 +135,9 @@ pub(crate) fn command_any(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
 ///                getquotaroot / ; RFC 9208
 ///                setquota /     ; RFC 9208
 ///                setmetadata /  ; RFC 5464
-///                getmetadata    ; RFC 5464
+///                getmetadata /  ; RFC 5464
+///                esearch /      ; RFC 7377
+///                xlist          ; XLIST
 /// ```
 ///
 /// Note: Valid only in Authenticated or Selected state
@@ -156,6 +164,9 @@ pub(crate) fn command_auth(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
         setmetadata,
         #[cfg(feature = "ext_metadata")]
         getmetadata,
+        esearch,
+        #[cfg(feature = "ext_xlist")]
+        xlist,
     ))(input)
 }
 
@@ -404,7 +415,8 @@ pub(crate) fn authenticate(
 ///                   fetch /
 ///                   store /
 ///                   uid /
-///                   search`
+///                   search /
+///                   store-x-gm-labels` ; X-GM-EXT-1
 ///
 /// Note: Valid only when in Selected state
 pub(crate) fn command_select(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
@@ -416,6 +428,8 @@ pub(crate) fn command_select(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
         copy,
         fetch,
         store,
+        #[cfg(feature = "ext_gmail")]
+        store_x_gm_labels,
         uid,
         search,
         sort,
@@ -535,14 +549,22 @@ pub(crate) fn store_att_flags(
     Ok((remaining, (store_type, store_response, flag_list)))
 }
 
-/// `uid = "UID" SP (copy / fetch / search / store)`
+/// `uid = "UID" SP (copy / fetch / search / store / move / store-x-gm-labels)`
 ///
 /// Note: Unique identifiers used instead of message sequence numbers
 pub(crate) fn uid(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
     let mut parser = tuple((
         tag_no_case(b"UID"),
         sp,
-        alt((copy, fetch, search, store, r#move)),
+        alt((
+            copy,
+            fetch,
+            search,
+            store,
+            r#move,
+            #[cfg(feature = "ext_gmail")]
+            store_x_gm_labels,
+        )),
     ));
 
     let (remaining, (_, _, mut cmd)) = parser(input)?;
@@ -553,6 +575,8 @@ pub(crate) fn uid(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
         | CommandBody::Search { ref mut uid, .. }
         | CommandBody::Store { ref mut uid, .. }
         | CommandBody::Move { ref mut uid, .. } => *uid = true,
+        #[cfg(feature = "ext_gmail")]
+        CommandBody::StoreXGmLabels { ref mut uid, .. } => *uid = true,
         _ => unreachable!(),
     }
 
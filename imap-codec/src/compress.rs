@@ -0,0 +1,448 @@
+//! COMPRESS=DEFLATE stream layer (RFC 4978).
+//!
+//! "When using the zlib library (see RFC 1951), the functions deflateInit2(), deflate(),
+//! inflateInit2(), and inflate() suffice to implement this extension. The windowBits value MUST
+//! be in the range -8 to -15, or else deflateInit2() uses the wrong format. [...]
+//! The client and server MUST use the Z_SYNC_FLUSH or Z_FULL_FLUSH option of deflate() after
+//! each command or response [...]."
+//! ([RFC 4978, 4](https://www.rfc-editor.org/rfc/rfc4978#section-4))
+//!
+//! The [`Compressor`] transforms encoded bytes before they are written to the socket, and the
+//! [`Decompressor`] transforms bytes read from the socket before they are passed to the
+//! [`Fragmentizer`].
+//!
+//! Both layers must be activated right after the tagged `OK` response to `COMPRESS`.
+//! [`ClientFlow`](crate::flow::client::ClientFlow) and [`ServerFlow`](crate::flow::server::ServerFlow)
+//! do this automatically.
+//!
+//! # Example
+//!
+//! ```rust
+//! use imap_codec::compress::{Compressor, Decompressor};
+//!
+//! let mut compressor = Compressor::new();
+//! let mut decompressor = Decompressor::new();
+//!
+//! let compressed = compressor.compress(b"A1 NOOP\r\n");
+//! let (decompressed, consumed) = decompressor.decompress(&compressed, 1024).unwrap();
+//! assert_eq!(decompressed, b"A1 NOOP\r\n");
+//! assert_eq!(consumed, compressed.len());
+//! ```
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+use crate::fragmentizer::Fragmentizer;
+
+/// Raw DEFLATE compressor.
+///
+/// Every call to [`Compressor::compress`] ends with a sync flush, i.e., the peer can decompress
+/// all bytes passed so far.
+#[derive(Debug)]
+pub struct Compressor {
+    inner: Compress,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compressor {
+    /// Creates a compressor with the default compression level.
+    pub fn new() -> Self {
+        Self::with_level(Compression::default().level())
+    }
+
+    /// Creates a compressor with the given compression level (0-9).
+    pub fn with_level(level: u32) -> Self {
+        Self {
+            // `false` selects raw DEFLATE, i.e., no zlib header.
+            inner: Compress::new(Compression::new(level), false),
+        }
+    }
+
+    /// Compresses bytes (and flushes them).
+    pub fn compress(&mut self, bytes: &[u8]) -> Vec<u8> {
+        if bytes.is_empty() {
+            return Vec::new();
+        }
+
+        let start = self.inner.total_in();
+        let mut output = Vec::with_capacity(bytes.len() / 2 + 64);
+
+        loop {
+            let consumed = (self.inner.total_in() - start) as usize;
+
+            // Raw DEFLATE compression can't fail.
+            self.inner
+                .compress_vec(&bytes[consumed..], &mut output, FlushCompress::Sync)
+                .unwrap();
+
+            // The flush is complete when all bytes were consumed and there was enough space left.
+            if (self.inner.total_in() - start) as usize == bytes.len()
+                && output.len() < output.capacity()
+            {
+                return output;
+            }
+
+            output.reserve(output.capacity().max(64));
+        }
+    }
+}
+
+/// Raw DEFLATE decompressor.
+#[derive(Debug)]
+pub struct Decompressor {
+    inner: Decompress,
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decompressor {
+    /// Creates a decompressor.
+    pub fn new() -> Self {
+        Self {
+            // `false` selects raw DEFLATE, i.e., no zlib header.
+            inner: Decompress::new(false),
+        }
+    }
+
+    /// Decompresses bytes but produces at most `max_output` bytes.
+    ///
+    /// Returns the decompressed bytes and the number of consumed input bytes. Input that was not
+    /// consumed (because `max_output` was reached) must be passed again. Incomplete input is kept
+    /// internally and completed by the following call.
+    ///
+    /// Note: A few bytes of DEFLATE data can expand to gigabytes. Thus, the output is bounded.
+    pub fn decompress(
+        &mut self,
+        bytes: &[u8],
+        max_output: usize,
+    ) -> Result<(Vec<u8>, usize), DecompressError> {
+        let start = self.inner.total_in();
+        let mut output = Vec::new();
+
+        loop {
+            let consumed = (self.inner.total_in() - start) as usize;
+            let produced = output.len();
+
+            if produced == max_output {
+                return Ok((output, consumed));
+            }
+
+            // Grow exponentially but never beyond `max_output`.
+            let space = (bytes.len() * 4 + 64)
+                .max(produced)
+                .min(max_output - produced);
+            output.resize(produced + space, 0);
+
+            let total_out = self.inner.total_out();
+            let status = self
+                .inner
+                .decompress(
+                    &bytes[consumed..],
+                    &mut output[produced..],
+                    FlushDecompress::None,
+                )
+                .map_err(|_| DecompressError)?;
+            let written = (self.inner.total_out() - total_out) as usize;
+            output.truncate(produced + written);
+
+            // The output is full, so there may be more.
+            if written == space {
+                continue;
+            }
+
+            let total_in = (self.inner.total_in() - start) as usize;
+
+            if status == Status::StreamEnd
+                || total_in == bytes.len()
+                || (total_in == consumed && written == 0)
+            {
+                return Ok((output, total_in));
+            }
+        }
+    }
+}
+
+/// Received bytes are not a valid raw DEFLATE stream.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecompressError;
+
+/// COMPRESS=DEFLATE layer of a flow.
+///
+/// Received bytes are decompressed in chunks of at most [`CompressionLayer::CHUNK_SIZE`] bytes
+/// and only when the fragmentizer needs more bytes. Thus, the limits of the fragmentizer apply
+/// before a decompression bomb can exhaust the memory.
+#[derive(Debug)]
+pub(crate) struct CompressionLayer {
+    compressor: Compressor,
+    decompressor: Decompressor,
+    /// Received bytes that are not decompressed yet.
+    input: Vec<u8>,
+    /// Number of output bytes that were encoded before the activation (and must not be
+    /// compressed).
+    uncompressed_output: usize,
+}
+
+impl CompressionLayer {
+    /// Maximum number of decompressed bytes passed to the fragmentizer at once.
+    pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Activates compression.
+    ///
+    /// Bytes that were already enqueued into the fragmentizer (but not parsed yet) were sent
+    /// after the activation and are moved back into the layer.
+    pub(crate) fn activate(fragmentizer: &mut Fragmentizer, output: &[u8]) -> Self {
+        Self {
+            compressor: Compressor::new(),
+            decompressor: Decompressor::new(),
+            input: fragmentizer.take_unparsed_bytes(),
+            uncompressed_output: output.len(),
+        }
+    }
+
+    pub(crate) fn enqueue_input(&mut self, bytes: &[u8]) {
+        self.input.extend_from_slice(bytes);
+    }
+
+    /// Decompresses (a chunk of) the received bytes into the fragmentizer.
+    ///
+    /// Returns whether any progress was made, i.e., whether the fragmentizer should be progressed
+    /// again.
+    pub(crate) fn decompress_into(
+        &mut self,
+        fragmentizer: &mut Fragmentizer,
+    ) -> Result<bool, DecompressError> {
+        if self.input.is_empty() {
+            return Ok(false);
+        }
+
+        let (output, consumed) = self
+            .decompressor
+            .decompress(&self.input, Self::CHUNK_SIZE)?;
+        self.input.drain(..consumed);
+        fragmentizer.enqueue_bytes(&output);
+
+        Ok(consumed > 0 || !output.is_empty())
+    }
+
+    /// Compresses the output (except for bytes encoded before the activation).
+    pub(crate) fn compress(&mut self, mut output: Vec<u8>) -> Vec<u8> {
+        let uncompressed = std::mem::take(&mut self.uncompressed_output);
+        let compressed = self.compressor.compress(&output[uncompressed..]);
+        output.truncate(uncompressed);
+        output.extend(compressed);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        command::{Command, CommandBody},
+        extensions::compress::CompressionAlgorithm,
+        response::{Data, Greeting, Status},
+    };
+
+    use super::*;
+    use crate::flow::{
+        client::{ClientFlow, ClientFlowError, ClientFlowEvent, ClientFlowOptions},
+        server::{ServerFlow, ServerFlowError, ServerFlowEvent, ServerFlowOptions},
+    };
+
+    #[test]
+    fn test_compress_decompress() {
+        let mut compressor = Compressor::new();
+        let mut decompressor = Decompressor::new();
+
+        let messages: [&[u8]; 4] = [
+            b"A1 NOOP\r\n",
+            b"",
+            b"A2 LOGIN alice {5+}\r\npass!\r\n",
+            &[b'x'; 100_000],
+        ];
+
+        for message in messages {
+            let compressed = compressor.compress(message);
+            assert_eq!(
+                decompressor.decompress(&compressed, usize::MAX).unwrap(),
+                (message.to_vec(), compressed.len())
+            );
+        }
+    }
+
+    #[test]
+    fn test_decompress_partial() {
+        let mut compressor = Compressor::new();
+        let mut decompressor = Decompressor::new();
+
+        let compressed = compressor.compress(b"* OK [COMPRESSIONACTIVE] ...\r\n");
+        let (first, second) = compressed.split_at(compressed.len() / 2);
+
+        let mut decompressed = decompressor.decompress(first, usize::MAX).unwrap().0;
+        decompressed.extend(decompressor.decompress(second, usize::MAX).unwrap().0);
+        assert_eq!(decompressed, b"* OK [COMPRESSIONACTIVE] ...\r\n");
+    }
+
+    #[test]
+    fn test_flows_compress() {
+        let mut client = ClientFlow::new(ClientFlowOptions::default());
+        let mut server = ServerFlow::new(
+            ServerFlowOptions::default(),
+            Greeting::ok(None, "...").unwrap(),
+        );
+
+        client
+            .enqueue_command(
+                Command::new(
+                    "A1",
+                    CommandBody::Compress {
+                        algorithm: CompressionAlgorithm::Deflate,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+
+        // Greeting and `COMPRESS` are not compressed.
+        client.enqueue_input(&server.take_output());
+        while client.progress().unwrap().is_some() {}
+        server.enqueue_input(&client.take_output());
+        let Some(ServerFlowEvent::CommandReceived { command }) = server.progress().unwrap() else {
+            panic!("expected command");
+        };
+        server.enqueue_status(Status::ok(Some(command.tag), None, "...").unwrap());
+        let output = server.take_output();
+        assert!(output.starts_with(b"A1 OK ...\r\n"));
+
+        // Everything after the tagged OK is compressed.
+        client.enqueue_input(&output);
+        while client.progress().unwrap().is_some() {}
+        let output = client.take_output();
+        assert!(!output.is_empty());
+        assert!(!output.starts_with(b"A2"));
+
+        server.enqueue_input(&output);
+        let Some(ServerFlowEvent::CommandReceived { command }) = server.progress().unwrap() else {
+            panic!("expected command");
+        };
+        assert_eq!(command.body, CommandBody::Noop);
+        server.enqueue_data(Data::Exists(1));
+        server.enqueue_status(Status::ok(Some(command.tag), None, "...").unwrap());
+
+        client.enqueue_input(&server.take_output());
+        let mut events = Vec::new();
+        while let Some(event) = client.progress().unwrap() {
+            events.push(event);
+        }
+        assert_eq!(
+            events[0],
+            ClientFlowEvent::DataReceived {
+                data: Data::Exists(1)
+            }
+        );
+        assert!(matches!(
+            &events[1],
+            ClientFlowEvent::CommandCompleted { command, .. } if command.tag.as_ref() == "A2"
+        ));
+
+        client.enqueue_input(&[0xff; 16]);
+        assert_eq!(client.progress(), Err(ClientFlowError::DecompressionFailed));
+    }
+
+    #[test]
+    fn test_decompress_bounded() {
+        let mut compressor = Compressor::with_level(9);
+        let mut decompressor = Decompressor::new();
+
+        // 64 MiB of zeros compress to a few KiB.
+        let compressed = compressor.compress(&vec![0; 64 * 1024 * 1024]);
+        assert!(compressed.len() < 100 * 1024);
+
+        let mut input = compressed.as_slice();
+        let mut total = 0;
+        while !input.is_empty() {
+            let (output, consumed) = decompressor.decompress(input, 1024).unwrap();
+            assert!(output.len() <= 1024);
+            assert!(!output.is_empty() || consumed > 0);
+            total += output.len();
+            input = &input[consumed..];
+        }
+        // Flush what is still buffered internally.
+        loop {
+            let (output, _) = decompressor.decompress(&[], 1024).unwrap();
+            if output.is_empty() {
+                break;
+            }
+            total += output.len();
+        }
+        assert_eq!(total, 64 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_flow_decompression_bomb() {
+        let mut server = ServerFlow::new(
+            ServerFlowOptions {
+                max_command_size: 1024,
+                ..Default::default()
+            },
+            Greeting::ok(None, "...").unwrap(),
+        );
+        server.take_output();
+        server.enqueue_input(b"A1 COMPRESS DEFLATE\r\n");
+        let Some(ServerFlowEvent::CommandReceived { command }) = server.progress().unwrap() else {
+            panic!("expected command");
+        };
+        server.enqueue_status(Status::ok(Some(command.tag), None, "...").unwrap());
+        server.take_output();
+
+        // A single line of 64 MiB compresses to about 64 KiB.
+        let mut compressor = Compressor::with_level(9);
+        let mut bomb = compressor.compress(b"A2 NOOP ");
+        bomb.extend(compressor.compress(&vec![b'x'; 64 * 1024 * 1024]));
+        server.enqueue_input(&bomb);
+
+        // The bytes exceeding the limit are discarded while decompressing chunk by chunk.
+        assert_eq!(server.progress(), Ok(None));
+
+        server.enqueue_input(&compressor.compress(b"\r\n"));
+        assert!(matches!(
+            server.progress(),
+            Err(ServerFlowError::MessageTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compression_layer_chunks() {
+        let mut fragmentizer = Fragmentizer::new(1024);
+        let mut layer = CompressionLayer::activate(&mut fragmentizer, &[]);
+
+        let mut compressor = Compressor::with_level(9);
+        layer.enqueue_input(&compressor.compress(&vec![b'x'; 16 * 1024 * 1024]));
+
+        for _ in 0..4 {
+            assert!(layer.decompress_into(&mut fragmentizer).unwrap());
+            assert_eq!(
+                fragmentizer.take_unparsed_bytes().len(),
+                CompressionLayer::CHUNK_SIZE
+            );
+        }
+    }
+
+    #[test]
+    fn test_decompress_invalid() {
+        assert_eq!(
+            Decompressor::new().decompress(&[0xff; 16], usize::MAX),
+            Err(DecompressError)
+        );
+    }
+}
@@ -1,9 +1,5 @@
 use std::{borrow::Cow, num::NonZeroU32, str::from_utf8};
 
-#[cfg(not(feature = "quirk_crlf_relaxed"))]
-use abnf_core::streaming::crlf;
-#[cfg(feature = "quirk_crlf_relaxed")]
-use abnf_core::streaming::crlf_relaxed as crlf;
 use abnf_core::{is_alpha, is_digit, streaming::dquote};
 use base64::{engine::general_purpose::STANDARD as _base64, Engine};
 use imap_types::{
@@ -23,10 +19,14 @@ use nom::{
     bytes::streaming::{escaped, tag, tag_no_case, take, take_while, take_while1, take_while_m_n},
     character::streaming::{char, digit1, one_of},
     combinator::{map, map_res, opt, recognize},
+    error::context,
     sequence::{delimited, terminated, tuple},
 };
 
-use crate::decode::{IMAPErrorKind, IMAPParseError, IMAPResult};
+use crate::{
+    decode::{IMAPErrorKind, IMAPParseError, IMAPResult},
+    quirks::crlf,
+};
 
 // ----- number -----
 
@@ -34,12 +34,15 @@ use crate::decode::{IMAPErrorKind, IMAPParseError, IMAPResult};
 ///
 /// Unsigned 32-bit integer (0 <= n < 4,294,967,296)
 pub(crate) fn number(input: &[u8]) -> IMAPResult<&[u8], u32> {
-    map_res(
-        // # Safety
-        //
-        // `unwrap` is safe because `1*DIGIT` contains ASCII-only characters.
-        map(digit1, |val| from_utf8(val).unwrap()),
-        str::parse::<u32>,
+    context(
+        "number",
+        map_res(
+            // # Safety
+            //
+            // `unwrap` is safe because `1*DIGIT` contains ASCII-only characters.
+            map(digit1, |val| from_utf8(val).unwrap()),
+            str::parse::<u32>,
+        ),
     )(input)
 }
 
@@ -51,12 +54,15 @@ pub(crate) fn number(input: &[u8]) -> IMAPResult<&[u8], u32> {
 ///
 /// Defined in RFC 9051
 pub(crate) fn number64(input: &[u8]) -> IMAPResult<&[u8], u64> {
-    map_res(
-        // # Safety
-        //
-        // `unwrap` is safe because `1*DIGIT` contains ASCII-only characters.
-        map(digit1, |val| from_utf8(val).unwrap()),
-        str::parse::<u64>,
+    context(
+        "number64",
+        map_res(
+            // # Safety
+            //
+            // `unwrap` is safe because `1*DIGIT` contains ASCII-only characters.
+            map(digit1, |val| from_utf8(val).unwrap()),
+            str::parse::<u64>,
+        ),
     )(input)
 }
 
@@ -64,7 +70,7 @@ pub(crate) fn number64(input: &[u8]) -> IMAPResult<&[u8], u64> {
 ///
 /// Non-zero unsigned 32-bit integer (0 < n < 4,294,967,296)
 pub(crate) fn nz_number(input: &[u8]) -> IMAPResult<&[u8], NonZeroU32> {
-    map_res(number, NonZeroU32::try_from)(input)
+    context("nz-number", map_res(number, NonZeroU32::try_from))(input)
 }
 
 // ----- string -----
@@ -143,18 +149,21 @@ pub(crate) fn is_any_text_char_except_quoted_specials(byte: u8) -> bool {
 /// ```
 /// -- <https://datatracker.ietf.org/doc/html/rfc7888#section-8>
 pub(crate) fn literal(input: &[u8]) -> IMAPResult<&[u8], Literal> {
-    let (remaining, (length, mode)) = terminated(
-        delimited(
-            tag(b"{"),
-            tuple((
-                number,
-                map(opt(char('+')), |i| {
-                    i.map(|_| LiteralMode::NonSync).unwrap_or(LiteralMode::Sync)
-                }),
-            )),
-            tag(b"}"),
+    let (remaining, (length, mode)) = context(
+        "literal",
+        terminated(
+            delimited(
+                tag(b"{"),
+                tuple((
+                    number,
+                    map(opt(char('+')), |i| {
+                        i.map(|_| LiteralMode::NonSync).unwrap_or(LiteralMode::Sync)
+                    }),
+                )),
+                tag(b"}"),
+            ),
+            crlf,
         ),
-        crlf,
     )(input)?;
 
     // Signal that an continuation request could be required.
@@ -162,6 +171,7 @@ pub(crate) fn literal(input: &[u8]) -> IMAPResult<&[u8], Literal> {
     if remaining.is_empty() {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: Some("literal"),
             kind: IMAPErrorKind::Literal {
                 // We don't know the tag here and rely on an upper parser, e.g., `command` to fill this in.
                 tag: None,
@@ -181,6 +191,7 @@ pub(crate) fn literal(input: &[u8]) -> IMAPResult<&[u8], Literal> {
         }
         Err(_) => Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: Some("literal"),
             kind: IMAPErrorKind::LiteralContainsNull,
         })),
     }
@@ -254,12 +265,15 @@ pub(crate) fn text(input: &[u8]) -> IMAPResult<&[u8], Text> {
 
 /// `base64 = *(4base64-char) [base64-terminal]`
 pub(crate) fn base64(input: &[u8]) -> IMAPResult<&[u8], Vec<u8>> {
-    map_res(
-        recognize(tuple((
-            take_while(is_base64_char),
-            opt(alt((tag("=="), tag("=")))),
-        ))),
-        |input| _base64.decode(input),
+    context(
+        "base64",
+        map_res(
+            recognize(tuple((
+                take_while(is_base64_char),
+                opt(alt((tag("=="), tag("=")))),
+            ))),
+            |input| _base64.decode(input),
+        ),
     )(input)
 }
 
@@ -283,12 +297,15 @@ pub(crate) fn charset(input: &[u8]) -> IMAPResult<&[u8], Charset> {
 
 /// `tag = 1*<any ASTRING-CHAR except "+">`
 pub(crate) fn tag_imap(input: &[u8]) -> IMAPResult<&[u8], Tag> {
-    map(take_while1(|b| is_astring_char(b) && b != b'+'), |val| {
-        // # Safety
-        //
-        // `is_astring_char` ensures that `val` is UTF-8.
-        Tag::unvalidated(from_utf8(val).unwrap())
-    })(input)
+    context(
+        "tag",
+        map(take_while1(|b| is_astring_char(b) && b != b'+'), |val| {
+            // # Safety
+            //
+            // `is_astring_char` ensures that `val` is UTF-8.
+            Tag::unvalidated(from_utf8(val).unwrap())
+        }),
+    )(input)
 }
 
 // TODO: This could be exposed in a more elegant way...
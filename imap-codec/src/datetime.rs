@@ -11,6 +11,7 @@ use nom::{
     bytes::streaming::{tag, tag_no_case, take_while_m_n},
     character::streaming::char,
     combinator::{map, map_res, value},
+    error::context,
     sequence::{delimited, preceded, tuple},
 };
 
@@ -99,20 +100,23 @@ pub(crate) fn time(input: &[u8]) -> IMAPResult<&[u8], Option<NaiveTime>> {
 ///             DQUOTE
 /// ```
 pub(crate) fn date_time(input: &[u8]) -> IMAPResult<&[u8], DateTime> {
-    let mut parser = delimited(
-        dquote,
-        tuple((
-            date_day_fixed,
-            tag(b"-"),
-            date_month,
-            tag(b"-"),
-            date_year,
-            sp,
-            time,
-            sp,
-            zone,
-        )),
-        dquote,
+    let mut parser = context(
+        "date-time",
+        delimited(
+            dquote,
+            tuple((
+                date_day_fixed,
+                tag(b"-"),
+                date_month,
+                tag(b"-"),
+                date_year,
+                sp,
+                time,
+                sp,
+                zone,
+            )),
+            dquote,
+        ),
     );
 
     let (remaining, (d, _, m, _, y, _, time, _, zone)) = parser(input)?;
@@ -128,12 +132,14 @@ pub(crate) fn date_time(input: &[u8]) -> IMAPResult<&[u8], DateTime> {
             } else {
                 Err(nom::Err::Failure(IMAPParseError {
                     input,
+                    rule: Some("date-time"),
                     kind: IMAPErrorKind::BadDateTime,
                 }))
             }
         }
         _ => Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: Some("date-time"),
             kind: IMAPErrorKind::BadDateTime,
         })),
     }
@@ -7,13 +7,15 @@ use nom::{
     branch::alt,
     bytes::streaming::tag,
     combinator::map,
-    multi::many1,
+    error::context,
+    multi::{many0, many1},
     sequence::{delimited, tuple},
 };
 
 use crate::{
     core::{nil, nstring},
     decode::IMAPResult,
+    quirks,
 };
 
 /// ```abnf
@@ -31,30 +33,33 @@ use crate::{
 ///            ")"
 /// ```
 pub(crate) fn envelope(input: &[u8]) -> IMAPResult<&[u8], Envelope> {
-    let mut parser = delimited(
-        tag(b"("),
-        tuple((
-            env_date,
-            sp,
-            env_subject,
-            sp,
-            env_from,
-            sp,
-            env_sender,
-            sp,
-            env_reply_to,
-            sp,
-            env_to,
-            sp,
-            env_cc,
-            sp,
-            env_bcc,
-            sp,
-            env_in_reply_to,
-            sp,
-            env_message_id,
-        )),
-        tag(b")"),
+    let mut parser = context(
+        "envelope",
+        delimited(
+            tag(b"("),
+            tuple((
+                env_date,
+                sp,
+                env_subject,
+                sp,
+                env_from,
+                sp,
+                env_sender,
+                sp,
+                env_reply_to,
+                sp,
+                env_to,
+                sp,
+                env_cc,
+                sp,
+                env_bcc,
+                sp,
+                env_in_reply_to,
+                sp,
+                env_message_id,
+            )),
+            tag(b")"),
+        ),
     );
 
     let (
@@ -178,15 +183,17 @@ pub(crate) fn env_message_id(input: &[u8]) -> IMAPResult<&[u8], NString> {
 ///             addr-host
 ///             ")"`
 pub(crate) fn address(input: &[u8]) -> IMAPResult<&[u8], Address> {
-    #[cfg_attr(feature = "quirk_spaces_between_addresses", allow(unused_mut))]
     let mut parser = delimited(
         tag(b"("),
         tuple((addr_name, sp, addr_adl, sp, addr_mailbox, sp, addr_host)),
         tag(b")"),
     );
 
-    #[cfg(feature = "quirk_spaces_between_addresses")]
-    let mut parser = nom::sequence::preceded(nom::multi::many0(sp), parser);
+    let input = if quirks::current().spaces_between_addresses {
+        many0(sp)(input)?.0
+    } else {
+        input
+    };
 
     let (remaining, (name, _, adl, _, mailbox, _, host)) = parser(input)?;
 
@@ -1,6 +1,8 @@
 pub mod binary;
 pub mod compress;
 pub mod enable;
+#[cfg(feature = "ext_gmail")]
+pub mod gmail;
 #[cfg(feature = "ext_id")]
 pub mod id;
 pub mod idle;
@@ -8,8 +10,13 @@ pub mod literal;
 #[cfg(feature = "ext_metadata")]
 pub mod metadata;
 pub mod r#move;
+pub mod multisearch;
 pub mod quota;
+#[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
+pub mod referral;
 pub mod sort;
 pub mod thread;
 pub mod uidplus;
 pub mod unselect;
+#[cfg(feature = "ext_xlist")]
+pub mod xlist;
@@ -1,9 +1,5 @@
 use std::{borrow::Cow, io::Write, num::NonZeroU32};
 
-#[cfg(not(feature = "quirk_crlf_relaxed"))]
-use abnf_core::streaming::crlf;
-#[cfg(feature = "quirk_crlf_relaxed")]
-use abnf_core::streaming::crlf_relaxed as crlf;
 use imap_types::{
     core::LiteralMode,
     extensions::binary::{Literal8, LiteralOrLiteral8},
@@ -20,6 +16,7 @@ use crate::{
     decode::{IMAPErrorKind, IMAPParseError, IMAPResult},
     encode::{EncodeContext, EncodeIntoContext},
     fetch::section_part,
+    quirks::crlf,
 };
 
 /// See <https://datatracker.ietf.org/doc/html/rfc3516> and <https://datatracker.ietf.org/doc/html/rfc4466>
@@ -49,6 +46,7 @@ pub(crate) fn literal8(input: &[u8]) -> IMAPResult<&[u8], Literal8> {
     if remaining.is_empty() {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: Some("literal8"),
             kind: IMAPErrorKind::Literal {
                 // We don't know the tag here and rely on an upper parser, e.g., `command` to fill this in.
                 tag: None,
@@ -80,14 +78,18 @@ impl<'a> EncodeIntoContext for LiteralOrLiteral8<'a> {
 
 impl<'a> EncodeIntoContext for Literal8<'a> {
     fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
-        match self.mode {
+        let mode = ctx
+            .literal_policy()
+            .literal_mode(self.data.len(), self.mode);
+
+        match mode {
             LiteralMode::Sync => write!(ctx, "~{{{}}}\r\n", self.data.len())?,
             LiteralMode::NonSync => write!(ctx, "~{{{}+}}\r\n", self.data.len())?,
         }
 
-        ctx.push_line();
+        ctx.push_announcement(mode)?;
         ctx.write_all(&self.data)?;
-        ctx.push_literal(self.mode);
+        ctx.push_literal(mode);
 
         Ok(())
     }
@@ -0,0 +1,247 @@
+//! Gmail IMAP Extensions (X-GM-EXT-1)
+
+use std::io::Write;
+
+use abnf_core::streaming::sp;
+use imap_types::{
+    command::CommandBody,
+    extensions::gmail::GmailLabel,
+    fetch::MessageDataItem,
+    flag::{StoreResponse, StoreType},
+};
+use nom::{
+    branch::alt,
+    bytes::streaming::{tag, tag_no_case},
+    combinator::{map, opt, value},
+    multi::separated_list1,
+    sequence::{delimited, preceded, tuple},
+};
+
+use crate::{
+    core::{astring, atom, number64},
+    decode::IMAPResult,
+    encode::{EncodeContext, EncodeIntoContext},
+    sequence::sequence_set,
+};
+
+/// ```abnf
+/// store-x-gm-labels = "STORE" SP sequence-set SP store-att-x-gm-labels
+///
+/// store-att-x-gm-labels = (["+" / "-"] "X-GM-LABELS" [".SILENT"])
+///                         SP (x-gm-label-list / (x-gm-label *(SP x-gm-label)))
+/// ```
+pub(crate) fn store_x_gm_labels(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
+    let mut parser = tuple((
+        tag_no_case(b"STORE"),
+        sp,
+        sequence_set,
+        sp,
+        map(
+            opt(alt((
+                value(StoreType::Add, tag(b"+")),
+                value(StoreType::Remove, tag(b"-")),
+            ))),
+            |kind| kind.unwrap_or(StoreType::Replace),
+        ),
+        tag_no_case(b"X-GM-LABELS"),
+        map(opt(tag_no_case(b".SILENT")), |silent| match silent {
+            Some(_) => StoreResponse::Silent,
+            None => StoreResponse::Answer,
+        }),
+        sp,
+        alt((x_gm_label_list, separated_list1(sp, x_gm_label))),
+    ));
+
+    let (remaining, (_, _, sequence_set, _, kind, _, response, _, labels)) = parser(input)?;
+
+    Ok((
+        remaining,
+        CommandBody::StoreXGmLabels {
+            sequence_set,
+            kind,
+            response,
+            labels,
+            uid: false,
+        },
+    ))
+}
+
+/// ```abnf
+/// msg-att-x-gm = "X-GM-MSGID" SP number64 /
+///                "X-GM-THRID" SP number64 /
+///                "X-GM-LABELS" SP x-gm-label-list
+/// ```
+pub(crate) fn msg_att_x_gm(input: &[u8]) -> IMAPResult<&[u8], MessageDataItem> {
+    alt((
+        map(
+            preceded(tag_no_case(b"X-GM-MSGID "), number64),
+            MessageDataItem::XGmMsgId,
+        ),
+        map(
+            preceded(tag_no_case(b"X-GM-THRID "), number64),
+            MessageDataItem::XGmThrId,
+        ),
+        map(
+            preceded(tag_no_case(b"X-GM-LABELS "), x_gm_label_list),
+            MessageDataItem::XGmLabels,
+        ),
+    ))(input)
+}
+
+/// ```abnf
+/// x-gm-label-list = "(" [x-gm-label *(SP x-gm-label)] ")"
+/// ```
+pub(crate) fn x_gm_label_list(input: &[u8]) -> IMAPResult<&[u8], Vec<GmailLabel>> {
+    map(
+        delimited(tag(b"("), opt(separated_list1(sp, x_gm_label)), tag(b")")),
+        Option::unwrap_or_default,
+    )(input)
+}
+
+/// ```abnf
+/// x-gm-label = "\" atom / astring
+/// ```
+pub(crate) fn x_gm_label(input: &[u8]) -> IMAPResult<&[u8], GmailLabel> {
+    alt((
+        map(preceded(tag(b"\\"), atom), GmailLabel::System),
+        map(astring, GmailLabel::User),
+    ))(input)
+}
+
+impl<'a> EncodeIntoContext for GmailLabel<'a> {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        match self {
+            GmailLabel::System(atom) => {
+                ctx.write_all(b"\\")?;
+                atom.encode_ctx(ctx)
+            }
+            GmailLabel::User(astring) => astring.encode_ctx(ctx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        command::{Command, CommandBody},
+        core::{AString, Atom},
+        fetch::{MessageDataItem, MessageDataItemName},
+        response::{Capability, Data, Response},
+        search::SearchKey,
+    };
+
+    use super::*;
+    use crate::testing::{kat_inverse_command, kat_inverse_response};
+
+    #[test]
+    fn test_kat_inverse_command_gmail() {
+        kat_inverse_command(&[
+            (
+                b"A FETCH 1 (X-GM-MSGID X-GM-THRID X-GM-LABELS)\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new(
+                    "A",
+                    CommandBody::fetch(
+                        "1",
+                        vec![
+                            MessageDataItemName::XGmMsgId,
+                            MessageDataItemName::XGmThrId,
+                            MessageDataItemName::XGmLabels,
+                        ],
+                        false,
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+            ),
+            (
+                b"A SEARCH X-GM-RAW \"has:attachment in:unread\"\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new(
+                    "A",
+                    CommandBody::search(
+                        None,
+                        SearchKey::XGmRaw(AString::try_from("has:attachment in:unread").unwrap())
+                            .into(),
+                        false,
+                    ),
+                )
+                .unwrap(),
+            ),
+            (
+                b"A STORE 1:2 +X-GM-LABELS (\\Important foo \"bar baz\")\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new(
+                    "A",
+                    CommandBody::store_x_gm_labels(
+                        "1:2",
+                        StoreType::Add,
+                        StoreResponse::Answer,
+                        vec![
+                            GmailLabel::System(Atom::try_from("Important").unwrap()),
+                            GmailLabel::User(AString::try_from("foo").unwrap()),
+                            GmailLabel::User(AString::try_from("bar baz").unwrap()),
+                        ],
+                        false,
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+            ),
+            (
+                b"A UID STORE 1 X-GM-LABELS.SILENT ()\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new(
+                    "A",
+                    CommandBody::store_x_gm_labels(
+                        "1",
+                        StoreType::Replace,
+                        StoreResponse::Silent,
+                        vec![],
+                        true,
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_kat_inverse_response_gmail() {
+        kat_inverse_response(&[
+            (
+                b"* CAPABILITY IMAP4REV1 X-GM-EXT-1\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(
+                    Data::capability(vec![Capability::Imap4Rev1, Capability::XGmExt1]).unwrap(),
+                ),
+            ),
+            (
+                b"* 1 FETCH (X-GM-MSGID 1278455344230334865 X-GM-THRID 1266894439832287888 X-GM-LABELS (\\Inbox \\Sent Important \"Muy Importante\"))\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(
+                    Data::fetch(
+                        1,
+                        vec![
+                            MessageDataItem::XGmMsgId(1278455344230334865),
+                            MessageDataItem::XGmThrId(1266894439832287888),
+                            MessageDataItem::XGmLabels(vec![
+                                GmailLabel::System(Atom::try_from("Inbox").unwrap()),
+                                GmailLabel::System(Atom::try_from("Sent").unwrap()),
+                                GmailLabel::User(AString::try_from("Important").unwrap()),
+                                GmailLabel::User(AString::try_from("Muy Importante").unwrap()),
+                            ]),
+                        ],
+                    )
+                    .unwrap(),
+                ),
+            ),
+            (
+                b"* 2 FETCH (X-GM-LABELS ())\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::fetch(2, vec![MessageDataItem::XGmLabels(vec![])]).unwrap()),
+            ),
+        ]);
+    }
+}
@@ -5,8 +5,10 @@
 // command_any ::= "CAPABILITY" / "LOGOUT" / "NOOP" / x_command / id
 // response_data ::= "*" SPACE (resp_cond_state / resp_cond_bye / mailbox_data / message_data / capability_data / id_response)
 
+use std::io::Write;
+
 use abnf_core::streaming::sp;
-use imap_types::core::{IString, NString};
+use imap_types::extensions::id::{IdKey, IdParameters};
 use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
@@ -18,6 +20,7 @@ use nom::{
 use crate::{
     core::{nil, nstring, string},
     decode::IMAPResult,
+    encode::{EncodeContext, EncodeIntoContext},
 };
 
 /// ```abnf
@@ -25,8 +28,7 @@ use crate::{
 /// ```
 ///
 /// Note: Updated ABNF.
-#[allow(clippy::type_complexity)]
-pub(crate) fn id(input: &[u8]) -> IMAPResult<&[u8], Option<Vec<(IString, NString)>>> {
+pub(crate) fn id(input: &[u8]) -> IMAPResult<&[u8], Option<IdParameters>> {
     preceded(tag_no_case("ID "), id_params_list)(input)
 }
 
@@ -36,8 +38,7 @@ pub(crate) fn id(input: &[u8]) -> IMAPResult<&[u8], Option<Vec<(IString, NString
 ///
 /// Note: Updated ABNF.
 #[inline]
-#[allow(clippy::type_complexity)]
-pub(crate) fn id_response(input: &[u8]) -> IMAPResult<&[u8], Option<Vec<(IString, NString)>>> {
+pub(crate) fn id_response(input: &[u8]) -> IMAPResult<&[u8], Option<IdParameters>> {
     id(input)
 }
 
@@ -46,19 +47,70 @@ pub(crate) fn id_response(input: &[u8]) -> IMAPResult<&[u8], Option<Vec<(IString
 /// ```
 ///
 /// Note: Updated ABNF. (See <https://github.com/modern-email/defects/issues/12>)
-#[allow(clippy::type_complexity)]
-pub(crate) fn id_params_list(input: &[u8]) -> IMAPResult<&[u8], Option<Vec<(IString, NString)>>> {
-    alt((
-        map(
-            delimited(
-                tag("("),
-                separated_list0(sp, separated_pair(string, sp, nstring)),
-                tag(")"),
-            ),
-            Some,
+///
+/// Note: An empty list is treated like `NIL`, i.e., both are parsed as `None`.
+///
+/// Note: The limits of RFC 2971 are not enforced when parsing (see [`IdParameters::lenient`]).
+pub(crate) fn id_params_list(input: &[u8]) -> IMAPResult<&[u8], Option<IdParameters>> {
+    let mut parser = alt((
+        delimited(
+            tag("("),
+            separated_list0(sp, separated_pair(map(string, IdKey::from), sp, nstring)),
+            tag(")"),
         ),
-        value(None, nil),
-    ))(input)
+        value(Vec::new(), nil),
+    ));
+
+    let (remaining, parameters) = parser(input)?;
+
+    if parameters.is_empty() {
+        return Ok((remaining, None));
+    }
+
+    // Be lenient: A slightly non-conforming `ID` must not break the session.
+    if let Err(error) = IdParameters::validate(&parameters) {
+        log::warn!("Accepted non-conforming ID parameters: {error}");
+    }
+
+    // Note: `parameters` is not empty.
+    Ok((remaining, Some(IdParameters::lenient(parameters).unwrap())))
+}
+
+impl<'a> EncodeIntoContext for IdParameters<'a> {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        ctx.write_all(b"(")?;
+
+        let mut parameters = self.as_ref().iter();
+
+        if let Some((key, value)) = parameters.next() {
+            key.encode_ctx(ctx)?;
+            ctx.write_all(b" ")?;
+            value.encode_ctx(ctx)?;
+        }
+
+        for (key, value) in parameters {
+            ctx.write_all(b" ")?;
+            key.encode_ctx(ctx)?;
+            ctx.write_all(b" ")?;
+            value.encode_ctx(ctx)?;
+        }
+
+        ctx.write_all(b")")
+    }
+}
+
+impl<'a> EncodeIntoContext for IdKey<'a> {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        match self {
+            IdKey::Other(other) => other.inner().encode_ctx(ctx),
+            // Note: Well-known fields are valid quoted strings.
+            well_known => {
+                ctx.write_all(b"\"")?;
+                ctx.write_all(well_known.as_ref())?;
+                ctx.write_all(b"\"")
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -76,64 +128,94 @@ mod tests {
     fn test_parse_id() {
         let got = id(b"id (\"name\" \"imap-codec\")\r\n").unwrap().1;
         assert_eq!(
-            Some(vec![(
-                IString::try_from("name").unwrap(),
+            Some(IdParameters::unvalidated(vec![(
+                IdKey::Name,
                 NString::try_from("imap-codec").unwrap()
-            )]),
+            )])),
             got
         );
     }
 
+    #[test]
+    fn test_parse_id_limits() {
+        // 30 pairs are fine ...
+        let pairs = (0..30)
+            .map(|i| format!("\"x-{i}\" NIL"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let input = format!("ID ({pairs})\r\n");
+        assert!(id(input.as_bytes()).is_ok());
+
+        // ... 31 pairs are not, but are accepted when parsing.
+        let pairs = (0..31)
+            .map(|i| format!("\"x-{i}\" NIL"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let input = format!("ID ({pairs})\r\n");
+        let parameters = id(input.as_bytes()).unwrap().1.unwrap();
+        assert_eq!(parameters.as_ref().len(), 31);
+        assert!(IdParameters::try_from(parameters.into_inner()).is_err());
+
+        let input = format!("ID (\"{}\" NIL)\r\n", "x".repeat(31));
+        assert!(id(input.as_bytes()).unwrap().1.is_some());
+
+        let input = format!("ID (\"name\" \"{}\")\r\n", "x".repeat(1025));
+        assert!(id(input.as_bytes()).unwrap().1.is_some());
+
+        let input = b"ID (\"name\" \"a\" \"NAME\" \"b\")\r\n";
+        let parameters = id(input).unwrap().1.unwrap();
+        assert_eq!(
+            parameters.get(&IdKey::Name),
+            Some(&NString::try_from("a").unwrap())
+        );
+        assert!(IdParameters::try_from(parameters.into_inner()).is_err());
+    }
+
     #[test]
     fn test_kat_inverse_command_id() {
         kat_inverse_command(&[
             (
                 b"A ID nil\r\n".as_ref(),
                 b"".as_ref(),
-                Command::new("A", CommandBody::Id { parameters: None }).unwrap(),
+                Command::new("A", CommandBody::id(None)).unwrap(),
             ),
             (
                 b"A ID NIL\r\n".as_ref(),
                 b"".as_ref(),
-                Command::new("A", CommandBody::Id { parameters: None }).unwrap(),
+                Command::new("A", CommandBody::id(None)).unwrap(),
             ),
-            #[cfg(not(feature = "quirk_id_empty_to_nil"))]
             (
                 b"A ID ()\r\n".as_ref(),
                 b"".as_ref(),
-                Command::new(
-                    "A",
-                    CommandBody::Id {
-                        parameters: Some(vec![]),
-                    },
-                )
-                .unwrap(),
+                Command::new("A", CommandBody::id(None)).unwrap(),
             ),
             (
                 b"A ID (\"\" \"\")\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
-                    CommandBody::Id {
-                        parameters: Some(vec![(
-                            IString::try_from("").unwrap(),
+                    CommandBody::id(Some(
+                        IdParameters::try_from(vec![(
+                            IdKey::try_from("").unwrap(),
                             NString::try_from("").unwrap(),
-                        )]),
-                    },
+                        )])
+                        .unwrap(),
+                    )),
                 )
                 .unwrap(),
             ),
             (
-                b"A ID (\"name\" \"imap-codec\")\r\n".as_ref(),
+                b"A ID (\"NAME\" \"imap-codec\" \"x-foo\" NIL)\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
-                    CommandBody::Id {
-                        parameters: Some(vec![(
-                            IString::try_from("name").unwrap(),
-                            NString::try_from("imap-codec").unwrap(),
-                        )]),
-                    },
+                    CommandBody::id(Some(
+                        IdParameters::try_from(vec![
+                            (IdKey::Name, NString::try_from("imap-codec").unwrap()),
+                            (IdKey::try_from("x-foo").unwrap(), NString(None)),
+                        ])
+                        .unwrap(),
+                    )),
                 )
                 .unwrap(),
             ),
@@ -142,10 +224,56 @@ mod tests {
 
     #[test]
     fn test_kat_inverse_response_id() {
-        kat_inverse_response(&[(
-            b"* ID nil\r\n".as_ref(),
-            b"".as_ref(),
-            Response::Data(Data::Id { parameters: None }),
-        )]);
+        kat_inverse_response(&[
+            (
+                b"* ID nil\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::id(None)),
+            ),
+            (
+                b"* ID ()\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::id(None)),
+            ),
+            (
+                b"* ID (\"name\" \"Cyrus\" \"version\" \"1.5\" \"os\" \"sunos\" \"os-version\" \"5.5\" \"support-url\" \"mailto:cyrus-bugs+@andrew.cmu.edu\")\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::id(Some(
+                    IdParameters::try_from(vec![
+                        (IdKey::Name, NString::try_from("Cyrus").unwrap()),
+                        (IdKey::Version, NString::try_from("1.5").unwrap()),
+                        (IdKey::Os, NString::try_from("sunos").unwrap()),
+                        (IdKey::OsVersion, NString::try_from("5.5").unwrap()),
+                        (
+                            IdKey::SupportUrl,
+                            NString::try_from("mailto:cyrus-bugs+@andrew.cmu.edu").unwrap(),
+                        ),
+                    ])
+                    .unwrap(),
+                ))),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_id_parameters_get() {
+        let parameters = IdParameters::try_from(vec![
+            (IdKey::Name, NString::try_from("imap-codec").unwrap()),
+            (
+                IdKey::from(IString::try_from("X-Foo").unwrap()),
+                NString::try_from("bar").unwrap(),
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            parameters.get(&IdKey::Name),
+            Some(&NString::try_from("imap-codec").unwrap())
+        );
+        assert_eq!(
+            parameters.get(&IdKey::try_from("x-foo").unwrap()),
+            Some(&NString::try_from("bar").unwrap())
+        );
+        assert_eq!(parameters.get(&IdKey::Vendor), None);
     }
 }
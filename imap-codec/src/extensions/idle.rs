@@ -9,16 +9,13 @@
 
 use std::io::Write;
 
-#[cfg(not(feature = "quirk_crlf_relaxed"))]
-use abnf_core::streaming::crlf;
-#[cfg(feature = "quirk_crlf_relaxed")]
-use abnf_core::streaming::crlf_relaxed as crlf;
 use imap_types::{command::CommandBody, extensions::idle::IdleDone};
 use nom::{bytes::streaming::tag_no_case, combinator::value, sequence::tuple};
 
 use crate::{
     decode::IMAPResult,
     encode::{EncodeContext, EncodeIntoContext},
+    quirks::crlf,
 };
 
 /// `idle = "IDLE" CRLF "DONE"` (edited)
@@ -66,7 +63,7 @@ mod tests {
 
     use super::*;
     use crate::{
-        decode::{Decoder, IdleDoneDecodeError},
+        decode::{DecodeFailure, DecodeFailureKind, Decoder, IdleDoneDecodeError},
         testing::kat_inverse_command,
         IdleDoneCodec,
     };
@@ -100,10 +97,38 @@ mod tests {
             (b"done".as_ref(), Err(IdleDoneDecodeError::Incomplete)),
             (b"done\r".as_ref(), Err(IdleDoneDecodeError::Incomplete)),
             // Failed
-            (b"donee\r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
-            (b" done\r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
-            (b"done \r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
-            (b" done \r\n".as_ref(), Err(IdleDoneDecodeError::Failed)),
+            (
+                b"donee\r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 4,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b" done\r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b"done \r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 4,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
+            (
+                b" done \r\n".as_ref(),
+                Err(IdleDoneDecodeError::Failed(DecodeFailure {
+                    offset: 0,
+                    rule: None,
+                    kind: DecodeFailureKind::Syntax,
+                })),
+            ),
         ];
 
         for (test, expected) in tests {
@@ -15,14 +15,13 @@ use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
     combinator::{map, opt, value},
-    error::ErrorKind,
     multi::separated_list1,
     sequence::{delimited, preceded, separated_pair, tuple},
 };
 
 use crate::{
     core::{astring, nstring, number},
-    decode::{IMAPErrorKind, IMAPParseError, IMAPResult},
+    decode::IMAPResult,
     encode::{utils::join_serializable, EncodeContext, EncodeIntoContext},
     extensions::binary::literal8,
     mailbox::mailbox,
@@ -79,17 +78,17 @@ pub(crate) fn entry_value(input: &[u8]) -> IMAPResult<&[u8], EntryValue> {
 /// ```abnf
 /// entry = astring
 /// ```
+///
+/// Note: Entries violating the rules of RFC 5464 are accepted (see [`Entry::lenient`]).
 pub(crate) fn entry(input: &[u8]) -> IMAPResult<&[u8], Entry> {
     let (rem, astring) = astring(input)?;
 
-    if let Ok(entry) = Entry::try_from(astring) {
-        Ok((rem, entry))
-    } else {
-        Err(nom::Err::Failure(IMAPParseError {
-            input,
-            kind: IMAPErrorKind::Nom(ErrorKind::Verify),
-        }))
+    // Be lenient: A single non-conforming entry must not break a whole `METADATA` response.
+    if let Err(error) = Entry::validate(astring.as_ref()) {
+        log::warn!("Accepted non-conforming metadata entry: {error}");
     }
+
+    Ok((rem, Entry::lenient(astring)))
 }
 
 /// ```abnf
@@ -328,14 +327,15 @@ mod tests {
     fn test_kat_inverse_command_setmetadata() {
         kat_inverse_command(&[
             (
-                b"A SETMETADATA \"\" (/test nil)\r\n".as_ref(),
+                b"A SETMETADATA \"\" (/shared/test nil)\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
                     CommandBody::SetMetadata {
                         mailbox: Mailbox::Other(MailboxOther::try_from("").unwrap()),
                         entry_values: Vec1::try_from(vec![EntryValue {
-                            entry: Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            entry: Entry::try_from(AString::try_from("/shared/test").unwrap())
+                                .unwrap(),
                             value: NString8::NString(NString(None)),
                         }])
                         .unwrap(),
@@ -344,14 +344,15 @@ mod tests {
                 .unwrap(),
             ),
             (
-                b"A SETMETADATA \"\" (/test \"test\")\r\n".as_ref(),
+                b"A SETMETADATA \"\" (/shared/test \"test\")\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
                     CommandBody::SetMetadata {
                         mailbox: Mailbox::Other(MailboxOther::try_from("").unwrap()),
                         entry_values: Vec1::try_from(vec![EntryValue {
-                            entry: Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            entry: Entry::try_from(AString::try_from("/shared/test").unwrap())
+                                .unwrap(),
                             value: NString8::NString(NString(Some(
                                 IString::try_from("test").unwrap(),
                             ))),
@@ -362,14 +363,15 @@ mod tests {
                 .unwrap(),
             ),
             (
-                b"A SETMETADATA \"\" (/test ~{4+}\r\nt\x00st)\r\n".as_ref(),
+                b"A SETMETADATA \"\" (/shared/test ~{4+}\r\nt\x00st)\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
                     CommandBody::SetMetadata {
                         mailbox: Mailbox::Other(MailboxOther::try_from("").unwrap()),
                         entry_values: Vec1::try_from(vec![EntryValue {
-                            entry: Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            entry: Entry::try_from(AString::try_from("/shared/test").unwrap())
+                                .unwrap(),
                             value: NString8::Literal8(Literal8 {
                                 data: b"t\x00st".as_ref().into(),
                                 mode: LiteralMode::NonSync,
@@ -387,7 +389,7 @@ mod tests {
     fn test_kat_inverse_command_getmetadata() {
         kat_inverse_command(&[
             (
-                b"A GETMETADATA \"\" /test\r\n".as_ref(),
+                b"A GETMETADATA \"\" /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -395,14 +397,14 @@ mod tests {
                         options: vec![],
                         mailbox: Mailbox::Other(MailboxOther::try_from("").unwrap()),
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
                 .unwrap(),
             ),
             (
-                b"A GETMETADATA INBOX /test\r\n".as_ref(),
+                b"A GETMETADATA INBOX /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -410,14 +412,14 @@ mod tests {
                         options: vec![],
                         mailbox: Mailbox::Inbox,
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
                 .unwrap(),
             ),
             (
-                b"A GETMETADATA (MAXSIZE 0) INBOX /test\r\n".as_ref(),
+                b"A GETMETADATA (MAXSIZE 0) INBOX /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -425,14 +427,14 @@ mod tests {
                         options: vec![GetMetadataOption::MaxSize(0)],
                         mailbox: Mailbox::Inbox,
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
                 .unwrap(),
             ),
             (
-                b"A GETMETADATA (MAXSIZE 1337) INBOX /test\r\n".as_ref(),
+                b"A GETMETADATA (MAXSIZE 1337) INBOX /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -440,14 +442,14 @@ mod tests {
                         options: vec![GetMetadataOption::MaxSize(1337)],
                         mailbox: Mailbox::Inbox,
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
                 .unwrap(),
             ),
             (
-                b"A GETMETADATA (DEPTH 0) INBOX /test\r\n".as_ref(),
+                b"A GETMETADATA (DEPTH 0) INBOX /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -455,14 +457,14 @@ mod tests {
                         options: vec![GetMetadataOption::Depth(Depth::Null)],
                         mailbox: Mailbox::Inbox,
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
                 .unwrap(),
             ),
             (
-                b"A GETMETADATA (DEPTH 1) INBOX /test\r\n".as_ref(),
+                b"A GETMETADATA (DEPTH 1) INBOX /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -470,14 +472,14 @@ mod tests {
                         options: vec![GetMetadataOption::Depth(Depth::One)],
                         mailbox: Mailbox::Inbox,
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
                 .unwrap(),
             ),
             (
-                b"A GETMETADATA (DEPTH infinity) INBOX /test\r\n".as_ref(),
+                b"A GETMETADATA (DEPTH infinity) INBOX /shared/test\r\n".as_ref(),
                 b"".as_ref(),
                 Command::new(
                     "A",
@@ -485,7 +487,7 @@ mod tests {
                         options: vec![GetMetadataOption::Depth(Depth::Infinity)],
                         mailbox: Mailbox::Inbox,
                         entries: Vec1::from(
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
                         ),
                     },
                 )
@@ -498,27 +500,28 @@ mod tests {
     fn test_kat_inverse_response_metadata() {
         kat_inverse_response(&[
             (
-                b"* metadata INBOX /test /xxx\r\n".as_ref(),
+                b"* metadata INBOX /shared/test /private/xxx\r\n".as_ref(),
                 b"".as_ref(),
                 Response::Data(Data::Metadata {
                     mailbox: Mailbox::Inbox,
                     items: MetadataResponse::WithoutValues(
                         Vec1::try_from(vec![
-                            Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
-                            Entry::try_from(AString::try_from("/xxx").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/shared/test").unwrap()).unwrap(),
+                            Entry::try_from(AString::try_from("/private/xxx").unwrap()).unwrap(),
                         ])
                         .unwrap(),
                     ),
                 }),
             ),
             (
-                b"* metadata INBOX (/test {4}\r\nABCD)\r\n".as_ref(),
+                b"* metadata INBOX (/shared/test {4}\r\nABCD)\r\n".as_ref(),
                 b"".as_ref(),
                 Response::Data(Data::Metadata {
                     mailbox: Mailbox::Inbox,
                     items: MetadataResponse::WithValues(
                         Vec1::try_from(vec![EntryValue {
-                            entry: Entry::try_from(AString::try_from("/test").unwrap()).unwrap(),
+                            entry: Entry::try_from(AString::try_from("/shared/test").unwrap())
+                                .unwrap(),
                             value: NString8::NString(NString(Some(IString::Literal(
                                 Literal::try_from("ABCD").unwrap(),
                             )))),
@@ -527,6 +530,21 @@ mod tests {
                     ),
                 }),
             ),
+            // Non-conforming entries are accepted.
+            (
+                b"* METADATA INBOX /Shared/comment /vendor/x\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::Metadata {
+                    mailbox: Mailbox::Inbox,
+                    items: MetadataResponse::WithoutValues(
+                        Vec1::try_from(vec![
+                            Entry::try_from("/Shared/comment").unwrap(),
+                            Entry::lenient(AString::try_from("/vendor/x").unwrap()),
+                        ])
+                        .unwrap(),
+                    ),
+                }),
+            ),
         ]);
     }
 
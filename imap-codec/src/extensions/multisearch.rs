@@ -0,0 +1,430 @@
+//! IMAP MULTISEARCH Extension
+
+use std::io::Write;
+
+use abnf_core::streaming::{dquote, sp};
+use imap_types::{
+    command::CommandBody,
+    core::{Charset, Vec1},
+    extensions::multisearch::{
+        SearchCorrelator, SearchReturnData, SearchReturnOption, SourceMailboxes,
+    },
+    mailbox::Mailbox,
+    response::Data,
+    search::SearchKey,
+};
+use nom::{
+    branch::alt,
+    bytes::streaming::{tag, tag_no_case},
+    combinator::{map, opt, value},
+    multi::{many0, separated_list0, separated_list1},
+    sequence::{delimited, preceded, terminated, tuple},
+};
+
+use crate::{
+    core::{charset, number, nz_number, tag_imap},
+    decode::IMAPResult,
+    encode::{utils::join_serializable, EncodeContext, EncodeIntoContext},
+    mailbox::mailbox,
+    search::search_key,
+    sequence::sequence_set,
+};
+
+/// ```abnf
+/// esearch = "ESEARCH" [SP esearch-source-opts] [SP search-return-opts] SP search-program
+/// ```
+pub(crate) fn esearch(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
+    let mut parser = tuple((
+        tag_no_case(b"ESEARCH"),
+        opt(preceded(sp, esearch_source_opts)),
+        opt(preceded(sp, search_return_opts)),
+        sp,
+        search_program,
+    ));
+
+    let (remaining, (_, source, return_options, _, (charset, criteria))) = parser(input)?;
+
+    Ok((
+        remaining,
+        CommandBody::ESearch {
+            source,
+            return_options: return_options.unwrap_or_default(),
+            charset,
+            criteria,
+        },
+    ))
+}
+
+/// ```abnf
+/// esearch-source-opts = "IN" SP "(" source-mbox ")"
+///
+/// source-mbox = filter-mailboxes *(SP filter-mailboxes)
+/// ```
+///
+/// Note: `scope-options` are not supported.
+pub(crate) fn esearch_source_opts(input: &[u8]) -> IMAPResult<&[u8], Vec1<SourceMailboxes>> {
+    delimited(
+        tag_no_case(b"IN ("),
+        map(separated_list1(sp, filter_mailboxes), Vec1::unvalidated),
+        tag(b")"),
+    )(input)
+}
+
+/// ```abnf
+/// filter-mailboxes-other = "inboxes" /
+///                          "personal" /
+///                          "subscribed" /
+///                          ("subtree" SP one-or-more-mailbox) /
+///                          ("subtree-one" SP one-or-more-mailbox) / ; RFC 7377
+///                          ("mailboxes" SP one-or-more-mailbox)
+/// ```
+///
+/// Note: `filter-mailboxes-selected` is not supported.
+pub(crate) fn filter_mailboxes(input: &[u8]) -> IMAPResult<&[u8], SourceMailboxes> {
+    alt((
+        value(SourceMailboxes::Inboxes, tag_no_case(b"inboxes")),
+        value(SourceMailboxes::Personal, tag_no_case(b"personal")),
+        value(SourceMailboxes::Subscribed, tag_no_case(b"subscribed")),
+        map(
+            preceded(tag_no_case(b"subtree "), one_or_more_mailbox),
+            SourceMailboxes::Subtree,
+        ),
+        map(
+            preceded(tag_no_case(b"subtree-one "), one_or_more_mailbox),
+            SourceMailboxes::SubtreeOne,
+        ),
+        map(
+            preceded(tag_no_case(b"mailboxes "), one_or_more_mailbox),
+            SourceMailboxes::Mailboxes,
+        ),
+    ))(input)
+}
+
+/// ```abnf
+/// one-or-more-mailbox = mailbox / many-mailboxes
+///
+/// many-mailboxes = "(" mailbox *(SP mailbox) ")"
+/// ```
+pub(crate) fn one_or_more_mailbox(input: &[u8]) -> IMAPResult<&[u8], Vec1<Mailbox>> {
+    alt((
+        map(
+            delimited(tag(b"("), separated_list1(sp, mailbox), tag(b")")),
+            Vec1::unvalidated,
+        ),
+        map(mailbox, Vec1::from),
+    ))(input)
+}
+
+/// ```abnf
+/// search-return-opts = "RETURN" SP "(" [search-return-opt *(SP search-return-opt)] ")"
+/// ```
+pub(crate) fn search_return_opts(input: &[u8]) -> IMAPResult<&[u8], Vec<SearchReturnOption>> {
+    delimited(
+        tag_no_case(b"RETURN ("),
+        separated_list0(sp, search_return_opt),
+        tag(b")"),
+    )(input)
+}
+
+/// ```abnf
+/// search-return-opt = "MIN" / "MAX" / "ALL" / "COUNT"
+/// ```
+pub(crate) fn search_return_opt(input: &[u8]) -> IMAPResult<&[u8], SearchReturnOption> {
+    alt((
+        value(SearchReturnOption::Min, tag_no_case(b"MIN")),
+        value(SearchReturnOption::Max, tag_no_case(b"MAX")),
+        value(SearchReturnOption::All, tag_no_case(b"ALL")),
+        value(SearchReturnOption::Count, tag_no_case(b"COUNT")),
+    ))(input)
+}
+
+/// ```abnf
+/// search-program = ["CHARSET" SP charset SP] search-key *(SP search-key)
+/// ```
+#[allow(clippy::type_complexity)]
+pub(crate) fn search_program(
+    input: &[u8],
+) -> IMAPResult<&[u8], (Option<Charset>, Vec1<SearchKey>)> {
+    tuple((
+        opt(terminated(preceded(tag_no_case(b"CHARSET "), charset), sp)),
+        map(separated_list1(sp, search_key(9)), Vec1::unvalidated),
+    ))(input)
+}
+
+/// ```abnf
+/// esearch-response = "ESEARCH" [search-correlator] [SP "UID"] *(SP search-return-data)
+/// ```
+pub(crate) fn esearch_response(input: &[u8]) -> IMAPResult<&[u8], Data> {
+    let mut parser = tuple((
+        tag_no_case(b"ESEARCH"),
+        opt(search_correlator),
+        map(opt(tag_no_case(b" UID")), |uid| uid.is_some()),
+        many0(preceded(sp, search_return_data)),
+    ));
+
+    let (remaining, (_, correlator, uid, data)) = parser(input)?;
+
+    Ok((
+        remaining,
+        Data::ESearch {
+            correlator,
+            uid,
+            data,
+        },
+    ))
+}
+
+/// ```abnf
+/// search-correlator = SP "(" "TAG" SP tag-string
+///                     [SP "MAILBOX" SP astring SP "UIDVALIDITY" SP nz-number] ")"
+///
+/// tag-string = string
+/// ```
+///
+/// Note: `tag-string` is only supported as a quoted string.
+pub(crate) fn search_correlator(input: &[u8]) -> IMAPResult<&[u8], SearchCorrelator> {
+    let mut parser = delimited(
+        tag_no_case(b" (TAG "),
+        tuple((
+            delimited(dquote, tag_imap, dquote),
+            opt(tuple((
+                preceded(tag_no_case(b" MAILBOX "), mailbox),
+                preceded(tag_no_case(b" UIDVALIDITY "), nz_number),
+            ))),
+        )),
+        tag(b")"),
+    );
+
+    let (remaining, (tag, mailbox)) = parser(input)?;
+
+    Ok((remaining, SearchCorrelator { tag, mailbox }))
+}
+
+/// ```abnf
+/// search-return-data = "MIN" SP nz-number /
+///                      "MAX" SP nz-number /
+///                      "ALL" SP sequence-set /
+///                      "COUNT" SP number
+/// ```
+pub(crate) fn search_return_data(input: &[u8]) -> IMAPResult<&[u8], SearchReturnData> {
+    alt((
+        map(
+            preceded(tag_no_case(b"MIN "), nz_number),
+            SearchReturnData::Min,
+        ),
+        map(
+            preceded(tag_no_case(b"MAX "), nz_number),
+            SearchReturnData::Max,
+        ),
+        map(
+            preceded(tag_no_case(b"ALL "), sequence_set),
+            SearchReturnData::All,
+        ),
+        map(
+            preceded(tag_no_case(b"COUNT "), number),
+            SearchReturnData::Count,
+        ),
+    ))(input)
+}
+
+impl<'a> EncodeIntoContext for SourceMailboxes<'a> {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        fn encode_one_or_more_mailbox(
+            mailboxes: &Vec1<Mailbox>,
+            ctx: &mut EncodeContext,
+        ) -> std::io::Result<()> {
+            if mailboxes.as_ref().len() == 1 {
+                mailboxes.as_ref()[0].encode_ctx(ctx)
+            } else {
+                ctx.write_all(b"(")?;
+                join_serializable(mailboxes.as_ref(), b" ", ctx)?;
+                ctx.write_all(b")")
+            }
+        }
+
+        match self {
+            Self::Personal => ctx.write_all(b"personal"),
+            Self::Inboxes => ctx.write_all(b"inboxes"),
+            Self::Subscribed => ctx.write_all(b"subscribed"),
+            Self::Subtree(mailboxes) => {
+                ctx.write_all(b"subtree ")?;
+                encode_one_or_more_mailbox(mailboxes, ctx)
+            }
+            Self::SubtreeOne(mailboxes) => {
+                ctx.write_all(b"subtree-one ")?;
+                encode_one_or_more_mailbox(mailboxes, ctx)
+            }
+            Self::Mailboxes(mailboxes) => {
+                ctx.write_all(b"mailboxes ")?;
+                encode_one_or_more_mailbox(mailboxes, ctx)
+            }
+        }
+    }
+}
+
+impl EncodeIntoContext for SearchReturnOption {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        match self {
+            Self::Min => ctx.write_all(b"MIN"),
+            Self::Max => ctx.write_all(b"MAX"),
+            Self::All => ctx.write_all(b"ALL"),
+            Self::Count => ctx.write_all(b"COUNT"),
+        }
+    }
+}
+
+impl<'a> EncodeIntoContext for SearchCorrelator<'a> {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        ctx.write_all(b"(TAG \"")?;
+        self.tag.encode_ctx(ctx)?;
+        ctx.write_all(b"\"")?;
+
+        if let Some((mailbox, uid_validity)) = &self.mailbox {
+            ctx.write_all(b" MAILBOX ")?;
+            mailbox.encode_ctx(ctx)?;
+            ctx.write_all(b" UIDVALIDITY ")?;
+            uid_validity.encode_ctx(ctx)?;
+        }
+
+        ctx.write_all(b")")
+    }
+}
+
+impl EncodeIntoContext for SearchReturnData {
+    fn encode_ctx(&self, ctx: &mut EncodeContext) -> std::io::Result<()> {
+        match self {
+            Self::Min(min) => write!(ctx, "MIN {min}"),
+            Self::Max(max) => write!(ctx, "MAX {max}"),
+            Self::All(sequence_set) => {
+                ctx.write_all(b"ALL ")?;
+                sequence_set.encode_ctx(ctx)
+            }
+            Self::Count(count) => write!(ctx, "COUNT {count}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        command::{Command, CommandBody},
+        core::Tag,
+        response::Response,
+        search::SearchKey,
+    };
+
+    use super::*;
+    use crate::testing::{kat_inverse_command, kat_inverse_response};
+
+    #[test]
+    fn test_kat_inverse_command_esearch() {
+        kat_inverse_command(&[
+            (
+                b"A ESEARCH UNSEEN\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new(
+                    "A",
+                    CommandBody::esearch(None, vec![], None, Vec1::from(SearchKey::Unseen)),
+                )
+                .unwrap(),
+            ),
+            (
+                b"tag1 ESEARCH IN (mailboxes folder1 subtree-one folder2) UNSEEN\r\n?",
+                b"?",
+                Command::new(
+                    "tag1",
+                    CommandBody::esearch(
+                        Some(
+                            vec![
+                                SourceMailboxes::Mailboxes(Vec1::from(
+                                    Mailbox::try_from("folder1").unwrap(),
+                                )),
+                                SourceMailboxes::SubtreeOne(Vec1::from(
+                                    Mailbox::try_from("folder2").unwrap(),
+                                )),
+                            ]
+                            .try_into()
+                            .unwrap(),
+                        ),
+                        vec![],
+                        None,
+                        Vec1::from(SearchKey::Unseen),
+                    ),
+                )
+                .unwrap(),
+            ),
+            (
+                b"tag1 ESEARCH IN (personal subtree (INBOX Archive)) RETURN (MIN MAX COUNT) CHARSET UTF-8 UNSEEN DELETED\r\n",
+                b"",
+                Command::new(
+                    "tag1",
+                    CommandBody::esearch(
+                        Some(
+                            vec![
+                                SourceMailboxes::Personal,
+                                SourceMailboxes::Subtree(
+                                    vec![Mailbox::Inbox, Mailbox::try_from("Archive").unwrap()]
+                                        .try_into()
+                                        .unwrap(),
+                                ),
+                            ]
+                            .try_into()
+                            .unwrap(),
+                        ),
+                        vec![
+                            SearchReturnOption::Min,
+                            SearchReturnOption::Max,
+                            SearchReturnOption::Count,
+                        ],
+                        Some(Charset::try_from("UTF-8").unwrap()),
+                        vec![SearchKey::Unseen, SearchKey::Deleted]
+                            .try_into()
+                            .unwrap(),
+                    ),
+                )
+                .unwrap(),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_kat_inverse_response_esearch() {
+        kat_inverse_response(&[
+            (
+                b"* ESEARCH\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::esearch(None, false, vec![])),
+            ),
+            (
+                b"* ESEARCH (TAG \"tag1\" MAILBOX folder1 UIDVALIDITY 1) UID ALL 4001,4003:4005\r\n",
+                b"",
+                Response::Data(Data::esearch(
+                    Some(SearchCorrelator {
+                        tag: Tag::try_from("tag1").unwrap(),
+                        mailbox: Some((
+                            Mailbox::try_from("folder1").unwrap(),
+                            1.try_into().unwrap(),
+                        )),
+                    }),
+                    true,
+                    vec![SearchReturnData::All("4001,4003:4005".try_into().unwrap())],
+                )),
+            ),
+            (
+                b"* ESEARCH (TAG \"A282\") MIN 2 COUNT 3\r\n",
+                b"",
+                Response::Data(Data::esearch(
+                    Some(SearchCorrelator {
+                        tag: Tag::try_from("A282").unwrap(),
+                        mailbox: None,
+                    }),
+                    false,
+                    vec![
+                        SearchReturnData::Min(2.try_into().unwrap()),
+                        SearchReturnData::Count(3),
+                    ],
+                )),
+            ),
+        ]);
+    }
+}
@@ -0,0 +1,104 @@
+//! IMAP4 Login Referrals and IMAP4 Mailbox Referrals
+
+use std::str::from_utf8;
+
+use imap_types::{extensions::referral::ImapUrl, response::Code, utils::indicators::is_text_char};
+use nom::{
+    bytes::streaming::{tag_no_case, take_while1},
+    error::ErrorKind,
+    sequence::preceded,
+};
+
+use crate::decode::{IMAPErrorKind, IMAPParseError, IMAPResult};
+
+/// ```abnf
+/// resp-text-code =/ "REFERRAL" SP imapurl
+/// ```
+///
+/// Note: An unsupported (or invalid) IMAP URL is not an error and results in [`Code::Other`].
+pub(crate) fn resp_code_referral(input: &[u8]) -> IMAPResult<&[u8], Code> {
+    let (remaining, url) = preceded(
+        tag_no_case(b"REFERRAL "),
+        take_while1(|b| is_text_char(b) && b != b']'),
+    )(input)?;
+
+    // # Safety
+    //
+    // `is_text_char` makes sure that the sequence of bytes
+    // is always valid ASCII. Thus, it is also valid UTF-8.
+    match ImapUrl::try_from(from_utf8(url).unwrap()) {
+        Ok(url) => Ok((remaining, Code::Referral(url))),
+        Err(_) => Err(nom::Err::Error(IMAPParseError {
+            input,
+            rule: None,
+            kind: IMAPErrorKind::Nom(ErrorKind::Verify),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        core::Tag,
+        mailbox::Mailbox,
+        response::{CodeOther, Response, Status},
+    };
+
+    use super::*;
+    use crate::testing::kat_inverse_response;
+
+    #[test]
+    fn test_kat_inverse_response_referral() {
+        kat_inverse_response(&[
+            (
+                b"A001 NO [REFERRAL imap://user;AUTH=*@SERVER2/] Try SERVER2.\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Status(
+                    Status::no(
+                        Some(Tag::try_from("A001").unwrap()),
+                        Some(Code::Referral(
+                            ImapUrl::try_from("imap://user;AUTH=*@SERVER2/").unwrap(),
+                        )),
+                        "Try SERVER2.",
+                    )
+                    .unwrap(),
+                ),
+            ),
+            (
+                b"A002 NO [REFERRAL imap://SERVER2:1143/SHARED/FOO] Remote mailbox.\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Status(
+                    Status::no(
+                        Some(Tag::try_from("A002").unwrap()),
+                        Some(Code::Referral(
+                            ImapUrl::new(
+                                "SERVER2",
+                                Some(1143),
+                                None,
+                                None,
+                                Some(Mailbox::try_from("SHARED/FOO").unwrap()),
+                            )
+                            .unwrap(),
+                        )),
+                        "Remote mailbox.",
+                    )
+                    .unwrap(),
+                ),
+            ),
+            (
+                b"* OK [REFERRAL imap://SERVER2/INBOX;UID=1] ...\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Status(
+                    Status::ok(
+                        None,
+                        Some(Code::Other(CodeOther::unvalidated(
+                            b"REFERRAL imap://SERVER2/INBOX;UID=1".as_ref(),
+                        ))),
+                        "...",
+                    )
+                    .unwrap(),
+                ),
+            ),
+        ]);
+    }
+}
@@ -122,6 +122,7 @@ pub(crate) fn thread_list_limited(
     if remaining_recursion == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
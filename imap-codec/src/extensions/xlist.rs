@@ -0,0 +1,108 @@
+//! The (legacy) IMAP XLIST command
+
+use abnf_core::streaming::sp;
+use imap_types::{command::CommandBody, response::Data};
+use nom::{
+    bytes::streaming::tag_no_case,
+    combinator::map,
+    sequence::{preceded, tuple},
+};
+
+use crate::{
+    decode::IMAPResult,
+    mailbox::{list_mailbox, mailbox, mailbox_list},
+};
+
+/// ```abnf
+/// xlist = "XLIST" SP mailbox SP list-mailbox
+/// ```
+pub(crate) fn xlist(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
+    let mut parser = tuple((tag_no_case(b"XLIST "), mailbox, sp, list_mailbox));
+
+    let (remaining, (_, reference, _, mailbox_wildcard)) = parser(input)?;
+
+    Ok((
+        remaining,
+        CommandBody::XList {
+            reference,
+            mailbox_wildcard,
+        },
+    ))
+}
+
+/// ```abnf
+/// xlist-data = "XLIST" SP mailbox-list
+/// ```
+pub(crate) fn xlist_data(input: &[u8]) -> IMAPResult<&[u8], Data> {
+    map(
+        preceded(tag_no_case(b"XLIST "), mailbox_list),
+        |(items, delimiter, mailbox)| Data::XList {
+            items: items.unwrap_or_default(),
+            delimiter,
+            mailbox,
+        },
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        command::{Command, CommandBody},
+        core::{Atom, QuotedChar},
+        flag::FlagNameAttribute,
+        mailbox::Mailbox,
+        response::{Capability, Data, Response},
+    };
+
+    use crate::testing::{kat_inverse_command, kat_inverse_response};
+
+    #[test]
+    fn test_kat_inverse_command_xlist() {
+        kat_inverse_command(&[
+            (
+                b"A XLIST \"\" *\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new("A", CommandBody::xlist("", "*").unwrap()).unwrap(),
+            ),
+            (
+                b"A XLIST INBOX %\r\n".as_ref(),
+                b"".as_ref(),
+                Command::new("A", CommandBody::xlist("INBOX", "%").unwrap()).unwrap(),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_kat_inverse_response_xlist() {
+        kat_inverse_response(&[
+            (
+                b"* CAPABILITY IMAP4REV1 XLIST\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(
+                    Data::capability(vec![Capability::Imap4Rev1, Capability::XList]).unwrap(),
+                ),
+            ),
+            (
+                b"* XLIST (\\HasNoChildren \\Sent) \"/\" \"[Gmail]/Sent Mail\"\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::XList {
+                    items: vec![
+                        FlagNameAttribute::from(Atom::try_from("HasNoChildren").unwrap()),
+                        FlagNameAttribute::from(Atom::try_from("Sent").unwrap()),
+                    ],
+                    delimiter: Some(QuotedChar::try_from('/').unwrap()),
+                    mailbox: Mailbox::try_from("[Gmail]/Sent Mail").unwrap(),
+                }),
+            ),
+            (
+                b"* XLIST () NIL INBOX\r\n".as_ref(),
+                b"".as_ref(),
+                Response::Data(Data::XList {
+                    items: vec![],
+                    delimiter: None,
+                    mailbox: Mailbox::Inbox,
+                }),
+            ),
+        ]);
+    }
+}
@@ -9,10 +9,13 @@ use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
     combinator::{map, opt, value},
+    error::context,
     multi::separated_list1,
     sequence::{delimited, preceded, tuple},
 };
 
+#[cfg(feature = "ext_gmail")]
+use crate::extensions::gmail::msg_att_x_gm;
 use crate::{
     body::body,
     core::{astring, nstring, number, nz_number},
@@ -34,7 +37,10 @@ use crate::{
 ///             "BODY.PEEK" section ["<" number "." nz-number ">"] /
 ///             "BINARY"      section-binary [partial] / ; RFC 3516
 ///             "BINARY.PEEK" section-binary [partial] / ; RFC 3516
-///             "BINARY.SIZE" section-binary             ; RFC 3516
+///             "BINARY.SIZE" section-binary /           ; RFC 3516
+///             "X-GM-MSGID" /                           ; X-GM-EXT-1
+///             "X-GM-THRID" /                           ; X-GM-EXT-1
+///             "X-GM-LABELS"                            ; X-GM-EXT-1
 /// ```
 pub(crate) fn fetch_att(input: &[u8]) -> IMAPResult<&[u8], MessageDataItemName> {
     alt((
@@ -109,23 +115,43 @@ pub(crate) fn fetch_att(input: &[u8]) -> IMAPResult<&[u8], MessageDataItemName>
         value(MessageDataItemName::Rfc822Size, tag_no_case(b"RFC822.SIZE")),
         value(MessageDataItemName::Rfc822Text, tag_no_case(b"RFC822.TEXT")),
         value(MessageDataItemName::Rfc822, tag_no_case(b"RFC822")),
+        #[cfg(feature = "ext_gmail")]
+        value(MessageDataItemName::XGmMsgId, tag_no_case(b"X-GM-MSGID")),
+        #[cfg(feature = "ext_gmail")]
+        value(MessageDataItemName::XGmThrId, tag_no_case(b"X-GM-THRID")),
+        #[cfg(feature = "ext_gmail")]
+        value(MessageDataItemName::XGmLabels, tag_no_case(b"X-GM-LABELS")),
     ))(input)
 }
 
 /// `msg-att = "("
 ///            (msg-att-dynamic / msg-att-static) *(SP (msg-att-dynamic / msg-att-static))
 ///            ")"`
+///
+/// Note: With `ext_gmail`, `msg-att-x-gm` is accepted, too.
 pub(crate) fn msg_att(input: &[u8]) -> IMAPResult<&[u8], Vec1<MessageDataItem>> {
-    delimited(
-        tag(b"("),
-        map(
-            separated_list1(sp, alt((msg_att_dynamic, msg_att_static))),
-            Vec1::unvalidated,
+    context(
+        "msg-att",
+        delimited(
+            tag(b"("),
+            map(separated_list1(sp, msg_att_item), Vec1::unvalidated),
+            tag(b")"),
         ),
-        tag(b")"),
     )(input)
 }
 
+/// `msg-att-dynamic / msg-att-static`
+///
+/// Note: With `ext_gmail`, `msg-att-x-gm` is accepted, too.
+pub(crate) fn msg_att_item(input: &[u8]) -> IMAPResult<&[u8], MessageDataItem> {
+    alt((
+        msg_att_dynamic,
+        msg_att_static,
+        #[cfg(feature = "ext_gmail")]
+        msg_att_x_gm,
+    ))(input)
+}
+
 /// `msg-att-dynamic = "FLAGS" SP "(" [flag-fetch *(SP flag-fetch)] ")"`
 ///
 /// Note: MAY change for a message
@@ -0,0 +1,693 @@
+//! Incremental parser for (large) `FETCH` responses.
+//!
+//! The [`ResponseCodec`](crate::ResponseCodec) materializes a `FETCH` response as a whole, i.e.,
+//! including all data items and literals. The [`FetchParser`] yields the data items one at a time
+//! instead. Items with a literal value, e.g., `BODY[] {4096}`, are not buffered but passed on in
+//! chunks as soon as they arrive. This allows to, e.g., stream message bodies to disk.
+//!
+//! Other responses, e.g., the tagged `OK` that completes the `FETCH` command, are reported as
+//! [`FetchEvent::NotFetch`]. Their bytes are left in the parser and can be passed on to the
+//! [`ResponseCodec`](crate::ResponseCodec) via [`FetchParser::take_remaining`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use imap_codec::fetch_parser::{FetchEvent, FetchParser};
+//!
+//! let mut parser = FetchParser::new().with_skipped_items(["BODYSTRUCTURE"]);
+//! let mut body = Vec::new();
+//!
+//! for received in [
+//!     b"* 1 FETCH (UID 42 BODYSTRUCTURE (\"TEXT\" \"PLAIN\" NIL NIL NIL \"7BIT\" 5 1) BODY[] {5}\r\nHe"
+//!         .as_ref(),
+//!     b"llo)\r\n",
+//! ] {
+//!     parser.enqueue_bytes(received);
+//!
+//!     while let Some(event) = parser.progress().unwrap() {
+//!         match event {
+//!             FetchEvent::LiteralChunk { data } => body.extend_from_slice(data),
+//!             event => println!("{event:?}"),
+//!         }
+//!     }
+//! }
+//!
+//! assert_eq!(body, b"Hello");
+//! ```
+
+use std::num::NonZeroU32;
+
+use abnf_core::streaming::sp;
+use imap_types::{
+    fetch::{MessageDataItem, Section},
+    IntoStatic,
+};
+use nom::{
+    branch::alt,
+    bytes::streaming::{tag, tag_no_case, take_while1},
+    character::streaming::char,
+    combinator::{map, opt, value},
+    multi::many0,
+    sequence::{delimited, preceded, terminated, tuple},
+};
+
+use crate::{
+    core::{literal, number, nz_number, quoted},
+    decode::{DecodeFailure, IMAPErrorKind, IMAPParseError, IMAPResult},
+    extensions::binary::{literal8, section_binary},
+    fetch::{msg_att_item, section},
+    quirks::{self, crlf},
+    Quirks,
+};
+
+/// Incremental parser for `FETCH` responses.
+///
+/// Enqueue received bytes with [`FetchParser::enqueue_bytes`] and call [`FetchParser::progress`]
+/// until it returns `Ok(None)`. Multiple `FETCH` responses can be parsed one after another.
+///
+/// When another response is next, [`FetchEvent::NotFetch`] is returned (repeatedly) until its
+/// bytes are taken out with [`FetchParser::take_remaining`].
+#[derive(Clone, Debug, Default)]
+pub struct FetchParser {
+    /// Enqueued bytes that are not parsed yet.
+    buffer: Vec<u8>,
+    /// Number of bytes at the start of the buffer that were passed out by the last event.
+    consumed: usize,
+    /// Number of bytes dequeued so far.
+    position: usize,
+    /// The next expected element.
+    state: State,
+    /// Names of the data items that are skipped.
+    skipped: Vec<String>,
+    /// Quirks used for parsing.
+    quirks: Quirks,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+enum State {
+    /// `"*" SP nz-number SP "FETCH" SP "("`
+    #[default]
+    Start,
+    /// A data item.
+    Item,
+    /// `SP` (followed by a data item) or `")" CRLF`.
+    Separator,
+    /// The literal data of a streamed data item.
+    Literal {
+        remaining: u32,
+        skipped: bool,
+    },
+    Failed(DecodeFailure),
+}
+
+impl FetchParser {
+    /// Creates a parser.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skips the given data items, e.g., `["BODYSTRUCTURE"]`.
+    ///
+    /// Skipped items are neither materialized nor reported. Names are compared case-insensitively
+    /// to the name of an item without section and origin, i.e., `"BODY"` skips `BODY[1]<0>`, too.
+    pub fn with_skipped_items<'n>(mut self, names: impl IntoIterator<Item = &'n str>) -> Self {
+        self.skipped.extend(names.into_iter().map(str::to_owned));
+        self
+    }
+
+    /// Use the given quirks for parsing.
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Enqueues received bytes.
+    pub fn enqueue_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Parses the enqueued bytes until the next event.
+    ///
+    /// Returns `Ok(None)` when more bytes are required. After an error, the parser must not be
+    /// used anymore.
+    pub fn progress(&mut self) -> Result<Option<FetchEvent>, FetchParserError> {
+        let consumed = std::mem::take(&mut self.consumed);
+        self.dequeue(consumed);
+
+        loop {
+            match self.state {
+                State::Start => match run(&self.buffer, self.position, self.quirks, fetch_start) {
+                    Ok(Some((consumed, seq))) => {
+                        self.dequeue(consumed);
+                        self.state = State::Item;
+                        return Ok(Some(FetchEvent::Start { seq }));
+                    }
+                    Ok(None) => return Ok(None),
+                    // Note: The bytes are not dequeued and passed on through `take_remaining`.
+                    Err(_) => return Ok(Some(FetchEvent::NotFetch)),
+                },
+                State::Item => {
+                    let skipped = match run(&self.buffer, self.position, self.quirks, item_name) {
+                        Ok(Some((_, name))) => self
+                            .skipped
+                            .iter()
+                            .any(|skipped| skipped.as_bytes().eq_ignore_ascii_case(name)),
+                        Ok(None) => return Ok(None),
+                        Err(failure) => return self.fail(failure),
+                    };
+
+                    match run(&self.buffer, self.position, self.quirks, streamed_item) {
+                        Ok(Some((consumed, (item, length)))) => {
+                            self.dequeue(consumed);
+                            self.state = State::Literal {
+                                remaining: length,
+                                skipped,
+                            };
+
+                            if !skipped {
+                                return Ok(Some(FetchEvent::ItemStart { item, length }));
+                            }
+
+                            continue;
+                        }
+                        Ok(None) => return Ok(None),
+                        // Not a streamed data item.
+                        Err(_) => {}
+                    }
+
+                    if skipped {
+                        match run(&self.buffer, self.position, self.quirks, skip_item) {
+                            Ok(Some((consumed, ()))) => {
+                                self.dequeue(consumed);
+                                self.state = State::Separator;
+                            }
+                            Ok(None) => return Ok(None),
+                            Err(failure) => return self.fail(failure),
+                        }
+                    } else {
+                        match run(&self.buffer, self.position, self.quirks, msg_att_item) {
+                            Ok(Some((consumed, item))) => {
+                                let item = Box::new(item.into_static());
+                                self.dequeue(consumed);
+                                self.state = State::Separator;
+                                return Ok(Some(FetchEvent::Item { item }));
+                            }
+                            Ok(None) => return Ok(None),
+                            Err(failure) => return self.fail(failure),
+                        }
+                    }
+                }
+                State::Separator => {
+                    match run(&self.buffer, self.position, self.quirks, separator) {
+                        Ok(Some((consumed, end))) => {
+                            self.dequeue(consumed);
+
+                            if end {
+                                self.state = State::Start;
+                                return Ok(Some(FetchEvent::End));
+                            }
+
+                            self.state = State::Item;
+                        }
+                        Ok(None) => return Ok(None),
+                        Err(failure) => return self.fail(failure),
+                    }
+                }
+                State::Literal { remaining, skipped } => {
+                    if remaining == 0 {
+                        self.state = State::Separator;
+
+                        if !skipped {
+                            return Ok(Some(FetchEvent::ItemEnd));
+                        }
+
+                        continue;
+                    }
+
+                    if self.buffer.is_empty() {
+                        return Ok(None);
+                    }
+
+                    let length = self.buffer.len().min(remaining as usize);
+                    self.state = State::Literal {
+                        // Note: `length` is not larger than `remaining`.
+                        remaining: remaining - length as u32,
+                        skipped,
+                    };
+
+                    if skipped {
+                        self.dequeue(length);
+                    } else {
+                        self.consumed = length;
+                        return Ok(Some(FetchEvent::LiteralChunk {
+                            data: &self.buffer[..length],
+                        }));
+                    }
+                }
+                State::Failed(failure) => return Err(FetchParserError::Failed(failure)),
+            }
+        }
+    }
+
+    /// Takes all enqueued bytes that are not parsed yet, e.g., after [`FetchEvent::NotFetch`].
+    ///
+    /// The parser is reset to expect the start of a `FETCH` response. Bytes that belong to
+    /// following responses can be enqueued again. The offsets of later failures are relative to
+    /// the bytes enqueued after this call.
+    pub fn take_remaining(&mut self) -> Vec<u8> {
+        self.consumed = 0;
+        self.position = 0;
+        self.state = State::Start;
+
+        std::mem::take(&mut self.buffer)
+    }
+
+    fn dequeue(&mut self, byte_count: usize) {
+        self.buffer.drain(..byte_count);
+        self.position += byte_count;
+    }
+
+    fn fail(&mut self, failure: DecodeFailure) -> Result<Option<FetchEvent>, FetchParserError> {
+        self.state = State::Failed(failure);
+        Err(FetchParserError::Failed(failure))
+    }
+}
+
+/// Event emitted by [`FetchParser::progress`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FetchEvent<'a> {
+    /// A `FETCH` response starts.
+    Start { seq: NonZeroU32 },
+    /// A data item was parsed.
+    Item { item: Box<MessageDataItem<'static>> },
+    /// A data item with a literal value starts.
+    ///
+    /// The literal is passed on through [`FetchEvent::LiteralChunk`]s and followed by
+    /// [`FetchEvent::ItemEnd`].
+    ItemStart { item: StreamedItem, length: u32 },
+    /// A chunk of the literal of the current data item.
+    LiteralChunk { data: &'a [u8] },
+    /// The data item with a literal value ended.
+    ItemEnd,
+    /// The `FETCH` response ended.
+    End,
+    /// The next response is not a `FETCH` response (e.g. a tagged status).
+    ///
+    /// The response is not parsed and might not be complete yet. Take its bytes out with
+    /// [`FetchParser::take_remaining`] and decode them with the
+    /// [`ResponseCodec`](crate::ResponseCodec).
+    NotFetch,
+}
+
+/// Data item with a literal value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum StreamedItem {
+    /// `BODY[<section>]<<origin>>`
+    BodyExt {
+        section: Option<Section<'static>>,
+        origin: Option<u32>,
+    },
+    /// `BINARY[<section>]` (RFC 3516)
+    Binary { section: Vec<NonZeroU32> },
+    /// `RFC822`
+    Rfc822,
+    /// `RFC822.HEADER`
+    Rfc822Header,
+    /// `RFC822.TEXT`
+    Rfc822Text,
+}
+
+/// Error returned by [`FetchParser::progress`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FetchParserError {
+    /// Parsing failed.
+    ///
+    /// Note: The offset is relative to the first enqueued byte.
+    Failed(DecodeFailure),
+}
+
+/// Runs `parser` and returns the number of consumed bytes (or `None` if more bytes are required).
+fn run<'a, O>(
+    input: &'a [u8],
+    position: usize,
+    quirks: Quirks,
+    parser: impl FnOnce(&'a [u8]) -> IMAPResult<'a, &'a [u8], O>,
+) -> Result<Option<(usize, O)>, DecodeFailure> {
+    match quirks::with(quirks, || parser(input)) {
+        Ok((remaining, out)) => Ok(Some((input.len() - remaining.len(), out))),
+        // A literal is announced at the end of the input.
+        Err(nom::Err::Incomplete(_))
+        | Err(nom::Err::Failure(IMAPParseError {
+            kind: IMAPErrorKind::Literal { .. },
+            ..
+        })) => Ok(None),
+        Err(nom::Err::Error(error) | nom::Err::Failure(error)) => {
+            let mut failure = error.failure(input);
+            failure.offset += position;
+            Err(failure)
+        }
+    }
+}
+
+/// `"*" SP nz-number SP "FETCH" SP "("`
+fn fetch_start(input: &[u8]) -> IMAPResult<&[u8], NonZeroU32> {
+    delimited(tag(b"* "), nz_number, tag_no_case(b" FETCH ("))(input)
+}
+
+/// `SP` (returns `false`) or `")" CRLF` (returns `true`)
+fn separator(input: &[u8]) -> IMAPResult<&[u8], bool> {
+    alt((value(false, sp), value(true, tuple((tag(b")"), crlf)))))(input)
+}
+
+/// Name of a data item (without section and origin), e.g., `BODY` in `BODY[1]<0>`.
+fn item_name(input: &[u8]) -> IMAPResult<&[u8], &[u8]> {
+    take_while1(|b: u8| b.is_ascii_alphanumeric() || b == b'.' || b == b'-')(input)
+}
+
+/// Data item with a literal value (and the length of the literal).
+fn streamed_item(input: &[u8]) -> IMAPResult<&[u8], (StreamedItem, u32)> {
+    tuple((
+        alt((
+            map(
+                tuple((
+                    tag_no_case(b"BODY"),
+                    section,
+                    opt(delimited(tag(b"<"), number, tag(b">"))),
+                )),
+                |(_, section, origin)| StreamedItem::BodyExt {
+                    section: section.map(IntoStatic::into_static),
+                    origin,
+                },
+            ),
+            map(
+                preceded(tag_no_case(b"BINARY"), section_binary),
+                |section| StreamedItem::Binary { section },
+            ),
+            value(StreamedItem::Rfc822Header, tag_no_case(b"RFC822.HEADER")),
+            value(StreamedItem::Rfc822Text, tag_no_case(b"RFC822.TEXT")),
+            value(StreamedItem::Rfc822, tag_no_case(b"RFC822")),
+        )),
+        preceded(sp, literal_header),
+    ))(input)
+}
+
+/// `["~"] "{" number ["+"] "}" CRLF`
+fn literal_header(input: &[u8]) -> IMAPResult<&[u8], u32> {
+    terminated(
+        delimited(
+            tuple((opt(char('~')), char('{'))),
+            number,
+            tuple((opt(char('+')), char('}'))),
+        ),
+        crlf,
+    )(input)
+}
+
+/// Data item that is skipped without materializing its value.
+fn skip_item(input: &[u8]) -> IMAPResult<&[u8], ()> {
+    value(
+        (),
+        tuple((
+            item_name,
+            opt(section),
+            opt(delimited(tag(b"<"), number, tag(b">"))),
+            sp,
+            |input| skip_value(input, 64),
+        )),
+    )(input)
+}
+
+/// Any value, i.e., a string, a (nested) list, or an atom-like value, e.g., `NIL` or a number.
+fn skip_value(input: &[u8], remaining_recursions: usize) -> IMAPResult<&[u8], ()> {
+    if remaining_recursions == 0 {
+        return Err(nom::Err::Failure(IMAPParseError {
+            input,
+            rule: None,
+            kind: IMAPErrorKind::RecursionLimitExceeded,
+        }));
+    }
+
+    let nested = |input| skip_value(input, remaining_recursions - 1);
+
+    alt((
+        value((), quoted),
+        value((), literal),
+        value((), literal8),
+        value(
+            (),
+            delimited(tag(b"("), many0(alt((value((), sp), nested))), tag(b")")),
+        ),
+        value(
+            (),
+            take_while1(|b| !matches!(b, b' ' | b'(' | b')' | b'"' | b'{' | b'\r' | b'\n')),
+        ),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        core::{IString, NString, Tag},
+        fetch::Part,
+        flag::{Flag, FlagFetch},
+        response::{Data, Response, Status},
+    };
+
+    use super::*;
+    use crate::{
+        decode::{DecodeFailureKind, Decoder},
+        ResponseCodec,
+    };
+
+    /// Feeds `chunks` and returns the events (without literal chunks) and the literal data.
+    fn feed(parser: &mut FetchParser, chunks: &[&[u8]]) -> (Vec<FetchEvent<'static>>, Vec<u8>) {
+        let mut events = Vec::new();
+        let mut data = Vec::new();
+
+        for chunk in chunks {
+            parser.enqueue_bytes(chunk);
+
+            while let Some(event) = parser.progress().unwrap() {
+                match event {
+                    FetchEvent::LiteralChunk { data: chunk } => data.extend_from_slice(chunk),
+                    FetchEvent::Start { seq } => events.push(FetchEvent::Start { seq }),
+                    FetchEvent::Item { item } => events.push(FetchEvent::Item { item }),
+                    FetchEvent::ItemStart { item, length } => {
+                        events.push(FetchEvent::ItemStart { item, length })
+                    }
+                    FetchEvent::ItemEnd => events.push(FetchEvent::ItemEnd),
+                    FetchEvent::End => events.push(FetchEvent::End),
+                    FetchEvent::NotFetch => panic!("unexpected response"),
+                }
+            }
+        }
+
+        (events, data)
+    }
+
+    #[test]
+    fn test_fetch_parser() {
+        let response = b"* 3 FETCH (UID 7 FLAGS (\\Seen) ENVELOPE (NIL {3}\r\nabc NIL NIL NIL NIL NIL NIL NIL NIL) BODY[1.MIME]<5> {10}\r\n0123456789 BINARY[1] ~{2}\r\n\x00\x01 RFC822.SIZE 10)\r\n";
+
+        let Ok((_, Response::Data(Data::Fetch { items, .. }))) =
+            ResponseCodec::new().decode(response)
+        else {
+            panic!("expected FETCH");
+        };
+        let items = items.into_inner().into_static();
+
+        let expected = vec![
+            FetchEvent::Start {
+                seq: NonZeroU32::new(3).unwrap(),
+            },
+            FetchEvent::Item {
+                item: Box::new(items[0].clone()),
+            },
+            FetchEvent::Item {
+                item: Box::new(MessageDataItem::Flags(vec![FlagFetch::Flag(Flag::Seen)])),
+            },
+            FetchEvent::Item {
+                item: Box::new(items[2].clone()),
+            },
+            FetchEvent::ItemStart {
+                item: StreamedItem::BodyExt {
+                    section: Some(Section::Mime(Part(
+                        vec![NonZeroU32::new(1).unwrap()].try_into().unwrap(),
+                    ))),
+                    origin: Some(5),
+                },
+                length: 10,
+            },
+            FetchEvent::ItemEnd,
+            FetchEvent::ItemStart {
+                item: StreamedItem::Binary {
+                    section: vec![NonZeroU32::new(1).unwrap()],
+                },
+                length: 2,
+            },
+            FetchEvent::ItemEnd,
+            FetchEvent::Item {
+                item: Box::new(MessageDataItem::Rfc822Size(10)),
+            },
+            FetchEvent::End,
+        ];
+
+        // At once ...
+        let (events, data) = feed(&mut FetchParser::new(), &[response]);
+        assert_eq!(events, expected);
+        assert_eq!(data, b"0123456789\x00\x01");
+
+        // ... and byte by byte.
+        let chunks: Vec<&[u8]> = response.chunks(1).collect();
+        let (events, data) = feed(&mut FetchParser::new(), &chunks);
+        assert_eq!(events, expected);
+        assert_eq!(data, b"0123456789\x00\x01");
+    }
+
+    #[test]
+    fn test_fetch_parser_skipped_items() {
+        let mut parser = FetchParser::new().with_skipped_items(["bodystructure", "RFC822"]);
+
+        let (events, data) = feed(
+            &mut parser,
+            &[
+                b"* 1 FETCH (BODYSTRUCTURE ((\"TEXT\" \"PLAIN\" (\"a\" {1}\r\nb) NIL NIL \"7BIT\" 1 1)",
+                b"(\"TEXT\" \"HTML\" NIL NIL NIL \"7BIT\" 1 1) \"MIXED\") RFC822 {3}\r\nabc",
+                b" RFC822.HEADER NIL)\r\n* 2 FETCH (UID 1)\r\n",
+            ],
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                FetchEvent::Start {
+                    seq: NonZeroU32::new(1).unwrap()
+                },
+                FetchEvent::Item {
+                    item: Box::new(MessageDataItem::Rfc822Header(NString(None))),
+                },
+                FetchEvent::End,
+                FetchEvent::Start {
+                    seq: NonZeroU32::new(2).unwrap()
+                },
+                FetchEvent::Item {
+                    item: Box::new(MessageDataItem::Uid(NonZeroU32::new(1).unwrap())),
+                },
+                FetchEvent::End,
+            ]
+        );
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn test_fetch_parser_not_streamed() {
+        let (events, data) = feed(
+            &mut FetchParser::new(),
+            &[b"* 1 FETCH (BODY[] \"abc\" RFC822 NIL)\r\n"],
+        );
+
+        assert_eq!(
+            events[1..3],
+            [
+                FetchEvent::Item {
+                    item: Box::new(MessageDataItem::BodyExt {
+                        section: None,
+                        origin: None,
+                        data: NString(Some(IString::try_from("abc").unwrap())),
+                    }),
+                },
+                FetchEvent::Item {
+                    item: Box::new(MessageDataItem::Rfc822(NString(None))),
+                },
+            ]
+        );
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn test_fetch_parser_failed() {
+        let mut parser = FetchParser::new();
+        parser.enqueue_bytes(b"* 1 FETCH (UID 1 XFOO 1)\r\n");
+
+        assert!(matches!(
+            parser.progress(),
+            Ok(Some(FetchEvent::Start { .. }))
+        ));
+        assert!(matches!(
+            parser.progress(),
+            Ok(Some(FetchEvent::Item { .. }))
+        ));
+
+        let Err(FetchParserError::Failed(failure)) = parser.progress() else {
+            panic!("expected failure");
+        };
+        assert_eq!(failure.offset, 17);
+        assert_eq!(failure.kind, DecodeFailureKind::Syntax);
+
+        // The parser stays failed.
+        assert_eq!(parser.progress(), Err(FetchParserError::Failed(failure)));
+    }
+
+    #[test]
+    fn test_fetch_parser_not_fetch() {
+        let mut parser = FetchParser::new();
+        parser.enqueue_bytes(b"* 1 FETCH (UID 1)\r\nA1 OK done\r\n* 2 FETCH (UID 2)\r\n");
+
+        assert!(matches!(
+            parser.progress(),
+            Ok(Some(FetchEvent::Start { .. }))
+        ));
+        assert!(matches!(
+            parser.progress(),
+            Ok(Some(FetchEvent::Item { .. }))
+        ));
+        assert_eq!(parser.progress(), Ok(Some(FetchEvent::End)));
+        assert_eq!(parser.progress(), Ok(Some(FetchEvent::NotFetch)));
+        // The bytes are left intact.
+        assert_eq!(parser.progress(), Ok(Some(FetchEvent::NotFetch)));
+
+        let remaining = parser.take_remaining();
+        let (rest, response) = ResponseCodec::new().decode(&remaining).unwrap();
+        assert_eq!(
+            response,
+            Response::Status(Status::ok(Some(Tag::try_from("A1").unwrap()), None, "done").unwrap())
+        );
+
+        // Following responses are enqueued again.
+        let (events, _) = feed(&mut parser, &[rest]);
+        assert_eq!(
+            events[0],
+            FetchEvent::Start {
+                seq: NonZeroU32::new(2).unwrap()
+            }
+        );
+
+        parser.enqueue_bytes(b"* 3 EXISTS\r\n");
+        assert_eq!(parser.progress(), Ok(Some(FetchEvent::NotFetch)));
+        assert_eq!(parser.take_remaining(), b"* 3 EXISTS\r\n");
+    }
+
+    #[test]
+    fn test_fetch_parser_quirks() {
+        let mut parser = FetchParser::new().with_quirks(Quirks::none());
+        parser.enqueue_bytes(b"* 1 FETCH (UID 42)\n");
+        while let Ok(Some(_)) = parser.progress() {}
+        assert!(parser.progress().is_err());
+
+        let mut parser = FetchParser::new().with_quirks(Quirks::all());
+        let (events, _) = feed(&mut parser, &[b"* 1 FETCH (UID 42)\n"]);
+        assert_eq!(
+            events,
+            vec![
+                FetchEvent::Start {
+                    seq: NonZeroU32::new(1).unwrap()
+                },
+                FetchEvent::Item {
+                    item: Box::new(MessageDataItem::Uid(NonZeroU32::new(42).unwrap()))
+                },
+                FetchEvent::End,
+            ]
+        );
+    }
+}
@@ -5,6 +5,7 @@ use nom::{
     bytes::streaming::tag,
     character::streaming::char,
     combinator::{map, recognize, value},
+    error::context,
     multi::{separated_list0, separated_list1},
     sequence::{delimited, preceded, tuple},
 };
@@ -52,7 +53,10 @@ pub(crate) fn flag(input: &[u8]) -> IMAPResult<&[u8], Flag> {
 
 /// `flag-list = "(" [flag *(SP flag)] ")"`
 pub(crate) fn flag_list(input: &[u8]) -> IMAPResult<&[u8], Vec<Flag>> {
-    delimited(tag(b"("), separated_list0(sp, flag), tag(b")"))(input)
+    context(
+        "flag-list",
+        delimited(tag(b"("), separated_list0(sp, flag), tag(b")")),
+    )(input)
 }
 
 /// `flag-fetch = flag / "\Recent"`
@@ -0,0 +1,23 @@
+//! Sans-I/O protocol flows.
+//!
+//! The codecs and the [`Fragmentizer`](crate::fragmentizer::Fragmentizer) take care of single
+//! messages. However, an IMAP client (or server) also needs to know *when* to send or expect
+//! *which* message, e.g., a client must wait for a command continuation request before sending a
+//! synchronizing literal and a server must switch to [`AuthenticateDataCodec`](crate::AuthenticateDataCodec)
+//! during `AUTHENTICATE`.
+//!
+//! The flows in this module implement this logic without doing any I/O themselves:
+//! Received bytes are passed in, and events as well as bytes to send are handed out.
+//! Thus, the flows can be used with any runtime (or none at all).
+//!
+//! * [`ClientFlow`](client::ClientFlow) implements the client side.
+//! * [`ServerFlow`](server::ServerFlow) implements the server side.
+//! * [`CommandRegistry`](registry::CommandRegistry) correlates tagged responses with pending commands.
+//! * [`Collect`](collect::Collect) aggregates untagged responses into typed command results.
+//! * [`MailboxView`](mailbox::MailboxView) tracks the selected mailbox (including `EXPUNGE` renumbering).
+
+pub mod client;
+pub mod collect;
+pub mod mailbox;
+pub mod registry;
+pub mod server;
@@ -0,0 +1,1153 @@
+//! Sans-I/O client flow.
+//!
+//! # Example
+//!
+//! ```rust
+//! use imap_codec::{
+//!     flow::client::{ClientFlow, ClientFlowEvent, ClientFlowOptions},
+//!     imap_types::command::{Command, CommandBody},
+//! };
+//!
+//! let mut client = ClientFlow::new(ClientFlowOptions::default());
+//! client
+//!     .enqueue_command(Command::new("A1", CommandBody::Noop).unwrap())
+//!     .unwrap();
+//!
+//! // Bytes received from the server ...
+//! client.enqueue_input(b"* OK Hello\r\n");
+//!
+//! // ... result in events.
+//! while let Some(event) = client.progress().unwrap() {
+//!     match event {
+//!         ClientFlowEvent::GreetingReceived { greeting } => {}
+//!         ClientFlowEvent::CommandSent { tag } => {}
+//!         _ => {}
+//!     }
+//! }
+//!
+//! // Bytes to send to the server.
+//! assert_eq!(client.take_output(), b"A1 NOOP\r\n");
+//! ```
+
+use std::collections::VecDeque;
+
+#[cfg(any(feature = "compress", feature = "starttls"))]
+use imap_types::response::StatusKind;
+use imap_types::{
+    auth::AuthenticateData,
+    command::{Command, CommandBody},
+    core::{LiteralMode, Tag},
+    extensions::idle::IdleDone,
+    response::{
+        CommandContinuationRequest, Data, Greeting, GreetingKind, Response, Status, StatusBody,
+        Tagged, Unparsed,
+    },
+    secret::Secret,
+    state::State,
+    IntoStatic,
+};
+
+#[cfg(feature = "compress")]
+use crate::compress::{CompressionLayer, DecompressError};
+use crate::{
+    decode::Decoder,
+    encode::{Encoder, Fragment, LiteralPolicy},
+    flow::registry::{CommandRegistry, CommandRegistryError, CompletedCommand},
+    fragmentizer::{DecodeMessageError, ExceededLimit, Fragmentizer},
+    AuthenticateDataCodec, CommandCodec, GreetingCodec, IdleDoneCodec, Quirks, ResponseCodec,
+};
+
+/// Options for [`ClientFlow`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ClientFlowOptions {
+    /// Maximum size of a single response (including literals) in bytes.
+    ///
+    /// Larger responses are discarded and reported as [`ClientFlowError::MessageTooLong`].
+    pub max_response_size: u32,
+    /// Maximum length of a single line (excluding literals) in bytes.
+    ///
+    /// Responses with longer lines are discarded and reported as
+    /// [`ClientFlowError::LimitExceeded`] (see [`Fragmentizer::with_max_line_length`]).
+    pub max_line_length: Option<u32>,
+    /// Maximum size of a single literal in bytes.
+    ///
+    /// Responses with larger literals are discarded and reported as
+    /// [`ClientFlowError::LimitExceeded`] (see [`Fragmentizer::with_max_literal_size`]).
+    pub max_literal_size: Option<u32>,
+    /// Maximum number of literals in a single response.
+    ///
+    /// Responses with more literals are discarded and reported as
+    /// [`ClientFlowError::LimitExceeded`] (see [`Fragmentizer::with_max_literal_count`]).
+    pub max_literal_count: Option<u32>,
+    /// Report unknown or malformed untagged responses as [`ClientFlowEvent::UnparsedReceived`]
+    /// instead of [`ClientFlowError::MalformedMessage`] (see [`ResponseCodec::with_lenient`]).
+    ///
+    /// Malformed tagged `OK`, `NO`, or `BAD` responses still complete their command.
+    pub lenient: bool,
+    /// Quirks used for decoding (see [`Quirks`]).
+    pub quirks: Quirks,
+    /// Literal policy used for encoding commands (see [`CommandCodec::with_literal_policy`]).
+    ///
+    /// Use [`ClientFlow::set_literal_policy`] to change it when the server's capabilities are
+    /// known.
+    pub literal_policy: LiteralPolicy,
+}
+
+impl Default for ClientFlowOptions {
+    fn default() -> Self {
+        Self {
+            // 100 MiB
+            max_response_size: 100 * 1024 * 1024,
+            max_line_length: None,
+            max_literal_size: None,
+            max_literal_count: None,
+            lenient: false,
+            quirks: Quirks::default(),
+            literal_policy: LiteralPolicy::default(),
+        }
+    }
+}
+
+/// Sans-I/O IMAP client.
+///
+/// The client ...
+///
+/// * waits for the greeting before sending commands,
+/// * waits for a command continuation request before sending a synchronizing literal,
+/// * hands out command continuation requests during `AUTHENTICATE` and encodes the
+///   [`AuthenticateData`] passed to [`ClientFlow::authenticate_continue`],
+/// * waits for the acceptance of `IDLE` and encodes `DONE` on [`ClientFlow::idle_done`],
+/// * correlates tagged status responses with their commands, and
+/// * tracks the connection [`State`].
+///
+/// Commands are pipelined, i.e., sent without waiting for the completion of previous commands.
+/// The only exceptions are `AUTHENTICATE`, `IDLE`, and `COMPRESS`: Commands enqueued after them
+/// are held back until they are completed.
+///
+/// With the `compress` feature, a successful `COMPRESS DEFLATE` activates compression for all
+/// following input and output.
+#[cfg_attr(
+    feature = "starttls",
+    doc = "",
+    doc = " With the `starttls` feature, commands enqueued after `STARTTLS` are held back as well.",
+    doc = " After the tagged `OK`, all bytes received in plaintext are discarded (see",
+    doc = " [`ClientFlowError::StartTlsInjection`]). Upgrade the connection and call",
+    doc = " [`ClientFlow::tls_established`] to send the held back commands."
+)]
+#[derive(Debug)]
+pub struct ClientFlow {
+    fragmentizer: Fragmentizer,
+    greeting_codec: GreetingCodec,
+    command_codec: CommandCodec,
+    authenticate_data_codec: AuthenticateDataCodec,
+    response_codec: ResponseCodec,
+    idle_done_codec: IdleDoneCodec,
+    state: State<'static>,
+    output: Vec<u8>,
+    events: VecDeque<ClientFlowEvent>,
+    /// Commands that were not started yet.
+    queued: VecDeque<Command<'static>>,
+    /// Command that is currently sent.
+    sending: Option<Sending>,
+    /// Commands that were (at least partially) sent and are waiting for completion.
+    in_flight: CommandRegistry,
+    /// `AUTHENTICATE`, `IDLE`, or `COMPRESS` that blocks the sending of further commands.
+    exclusive: Option<Exclusive>,
+    #[cfg(feature = "compress")]
+    compression: Option<CompressionLayer>,
+}
+
+#[derive(Debug)]
+struct Sending {
+    tag: Tag<'static>,
+    fragments: VecDeque<Fragment>,
+    /// Whether we received a command continuation request for the next (synchronizing) literal.
+    continued: bool,
+}
+
+#[derive(Debug)]
+enum Exclusive {
+    Authenticate {
+        tag: Tag<'static>,
+        /// Whether the server requested more data, i.e., [`ClientFlow::authenticate_continue`]
+        /// may be called.
+        data_requested: bool,
+    },
+    Idle {
+        tag: Tag<'static>,
+        /// Whether the server accepted the `IDLE`, i.e., [`ClientFlow::idle_done`] may be called.
+        accepted: bool,
+    },
+    Compress {
+        tag: Tag<'static>,
+    },
+    #[cfg(feature = "starttls")]
+    StartTls {
+        tag: Tag<'static>,
+        /// Whether the server accepted the `STARTTLS`, i.e., [`ClientFlow::tls_established`] may
+        /// be called.
+        accepted: bool,
+    },
+}
+
+impl ClientFlow {
+    pub fn new(options: ClientFlowOptions) -> Self {
+        Self {
+            fragmentizer: Self::fragmentizer(&options),
+            greeting_codec: GreetingCodec::new().with_quirks(options.quirks),
+            command_codec: CommandCodec::new()
+                .with_quirks(options.quirks)
+                .with_literal_policy(options.literal_policy),
+            authenticate_data_codec: AuthenticateDataCodec::new().with_quirks(options.quirks),
+            response_codec: ResponseCodec::new()
+                .with_quirks(options.quirks)
+                .with_lenient(options.lenient),
+            idle_done_codec: IdleDoneCodec::new().with_quirks(options.quirks),
+            state: State::Greeting,
+            output: Vec::new(),
+            events: VecDeque::new(),
+            queued: VecDeque::new(),
+            sending: None,
+            in_flight: CommandRegistry::new(),
+            exclusive: None,
+            #[cfg(feature = "compress")]
+            compression: None,
+        }
+    }
+
+    fn fragmentizer(options: &ClientFlowOptions) -> Fragmentizer {
+        let mut fragmentizer = Fragmentizer::new(options.max_response_size);
+
+        if let Some(max_line_length) = options.max_line_length {
+            fragmentizer = fragmentizer.with_max_line_length(max_line_length);
+        }
+        if let Some(max_literal_size) = options.max_literal_size {
+            fragmentizer = fragmentizer.with_max_literal_size(max_literal_size);
+        }
+        if let Some(max_literal_count) = options.max_literal_count {
+            fragmentizer = fragmentizer.with_max_literal_count(max_literal_count);
+        }
+
+        fragmentizer
+    }
+
+    /// Returns the current connection state.
+    pub fn state(&self) -> &State<'static> {
+        &self.state
+    }
+
+    /// Sets the literal policy used for encoding commands, e.g., derived from the server's
+    /// capabilities via [`LiteralPolicy::from_capabilities`].
+    ///
+    /// The policy applies to all commands that are not sent yet. Non-synchronizing literals are
+    /// sent without waiting for a command continuation request.
+    pub fn set_literal_policy(&mut self, literal_policy: LiteralPolicy) {
+        self.command_codec = self
+            .command_codec
+            .clone()
+            .with_literal_policy(literal_policy);
+    }
+
+    /// Enqueues a command.
+    ///
+    /// The command is encoded into the output as soon as possible. Its completion is reported
+    /// through [`ClientFlowEvent::CommandCompleted`].
+    ///
+    /// Fails when a command with the same tag is queued or waiting for completion, as the tagged
+    /// responses couldn't be correlated.
+    pub fn enqueue_command(&mut self, command: Command<'_>) -> Result<(), ClientFlowError> {
+        if self.in_flight.contains(&command.tag)
+            || self.queued.iter().any(|queued| queued.tag == command.tag)
+        {
+            return Err(ClientFlowError::DuplicateTag {
+                command: Box::new(command.into_static()),
+            });
+        }
+
+        self.queued.push_back(command.into_static());
+        self.send_ready();
+
+        Ok(())
+    }
+
+    /// Continues an ongoing `AUTHENTICATE` after [`ClientFlowEvent::AuthenticateContinuationRequestReceived`].
+    pub fn authenticate_continue(
+        &mut self,
+        authenticate_data: AuthenticateData<'_>,
+    ) -> Result<(), ClientFlowError> {
+        match &mut self.exclusive {
+            Some(Exclusive::Authenticate { data_requested, .. }) if *data_requested => {
+                *data_requested = false;
+            }
+            _ => return Err(ClientFlowError::AuthenticateDataNotRequested),
+        }
+
+        // Note: Writing into a `Vec` can't fail.
+        self.authenticate_data_codec
+            .dump_into(&authenticate_data, &mut self.output)
+            .unwrap();
+
+        Ok(())
+    }
+
+    /// Ends an ongoing `IDLE` after [`ClientFlowEvent::IdleAccepted`].
+    pub fn idle_done(&mut self) -> Result<(), ClientFlowError> {
+        match &mut self.exclusive {
+            Some(Exclusive::Idle { accepted, .. }) if *accepted => {
+                *accepted = false;
+            }
+            _ => return Err(ClientFlowError::IdleNotAccepted),
+        }
+
+        // Note: Writing into a `Vec` can't fail.
+        self.idle_done_codec
+            .dump_into(&IdleDone, &mut self.output)
+            .unwrap();
+
+        Ok(())
+    }
+
+    /// Continues after the connection was upgraded to TLS.
+    ///
+    /// Must be called after the `STARTTLS` was completed with `OK` and the TLS handshake
+    /// succeeded. Commands enqueued after `STARTTLS` are held back until then.
+    #[cfg(feature = "starttls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "starttls")))]
+    pub fn tls_established(&mut self) -> Result<(), ClientFlowError> {
+        match &self.exclusive {
+            Some(Exclusive::StartTls { accepted: true, .. }) => {
+                self.exclusive = None;
+            }
+            _ => return Err(ClientFlowError::StartTlsNotAccepted),
+        }
+
+        self.send_ready();
+
+        Ok(())
+    }
+
+    /// Enqueues bytes received from the server.
+    pub fn enqueue_input(&mut self, bytes: &[u8]) {
+        #[cfg(feature = "compress")]
+        if let Some(compression) = &mut self.compression {
+            compression.enqueue_input(bytes);
+            return;
+        }
+
+        self.fragmentizer.enqueue_bytes(bytes);
+    }
+
+    /// Takes the bytes that should be sent to the server.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        let output = std::mem::take(&mut self.output);
+
+        #[cfg(feature = "compress")]
+        if let Some(compression) = &mut self.compression {
+            return compression.compress(output);
+        }
+
+        output
+    }
+
+    /// Progresses the flow until the next event.
+    ///
+    /// Returns `Ok(None)` when more bytes need to be enqueued via [`ClientFlow::enqueue_input`].
+    /// Errors are not fatal, i.e., the flow can be progressed further.
+    ///
+    /// Note: Check [`ClientFlow::take_output`] after each call.
+    pub fn progress(&mut self) -> Result<Option<ClientFlowEvent>, ClientFlowError> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            if self.fragmentizer.progress().is_none() {
+                // Decompress more bytes only when needed (to bound the memory).
+                #[cfg(feature = "compress")]
+                if let Some(compression) = &mut self.compression {
+                    if compression
+                        .decompress_into(&mut self.fragmentizer)
+                        .map_err(|DecompressError| ClientFlowError::DecompressionFailed)?
+                    {
+                        continue;
+                    }
+                }
+
+                return Ok(None);
+            }
+
+            if !self.fragmentizer.is_message_complete() {
+                continue;
+            }
+
+            if let State::Greeting = self.state {
+                self.handle_greeting()?;
+            } else {
+                self.handle_response()?;
+            }
+        }
+    }
+
+    fn handle_greeting(&mut self) -> Result<(), ClientFlowError> {
+        let greeting = match self.fragmentizer.decode_message(&self.greeting_codec) {
+            Ok(greeting) => greeting.into_static(),
+            Err(err) => {
+                return Err(ClientFlowError::from_decode_message_error(
+                    err,
+                    self.fragmentizer.message_bytes(),
+                ))
+            }
+        };
+
+        self.state = match greeting.kind {
+            GreetingKind::Ok => State::NotAuthenticated,
+            GreetingKind::PreAuth => State::Authenticated,
+            GreetingKind::Bye => State::Logout,
+        };
+
+        self.events
+            .push_back(ClientFlowEvent::GreetingReceived { greeting });
+        self.send_ready();
+
+        Ok(())
+    }
+
+    fn handle_response(&mut self) -> Result<(), ClientFlowError> {
+        let response = match self.fragmentizer.decode_message(&self.response_codec) {
+            Ok(response) => response.into_static(),
+            Err(err) => {
+                return Err(ClientFlowError::from_decode_message_error(
+                    err,
+                    self.fragmentizer.message_bytes(),
+                ))
+            }
+        };
+
+        match response {
+            Response::CommandContinuationRequest(continuation_request) => {
+                self.handle_continuation_request(continuation_request)
+            }
+            Response::Data(data) => {
+                self.events
+                    .push_back(ClientFlowEvent::DataReceived { data });
+                Ok(())
+            }
+            Response::Status(Status::Tagged(tagged)) => self.handle_tagged(tagged),
+            Response::Status(status) => {
+                if let Status::Bye(_) = status {
+                    self.state = State::Logout;
+                }
+
+                self.events
+                    .push_back(ClientFlowEvent::StatusReceived { status });
+                Ok(())
+            }
+            Response::Unparsed(unparsed) => {
+                self.events
+                    .push_back(ClientFlowEvent::UnparsedReceived { unparsed });
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_continuation_request(
+        &mut self,
+        continuation_request: CommandContinuationRequest<'static>,
+    ) -> Result<(), ClientFlowError> {
+        // A synchronizing literal has priority as it can't be interleaved with anything else.
+        if let Some(sending) = &mut self.sending {
+            if matches!(
+                sending.fragments.front(),
+                Some(Fragment::Literal {
+                    mode: LiteralMode::Sync,
+                    ..
+                })
+            ) && !sending.continued
+            {
+                sending.continued = true;
+                self.send_ready();
+                return Ok(());
+            }
+        }
+
+        match &mut self.exclusive {
+            Some(Exclusive::Authenticate {
+                tag,
+                data_requested,
+            }) if !*data_requested => {
+                *data_requested = true;
+                self.events
+                    .push_back(ClientFlowEvent::AuthenticateContinuationRequestReceived {
+                        tag: tag.clone(),
+                        continuation_request,
+                    });
+                Ok(())
+            }
+            Some(Exclusive::Idle { tag, accepted }) if !*accepted => {
+                *accepted = true;
+                self.state = match std::mem::replace(&mut self.state, State::Greeting) {
+                    State::Selected(mailbox) => State::IdleSelected(tag.clone(), mailbox),
+                    _ => State::IdleAuthenticated(tag.clone()),
+                };
+                self.events.push_back(ClientFlowEvent::IdleAccepted {
+                    tag: tag.clone(),
+                    continuation_request,
+                });
+                Ok(())
+            }
+            _ => Err(ClientFlowError::UnexpectedContinuationRequest {
+                continuation_request: Box::new(continuation_request),
+            }),
+        }
+    }
+
+    fn handle_tagged(&mut self, tagged: Tagged<'static>) -> Result<(), ClientFlowError> {
+        let CompletedCommand { command, status } = match self.in_flight.complete(tagged) {
+            Ok(completed) => completed,
+            Err(CommandRegistryError::UnknownTag { tagged }) => {
+                return Err(ClientFlowError::UnknownTag { tagged })
+            }
+            Err(CommandRegistryError::DuplicateTag { .. }) => unreachable!(),
+        };
+
+        // The server may reject a command before all of its literals were sent.
+        if matches!(&self.sending, Some(sending) if sending.tag == command.tag) {
+            self.sending = None;
+        }
+
+        if matches!(
+            &self.exclusive,
+            Some(
+                Exclusive::Authenticate { tag, .. }
+                    | Exclusive::Idle { tag, .. }
+                    | Exclusive::Compress { tag }
+            ) if *tag == command.tag
+        ) {
+            self.exclusive = None;
+        }
+
+        // Commands are held back until the upgrade (see `ClientFlow::tls_established`).
+        #[cfg(feature = "starttls")]
+        if let Some(Exclusive::StartTls { tag, accepted }) = &mut self.exclusive {
+            if *tag == command.tag {
+                match status.kind {
+                    StatusKind::Ok => *accepted = true,
+                    _ => self.exclusive = None,
+                }
+            }
+        }
+
+        self.state = self.state.next(&command.body, status.kind);
+
+        // Everything received in plaintext after the tagged OK must not be processed.
+        #[cfg(feature = "starttls")]
+        let discarded = match (&command.body, status.kind) {
+            (CommandBody::StartTLS, StatusKind::Ok) => {
+                Some(self.fragmentizer.discard_unparsed_bytes())
+            }
+            _ => None,
+        };
+
+        #[cfg(feature = "compress")]
+        if let (CommandBody::Compress { .. }, StatusKind::Ok) = (&command.body, status.kind) {
+            // The server compresses everything after the tagged OK and so do we.
+            self.compression = Some(CompressionLayer::activate(
+                &mut self.fragmentizer,
+                &self.output,
+            ));
+        }
+
+        self.events
+            .push_back(ClientFlowEvent::CommandCompleted { command, status });
+        self.send_ready();
+
+        #[cfg(feature = "starttls")]
+        if let Some(discarded) = discarded {
+            if !discarded.declassify().is_empty() {
+                return Err(ClientFlowError::StartTlsInjection { discarded });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves everything that can be sent right now into the output.
+    fn send_ready(&mut self) {
+        loop {
+            let sending = match &mut self.sending {
+                Some(sending) => sending,
+                None => {
+                    if self.state == State::Greeting || self.exclusive.is_some() {
+                        return;
+                    }
+
+                    let Some(command) = self.queued.pop_front() else {
+                        return;
+                    };
+
+                    match command.body {
+                        CommandBody::Authenticate { .. } => {
+                            self.exclusive = Some(Exclusive::Authenticate {
+                                tag: command.tag.clone(),
+                                data_requested: false,
+                            });
+                        }
+                        CommandBody::Idle => {
+                            self.exclusive = Some(Exclusive::Idle {
+                                tag: command.tag.clone(),
+                                accepted: false,
+                            });
+                        }
+                        CommandBody::Compress { .. } => {
+                            self.exclusive = Some(Exclusive::Compress {
+                                tag: command.tag.clone(),
+                            });
+                        }
+                        #[cfg(feature = "starttls")]
+                        CommandBody::StartTLS => {
+                            self.exclusive = Some(Exclusive::StartTls {
+                                tag: command.tag.clone(),
+                                accepted: false,
+                            });
+                        }
+                        _ => {}
+                    }
+
+                    let fragments = self.command_codec.encode(&command).collect();
+                    let tag = command.tag.clone();
+                    // Note: Duplicate tags are rejected by `enqueue_command`.
+                    self.in_flight.register(command).unwrap();
+
+                    self.sending.insert(Sending {
+                        tag,
+                        fragments,
+                        continued: false,
+                    })
+                }
+            };
+
+            while let Some(fragment) = sending.fragments.front() {
+                match fragment {
+                    Fragment::Literal {
+                        mode: LiteralMode::Sync,
+                        ..
+                    } if !sending.continued => {
+                        // Wait for a command continuation request.
+                        return;
+                    }
+                    Fragment::Literal {
+                        mode: LiteralMode::Sync,
+                        ..
+                    } => {
+                        sending.continued = false;
+                    }
+                    _ => {}
+                }
+
+                match sending.fragments.pop_front() {
+                    Some(Fragment::Line { data } | Fragment::Literal { data, .. }) => {
+                        self.output.extend(data)
+                    }
+                    None => unreachable!(),
+                }
+            }
+
+            let tag = sending.tag.clone();
+            self.sending = None;
+            self.events.push_back(ClientFlowEvent::CommandSent { tag });
+        }
+    }
+}
+
+/// Event emitted by [`ClientFlow::progress`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClientFlowEvent {
+    /// The server greeting was received.
+    GreetingReceived { greeting: Greeting<'static> },
+    /// A command was fully written into the output.
+    CommandSent { tag: Tag<'static> },
+    /// The server requests more data for an ongoing `AUTHENTICATE`.
+    ///
+    /// Answer it with [`ClientFlow::authenticate_continue`].
+    AuthenticateContinuationRequestReceived {
+        tag: Tag<'static>,
+        continuation_request: CommandContinuationRequest<'static>,
+    },
+    /// The server accepted an `IDLE`.
+    ///
+    /// End it with [`ClientFlow::idle_done`].
+    IdleAccepted {
+        tag: Tag<'static>,
+        continuation_request: CommandContinuationRequest<'static>,
+    },
+    /// Untagged data was received.
+    DataReceived { data: Data<'static> },
+    /// An untagged status (including `BYE`) was received.
+    StatusReceived { status: Status<'static> },
+    /// A command was completed by a tagged status.
+    CommandCompleted {
+        command: Command<'static>,
+        status: StatusBody<'static>,
+    },
+    /// An untagged response was received that couldn't be parsed (see
+    /// [`ClientFlowOptions::lenient`]).
+    UnparsedReceived { unparsed: Unparsed<'static> },
+}
+
+/// Error returned by [`ClientFlow`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClientFlowError {
+    /// A received message was malformed and discarded.
+    MalformedMessage { discarded: Secret<Vec<u8>> },
+    /// A received message exceeded [`ClientFlowOptions::max_response_size`] and was discarded.
+    MessageTooLong { discarded: Secret<Vec<u8>> },
+    /// A received message exceeded a limit configured in [`ClientFlowOptions`] (e.g.
+    /// [`ClientFlowOptions::max_line_length`]) and was discarded.
+    LimitExceeded {
+        limit: ExceededLimit,
+        discarded: Secret<Vec<u8>>,
+    },
+    /// A command continuation request was received although none was expected.
+    UnexpectedContinuationRequest {
+        continuation_request: Box<CommandContinuationRequest<'static>>,
+    },
+    /// A tagged status was received that doesn't belong to any command.
+    UnknownTag { tagged: Box<Tagged<'static>> },
+    /// [`ClientFlow::enqueue_command`] was called with the tag of a command that is not
+    /// completed yet.
+    DuplicateTag { command: Box<Command<'static>> },
+    /// [`ClientFlow::authenticate_continue`] was called although the server didn't request data.
+    AuthenticateDataNotRequested,
+    /// [`ClientFlow::idle_done`] was called although the server didn't accept an `IDLE`.
+    IdleNotAccepted,
+    /// Received bytes couldn't be decompressed after `COMPRESS`.
+    ///
+    /// Note: The connection is unusable and should be closed.
+    #[cfg(feature = "compress")]
+    DecompressionFailed,
+    /// The server sent bytes in plaintext after the tagged `OK` to `STARTTLS`.
+    ///
+    /// The bytes were discarded. This indicates an injection attack (see CVE-2011-0411).
+    /// The `STARTTLS` is still completed through [`ClientFlowEvent::CommandCompleted`].
+    #[cfg(feature = "starttls")]
+    StartTlsInjection { discarded: Secret<Vec<u8>> },
+    /// [`ClientFlow::tls_established`] was called although the server didn't accept a
+    /// `STARTTLS`.
+    #[cfg(feature = "starttls")]
+    StartTlsNotAccepted,
+}
+
+impl ClientFlowError {
+    fn from_decode_message_error<C>(err: DecodeMessageError<C>, message: &[u8]) -> Self
+    where
+        C: Decoder,
+    {
+        let discarded = Secret::new(message.to_vec());
+
+        match err {
+            DecodeMessageError::MessageTooLong { .. } => Self::MessageTooLong { discarded },
+            DecodeMessageError::LimitExceeded { limit, .. } => {
+                Self::LimitExceeded { limit, discarded }
+            }
+            DecodeMessageError::DecodingFailure(_)
+            | DecodeMessageError::DecodingRemainder { .. }
+            | DecodeMessageError::MessagePoisoned { .. } => Self::MalformedMessage { discarded },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{
+        auth::AuthMechanism,
+        extensions::compress::CompressionAlgorithm,
+        mailbox::Mailbox,
+        response::{Capability, StatusKind},
+    };
+
+    use super::*;
+
+    fn receive(client: &mut ClientFlow, bytes: &[u8]) -> Vec<ClientFlowEvent> {
+        client.enqueue_input(bytes);
+
+        let mut events = Vec::new();
+        while let Some(event) = client.progress().unwrap() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn greeted() -> ClientFlow {
+        let mut client = ClientFlow::new(ClientFlowOptions::default());
+        receive(&mut client, b"* OK ...\r\n");
+        client
+    }
+
+    #[test]
+    fn test_client_flow_waits_for_greeting() {
+        let mut client = ClientFlow::new(ClientFlowOptions::default());
+        client
+            .enqueue_command(Command::new("A1", CommandBody::Noop).unwrap())
+            .unwrap();
+        assert!(client.take_output().is_empty());
+
+        let events = receive(&mut client, b"* PREAUTH ...\r\n");
+        assert!(matches!(
+            events[0],
+            ClientFlowEvent::GreetingReceived { .. }
+        ));
+        assert_eq!(
+            events[1],
+            ClientFlowEvent::CommandSent {
+                tag: Tag::try_from("A1").unwrap()
+            }
+        );
+        assert_eq!(client.state(), &State::Authenticated);
+        assert_eq!(client.take_output(), b"A1 NOOP\r\n");
+    }
+
+    #[test]
+    fn test_client_flow_sync_literal() {
+        let mut client = greeted();
+        client
+            .enqueue_command(
+                Command::new("A1", CommandBody::login("alice", "\u{e4}").unwrap()).unwrap(),
+            )
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+        let _ = client.progress();
+        assert_eq!(client.take_output(), b"A1 LOGIN alice {2}\r\n");
+
+        let events = receive(&mut client, b"+ ...\r\n");
+        assert_eq!(client.take_output(), "\u{e4}\r\nA2 NOOP\r\n".as_bytes());
+        assert_eq!(events.len(), 2);
+
+        let events = receive(&mut client, b"A1 OK ...\r\nA2 OK ...\r\n");
+        assert!(matches!(
+            &events[0],
+            ClientFlowEvent::CommandCompleted { command, status } if command.tag.as_ref() == "A1" && status.kind == StatusKind::Ok
+        ));
+        assert_eq!(client.state(), &State::Authenticated);
+    }
+
+    #[test]
+    fn test_client_flow_literal_plus() {
+        let mut client = greeted();
+        client.set_literal_policy(LiteralPolicy::from_capabilities(&[Capability::LiteralPlus]));
+        client
+            .enqueue_command(
+                Command::new("A1", CommandBody::login("alice", "\u{e4}").unwrap()).unwrap(),
+            )
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+
+        // No command continuation request is awaited.
+        let events = receive(&mut client, b"");
+        assert_eq!(
+            client.take_output(),
+            "A1 LOGIN alice {2+}\r\n\u{e4}\r\nA2 NOOP\r\n".as_bytes()
+        );
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_client_flow_rejected_literal() {
+        let mut client = greeted();
+        client
+            .enqueue_command(
+                Command::new("A1", CommandBody::login("alice", "\u{e4}").unwrap()).unwrap(),
+            )
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+        let _ = client.take_output();
+
+        let events = receive(&mut client, b"A1 NO too big\r\n");
+        assert!(matches!(
+            &events[0],
+            ClientFlowEvent::CommandCompleted { command, .. } if command.tag.as_ref() == "A1"
+        ));
+        assert_eq!(client.take_output(), b"A2 NOOP\r\n");
+        assert_eq!(client.state(), &State::NotAuthenticated);
+    }
+
+    #[test]
+    fn test_client_flow_authenticate() {
+        let mut client = greeted();
+        client
+            .enqueue_command(
+                Command::new("A1", CommandBody::authenticate(AuthMechanism::Plain)).unwrap(),
+            )
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+        assert_eq!(client.take_output(), b"A1 AUTHENTICATE PLAIN\r\n");
+        assert_eq!(
+            client.authenticate_continue(AuthenticateData::Cancel),
+            Err(ClientFlowError::AuthenticateDataNotRequested)
+        );
+
+        let events = receive(&mut client, b"+ \r\n");
+        assert!(matches!(
+            events.last(),
+            Some(ClientFlowEvent::AuthenticateContinuationRequestReceived { .. })
+        ));
+
+        client
+            .authenticate_continue(AuthenticateData::r#continue(b"\x00alice\x00pass".to_vec()))
+            .unwrap();
+        assert_eq!(client.take_output(), b"AGFsaWNlAHBhc3M=\r\n");
+
+        receive(&mut client, b"A1 OK ...\r\n");
+        assert_eq!(client.state(), &State::Authenticated);
+        assert_eq!(client.take_output(), b"A2 NOOP\r\n");
+    }
+
+    #[test]
+    fn test_client_flow_idle() {
+        let mut client = ClientFlow::new(ClientFlowOptions::default());
+        receive(&mut client, b"* PREAUTH ...\r\n");
+        client
+            .enqueue_command(Command::new("A1", CommandBody::select("INBOX").unwrap()).unwrap())
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Idle).unwrap())
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A3", CommandBody::Noop).unwrap())
+            .unwrap();
+        assert_eq!(client.take_output(), b"A1 SELECT INBOX\r\nA2 IDLE\r\n");
+        assert_eq!(client.idle_done(), Err(ClientFlowError::IdleNotAccepted));
+
+        receive(&mut client, b"A1 OK ...\r\n");
+        assert_eq!(client.state(), &State::Selected(Mailbox::Inbox));
+
+        let events = receive(&mut client, b"+ idling\r\n* 1 EXISTS\r\n");
+        assert!(matches!(events[0], ClientFlowEvent::IdleAccepted { .. }));
+        assert!(matches!(events[1], ClientFlowEvent::DataReceived { .. }));
+        assert_eq!(
+            client.state(),
+            &State::IdleSelected(Tag::try_from("A2").unwrap(), Mailbox::Inbox)
+        );
+
+        client.idle_done().unwrap();
+        assert_eq!(client.take_output(), b"DONE\r\n");
+
+        receive(&mut client, b"A2 OK ...\r\n");
+        assert_eq!(client.state(), &State::Selected(Mailbox::Inbox));
+        assert_eq!(client.take_output(), b"A3 NOOP\r\n");
+    }
+
+    #[test]
+    fn test_client_flow_compress_is_exclusive() {
+        let mut client = greeted();
+        client
+            .enqueue_command(
+                Command::new(
+                    "A1",
+                    CommandBody::Compress {
+                        algorithm: CompressionAlgorithm::Deflate,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+        assert_eq!(client.take_output(), b"A1 COMPRESS DEFLATE\r\n");
+
+        receive(&mut client, b"A1 NO ...\r\n");
+        assert_eq!(client.take_output(), b"A2 NOOP\r\n");
+    }
+
+    #[test]
+    fn test_client_flow_errors() {
+        let mut client = greeted();
+
+        client.enqueue_input(b"+ ...\r\n");
+        assert!(matches!(
+            client.progress(),
+            Err(ClientFlowError::UnexpectedContinuationRequest { .. })
+        ));
+
+        client.enqueue_input(b"A1 OK ...\r\n");
+        assert!(matches!(
+            client.progress(),
+            Err(ClientFlowError::UnknownTag { .. })
+        ));
+
+        client.enqueue_input(b"* xxx\r\n* BYE ...\r\n");
+        assert!(matches!(
+            client.progress(),
+            Err(ClientFlowError::MalformedMessage { .. })
+        ));
+        assert!(matches!(
+            client.progress(),
+            Ok(Some(ClientFlowEvent::StatusReceived { .. }))
+        ));
+        assert_eq!(client.state(), &State::Logout);
+    }
+
+    #[test]
+    fn test_client_flow_duplicate_tag() {
+        let mut client = ClientFlow::new(ClientFlowOptions::default());
+        let noop = Command::new("A1", CommandBody::Noop).unwrap();
+
+        // Queued
+        client.enqueue_command(noop.clone()).unwrap();
+        assert!(matches!(
+            client.enqueue_command(noop.clone()),
+            Err(ClientFlowError::DuplicateTag { .. })
+        ));
+
+        // Sent
+        receive(&mut client, b"* OK ...\r\n");
+        assert!(matches!(
+            client.enqueue_command(noop.clone()),
+            Err(ClientFlowError::DuplicateTag { .. })
+        ));
+
+        // Completed
+        receive(&mut client, b"A1 OK ...\r\n");
+        client.enqueue_command(noop).unwrap();
+        assert_eq!(client.take_output(), b"A1 NOOP\r\nA1 NOOP\r\n");
+    }
+
+    #[test]
+    fn test_client_flow_lenient() {
+        let mut client = ClientFlow::new(ClientFlowOptions {
+            lenient: true,
+            ..Default::default()
+        });
+        receive(&mut client, b"* OK ...\r\n");
+
+        let events = receive(&mut client, b"* XVENDOR {3}\r\nabc\r\n* 1 EXISTS\r\n");
+        assert_eq!(
+            events,
+            vec![
+                ClientFlowEvent::UnparsedReceived {
+                    unparsed: Unparsed::unvalidated(b"* XVENDOR {3}\r\nabc\r\n".as_ref()),
+                },
+                ClientFlowEvent::DataReceived {
+                    data: Data::Exists(1)
+                },
+            ]
+        );
+
+        // A malformed tagged status completes its command.
+        client
+            .enqueue_command(Command::new("A1", CommandBody::Noop).unwrap())
+            .unwrap();
+        let _ = client.progress();
+        assert_eq!(client.take_output(), b"A1 NOOP\r\n");
+
+        let events = receive(&mut client, b"A1 OK [ALERT\r\n");
+        assert!(matches!(
+            &events[..],
+            [ClientFlowEvent::CommandCompleted { command, status }]
+                if command.tag.as_ref() == "A1" && status.kind == StatusKind::Ok
+        ));
+    }
+
+    #[test]
+    fn test_client_flow_quirks() {
+        let mut client = ClientFlow::new(ClientFlowOptions {
+            quirks: Quirks::none(),
+            ..Default::default()
+        });
+        client.enqueue_input(b"* OK ...\n");
+        assert!(matches!(
+            client.progress(),
+            Err(ClientFlowError::MalformedMessage { .. })
+        ));
+
+        let mut client = ClientFlow::new(ClientFlowOptions {
+            quirks: Quirks::all(),
+            ..Default::default()
+        });
+        receive(&mut client, b"* OK ...\n");
+        assert_eq!(client.state(), &State::NotAuthenticated);
+
+        let events = receive(&mut client, b"* 1 EXISTS\n");
+        assert_eq!(
+            events,
+            vec![ClientFlowEvent::DataReceived {
+                data: Data::Exists(1)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_client_flow_limit_exceeded() {
+        let mut client = ClientFlow::new(ClientFlowOptions {
+            max_literal_size: Some(2),
+            ..Default::default()
+        });
+        receive(&mut client, b"* OK ...\r\n");
+
+        client.enqueue_input(b"* 1 FETCH (BODY[] {3}\r\nabc)\r\n* 1 EXISTS\r\n");
+        assert!(matches!(
+            client.progress(),
+            Err(ClientFlowError::LimitExceeded {
+                limit: ExceededLimit::LiteralSize { max: 2, length: 3 },
+                ..
+            })
+        ));
+        assert_eq!(
+            client.progress(),
+            Ok(Some(ClientFlowEvent::DataReceived {
+                data: Data::Exists(1)
+            }))
+        );
+    }
+
+    #[cfg(feature = "starttls")]
+    #[test]
+    fn test_client_flow_starttls_injection() {
+        let mut client = greeted();
+        client
+            .enqueue_command(Command::new("A1", CommandBody::StartTLS).unwrap())
+            .unwrap();
+        client
+            .enqueue_command(Command::new("A2", CommandBody::Noop).unwrap())
+            .unwrap();
+        let _ = client.progress();
+        assert_eq!(client.take_output(), b"A1 STARTTLS\r\n");
+
+        client.enqueue_input(b"A1 OK Begin TLS now\r\n* CAPABILITY IMAP4rev1 AUTH=PLAIN\r\n");
+        assert_eq!(
+            client.progress(),
+            Err(ClientFlowError::StartTlsInjection {
+                discarded: Secret::new(b"* CAPABILITY IMAP4rev1 AUTH=PLAIN\r\n".to_vec())
+            })
+        );
+        assert!(matches!(
+            client.progress(),
+            Ok(Some(ClientFlowEvent::CommandCompleted { command, .. })) if command.tag.as_ref() == "A1"
+        ));
+
+        // Held back until the upgrade
+        assert_eq!(client.progress(), Ok(None));
+        assert_eq!(client.take_output(), b"");
+
+        client.tls_established().unwrap();
+        assert_eq!(
+            client.tls_established(),
+            Err(ClientFlowError::StartTlsNotAccepted)
+        );
+        assert!(matches!(
+            client.progress(),
+            Ok(Some(ClientFlowEvent::CommandSent { tag })) if tag.as_ref() == "A2"
+        ));
+        assert_eq!(client.progress(), Ok(None));
+        assert_eq!(client.take_output(), b"A2 NOOP\r\n");
+    }
+}
@@ -0,0 +1,469 @@
+//! Typed command results aggregated from untagged responses.
+//!
+//! Most commands return their result through untagged responses, e.g., `SELECT` results in
+//! `* FLAGS ...`, `* 23 EXISTS`, `* OK [UIDVALIDITY ...]`, etc.
+//! The collectors in this module consume these responses and produce a single typed value.
+//!
+//! # Example
+//!
+//! ```rust
+//! use imap_codec::{
+//!     decode::Decoder,
+//!     flow::collect::{Collect, SelectResult},
+//!     imap_types::response::{Response, Status},
+//!     ResponseCodec,
+//! };
+//!
+//! let mut result = SelectResult::default();
+//!
+//! for line in [
+//!     b"* 172 EXISTS\r\n".as_ref(),
+//!     b"* OK [UIDVALIDITY 3857529045] UIDs valid\r\n",
+//!     b"* FLAGS (\\Answered \\Flagged \\Deleted \\Seen \\Draft)\r\n",
+//! ] {
+//!     let (_, response) = ResponseCodec::default().decode_static(line).unwrap();
+//!     assert_eq!(result.collect(response), None);
+//! }
+//!
+//! let (_, response) = ResponseCodec::default()
+//!     .decode_static(b"A142 OK [READ-WRITE] SELECT completed\r\n")
+//!     .unwrap();
+//! let Response::Status(Status::Tagged(tagged)) = response else {
+//!     unreachable!()
+//! };
+//!
+//! let result = result.finish(tagged.body).unwrap();
+//! assert_eq!(result.exists, Some(172));
+//! assert!(!result.read_only);
+//! ```
+
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use imap_types::{
+    core::{AString, QuotedChar, Vec1},
+    extensions::quota::QuotaGet,
+    fetch::MessageDataItem,
+    flag::{Flag, FlagNameAttribute, FlagPerm},
+    mailbox::Mailbox,
+    response::{Code, Data, Response, Status, StatusBody, StatusKind},
+    status::StatusDataItem,
+};
+
+/// Collects untagged responses of a command into a typed result.
+pub trait Collect: Sized {
+    /// Processes an untagged response.
+    ///
+    /// Returns the response when it doesn't belong to the result, e.g., an unsolicited `EXISTS`
+    /// during `FETCH`.
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>>;
+
+    /// Finishes the result with the status of the tagged response.
+    ///
+    /// Returns the status when the command wasn't successful.
+    fn finish(self, status: StatusBody<'static>) -> Result<Self, Box<StatusBody<'static>>> {
+        match status.kind {
+            StatusKind::Ok => Ok(self),
+            StatusKind::No | StatusKind::Bad => Err(Box::new(status)),
+        }
+    }
+}
+
+/// Result of `SELECT` or `EXAMINE`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SelectResult {
+    /// Flags defined in the mailbox (`* FLAGS ...`).
+    pub flags: Vec<Flag<'static>>,
+    /// Number of messages (`* n EXISTS`).
+    pub exists: Option<u32>,
+    /// Number of recent messages (`* n RECENT`).
+    pub recent: Option<u32>,
+    /// Flags the client can change permanently (`[PERMANENTFLAGS ...]`).
+    pub permanent_flags: Option<Vec<FlagPerm<'static>>>,
+    /// UID validity (`[UIDVALIDITY n]`).
+    pub uid_validity: Option<NonZeroU32>,
+    /// Predicted next UID (`[UIDNEXT n]`).
+    pub uid_next: Option<NonZeroU32>,
+    /// Sequence number of the first unseen message (`[UNSEEN n]`).
+    pub unseen: Option<NonZeroU32>,
+    /// Whether the mailbox was selected read-only (`[READ-ONLY]` in the tagged response).
+    pub read_only: bool,
+}
+
+impl Collect for SelectResult {
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>> {
+        match response {
+            Response::Data(Data::Flags(flags)) => self.flags = flags,
+            Response::Data(Data::Exists(exists)) => self.exists = Some(exists),
+            Response::Data(Data::Recent(recent)) => self.recent = Some(recent),
+            Response::Status(Status::Untagged(StatusBody {
+                kind: StatusKind::Ok,
+                code: Some(code),
+                text,
+            })) => match code {
+                Code::PermanentFlags(flags) => self.permanent_flags = Some(flags),
+                Code::UidValidity(uid_validity) => self.uid_validity = Some(uid_validity),
+                Code::UidNext(uid_next) => self.uid_next = Some(uid_next),
+                Code::Unseen(unseen) => self.unseen = Some(unseen),
+                code => {
+                    return Some(Response::Status(Status::Untagged(StatusBody {
+                        kind: StatusKind::Ok,
+                        code: Some(code),
+                        text,
+                    })))
+                }
+            },
+            response => return Some(response),
+        }
+
+        None
+    }
+
+    fn finish(mut self, status: StatusBody<'static>) -> Result<Self, Box<StatusBody<'static>>> {
+        if status.kind != StatusKind::Ok {
+            return Err(Box::new(status));
+        }
+
+        self.read_only = status.code == Some(Code::ReadOnly);
+
+        Ok(self)
+    }
+}
+
+/// Mailbox returned by `LIST` or `LSUB` (or `XLIST`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ListItem {
+    /// Mailbox attributes.
+    pub items: Vec<FlagNameAttribute<'static>>,
+    /// Hierarchy delimiter.
+    pub delimiter: Option<QuotedChar>,
+    /// Mailbox name.
+    pub mailbox: Mailbox<'static>,
+}
+
+/// Result of `LIST` or `LSUB` (or `XLIST`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ListResult {
+    /// Mailboxes in the order they were received.
+    pub mailboxes: Vec<ListItem>,
+}
+
+impl Collect for ListResult {
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>> {
+        match response {
+            Response::Data(
+                Data::List {
+                    items,
+                    delimiter,
+                    mailbox,
+                }
+                | Data::Lsub {
+                    items,
+                    delimiter,
+                    mailbox,
+                },
+            ) => {
+                self.mailboxes.push(ListItem {
+                    items,
+                    delimiter,
+                    mailbox,
+                });
+
+                None
+            }
+            // `XLIST` has the same structure as `LIST`.
+            #[cfg(feature = "ext_xlist")]
+            Response::Data(Data::XList {
+                items,
+                delimiter,
+                mailbox,
+            }) => self.collect(Response::Data(Data::List {
+                items,
+                delimiter,
+                mailbox,
+            })),
+            response => Some(response),
+        }
+    }
+}
+
+/// Result of `FETCH` (or `UID FETCH`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FetchResult {
+    /// Message data items by message sequence number.
+    ///
+    /// Note: Items of multiple `FETCH` responses for the same message are merged.
+    pub messages: BTreeMap<NonZeroU32, Vec<MessageDataItem<'static>>>,
+}
+
+impl Collect for FetchResult {
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>> {
+        match response {
+            Response::Data(Data::Fetch { seq, items }) => {
+                self.messages.entry(seq).or_default().extend(items);
+
+                None
+            }
+            response => Some(response),
+        }
+    }
+}
+
+/// Result of `SEARCH` (or `UID SEARCH`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SearchResult {
+    /// Message sequence numbers (or UIDs).
+    pub ids: Vec<NonZeroU32>,
+}
+
+impl Collect for SearchResult {
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>> {
+        match response {
+            Response::Data(Data::Search(ids)) => {
+                self.ids.extend(ids);
+
+                None
+            }
+            response => Some(response),
+        }
+    }
+}
+
+/// Result of `STATUS`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StatusResult {
+    /// Mailbox name.
+    pub mailbox: Option<Mailbox<'static>>,
+    /// Status data items.
+    pub items: Vec<StatusDataItem>,
+}
+
+impl Collect for StatusResult {
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>> {
+        match response {
+            Response::Data(Data::Status { mailbox, items }) => {
+                self.mailbox = Some(mailbox);
+                self.items.extend(items.iter().cloned());
+
+                None
+            }
+            response => Some(response),
+        }
+    }
+}
+
+/// Result of `GETQUOTA` or `GETQUOTAROOT`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QuotaResult {
+    /// Quota roots of the mailbox (`GETQUOTAROOT` only).
+    pub roots: Vec<AString<'static>>,
+    /// Resource usage and limits by quota root.
+    pub quotas: Vec<(AString<'static>, Vec1<QuotaGet<'static>>)>,
+}
+
+impl Collect for QuotaResult {
+    fn collect(&mut self, response: Response<'static>) -> Option<Response<'static>> {
+        match response {
+            Response::Data(Data::QuotaRoot { roots, .. }) => {
+                self.roots.extend(roots);
+
+                None
+            }
+            Response::Data(Data::Quota { root, quotas }) => {
+                self.quotas.push((root, quotas));
+
+                None
+            }
+            response => Some(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::response::Tagged;
+
+    use super::*;
+    use crate::{decode::Decoder, ResponseCodec};
+
+    fn run<C>(
+        mut collector: C,
+        lines: &[&[u8]],
+        tagged: &[u8],
+    ) -> (Result<C, Box<StatusBody<'static>>>, Vec<Response<'static>>)
+    where
+        C: Collect,
+    {
+        let mut rest = Vec::new();
+
+        for line in lines {
+            let (_, response) = ResponseCodec::default().decode_static(line).unwrap();
+
+            if let Some(response) = collector.collect(response) {
+                rest.push(response);
+            }
+        }
+
+        let (_, response) = ResponseCodec::default().decode_static(tagged).unwrap();
+        let Response::Status(Status::Tagged(Tagged { body, .. })) = response else {
+            panic!("expected tagged status");
+        };
+
+        (collector.finish(body), rest)
+    }
+
+    #[test]
+    fn test_select_result() {
+        let (result, rest) = run(
+            SelectResult::default(),
+            &[
+                b"* 172 EXISTS\r\n",
+                b"* 1 RECENT\r\n",
+                b"* OK [UNSEEN 12] Message 12 is first unseen\r\n",
+                b"* OK [UIDVALIDITY 3857529045] UIDs valid\r\n",
+                b"* OK [UIDNEXT 4392] Predicted next UID\r\n",
+                b"* FLAGS (\\Answered \\Flagged \\Deleted \\Seen \\Draft)\r\n",
+                b"* OK [PERMANENTFLAGS (\\Deleted \\Seen \\*)] Limited\r\n",
+                b"* OK [ALERT] Hello\r\n",
+            ],
+            b"A142 OK [READ-ONLY] EXAMINE completed\r\n",
+        );
+        let result = result.unwrap();
+
+        assert_eq!(result.exists, Some(172));
+        assert_eq!(result.recent, Some(1));
+        assert_eq!(result.unseen, NonZeroU32::new(12));
+        assert_eq!(result.uid_validity, NonZeroU32::new(3857529045));
+        assert_eq!(result.uid_next, NonZeroU32::new(4392));
+        assert_eq!(result.flags.len(), 5);
+        assert_eq!(
+            result.permanent_flags,
+            Some(vec![
+                FlagPerm::Flag(Flag::Deleted),
+                FlagPerm::Flag(Flag::Seen),
+                FlagPerm::Asterisk
+            ])
+        );
+        assert!(result.read_only);
+        assert_eq!(rest.len(), 1);
+    }
+
+    #[test]
+    fn test_select_result_failed() {
+        let (result, _) = run(
+            SelectResult::default(),
+            &[],
+            b"A1 NO [TRYCREATE] No such mailbox\r\n",
+        );
+
+        assert_eq!(result.unwrap_err().code, Some(Code::TryCreate));
+    }
+
+    #[test]
+    fn test_list_result() {
+        let (result, _) = run(
+            ListResult::default(),
+            &[
+                b"* LIST (\\Noselect) \"/\" \"\"\r\n",
+                b"* LIST () \"/\" INBOX\r\n",
+            ],
+            b"A1 OK done\r\n",
+        );
+        let result = result.unwrap();
+
+        assert_eq!(result.mailboxes.len(), 2);
+        assert_eq!(result.mailboxes[1].mailbox, Mailbox::Inbox);
+        assert_eq!(
+            result.mailboxes[1].delimiter,
+            Some(QuotedChar::try_from('/').unwrap())
+        );
+    }
+
+    #[cfg(feature = "ext_xlist")]
+    #[test]
+    fn test_list_result_xlist() {
+        let (result, _) = run(
+            ListResult::default(),
+            &[
+                b"* XLIST (\\HasNoChildren \\Inbox) \"/\" \"Inbox\"\r\n",
+                b"* LIST (\\HasNoChildren \\Sent) \"/\" Sent\r\n",
+            ],
+            b"A1 OK done\r\n",
+        );
+        let result = result.unwrap();
+
+        assert_eq!(result.mailboxes.len(), 2);
+        assert_eq!(result.mailboxes[0].mailbox, Mailbox::Inbox);
+        assert_eq!(
+            result.mailboxes[1].mailbox,
+            Mailbox::try_from("Sent").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fetch_result() {
+        let (result, rest) = run(
+            FetchResult::default(),
+            &[
+                b"* 1 FETCH (UID 10)\r\n",
+                b"* 3 EXISTS\r\n",
+                b"* 2 FETCH (UID 11)\r\n",
+                b"* 1 FETCH (RFC822.SIZE 42)\r\n",
+            ],
+            b"A1 OK done\r\n",
+        );
+        let result = result.unwrap();
+
+        assert_eq!(
+            result.messages[&NonZeroU32::new(1).unwrap()],
+            vec![
+                MessageDataItem::Uid(NonZeroU32::new(10).unwrap()),
+                MessageDataItem::Rfc822Size(42)
+            ]
+        );
+        assert_eq!(result.messages.len(), 2);
+        assert_eq!(rest, vec![Response::Data(Data::Exists(3))]);
+    }
+
+    #[test]
+    fn test_search_status_quota_result() {
+        let (result, _) = run(
+            SearchResult::default(),
+            &[b"* SEARCH 2 84 882\r\n"],
+            b"A1 OK done\r\n",
+        );
+        assert_eq!(
+            result.unwrap().ids,
+            [2, 84, 882].map(|id| NonZeroU32::new(id).unwrap())
+        );
+
+        let (result, _) = run(
+            StatusResult::default(),
+            &[b"* STATUS blurdybloop (MESSAGES 231 UIDNEXT 44292)\r\n"],
+            b"A1 OK done\r\n",
+        );
+        let result = result.unwrap();
+        assert_eq!(
+            result.mailbox,
+            Some(Mailbox::try_from("blurdybloop").unwrap())
+        );
+        assert_eq!(
+            result.items,
+            vec![
+                StatusDataItem::Messages(231),
+                StatusDataItem::UidNext(NonZeroU32::new(44292).unwrap())
+            ]
+        );
+
+        let (result, _) = run(
+            QuotaResult::default(),
+            &[
+                b"* QUOTAROOT INBOX \"\"\r\n",
+                b"* QUOTA \"\" (STORAGE 10 512)\r\n",
+            ],
+            b"A1 OK done\r\n",
+        );
+        let result = result.unwrap();
+        assert_eq!(result.roots, vec![AString::try_from("").unwrap()]);
+        assert_eq!(result.quotas.len(), 1);
+    }
+}
@@ -0,0 +1,364 @@
+//! Client-side view of the selected mailbox.
+//!
+//! "The EXPUNGE response reports that the specified message sequence number has been permanently
+//! removed from the mailbox. The message sequence number for each successive message in the
+//! mailbox is immediately decremented by 1, and this decrement is reflected in message sequence
+//! numbers in subsequent responses (including other untagged EXPUNGE responses)."
+//! ([RFC 3501, 7.4.1](https://www.rfc-editor.org/rfc/rfc3501#section-7.4.1))
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::num::NonZeroU32;
+//!
+//! use imap_codec::{flow::mailbox::MailboxView, imap_types::response::Data};
+//!
+//! let mut view = MailboxView::new();
+//!
+//! view.apply(&Data::Exists(3)).unwrap();
+//! let expunged = NonZeroU32::new(2).unwrap();
+//! view.apply(&Data::Expunge(expunged)).unwrap();
+//!
+//! assert_eq!(view.exists(), 2);
+//! ```
+
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use imap_types::{fetch::MessageDataItem, flag::FlagFetch, response::Data, IntoStatic};
+
+use crate::flow::collect::SelectResult;
+
+/// Consistent view of the selected mailbox.
+///
+/// The view applies `EXISTS`, `RECENT`, `EXPUNGE`, and `FETCH` responses and keeps track of the
+/// message sequence number to UID mapping as well as per-message flags.
+/// `EXPUNGE` responses renumber all following messages.
+///
+/// Only messages with a known UID or flags are stored, i.e., a (valid) `* 4294967295 EXISTS`
+/// doesn't allocate memory for every message.
+///
+/// Note: `VANISHED` responses (QRESYNC) are not supported yet.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MailboxView {
+    exists: u32,
+    /// Known messages by message sequence number.
+    known: BTreeMap<u32, Message>,
+    recent: u32,
+    uid_validity: Option<NonZeroU32>,
+}
+
+/// Message without a known UID or flags.
+static UNKNOWN: Message = Message {
+    uid: None,
+    flags: None,
+};
+
+/// Message in a [`MailboxView`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Message {
+    uid: Option<NonZeroU32>,
+    flags: Option<Vec<FlagFetch<'static>>>,
+}
+
+impl Message {
+    /// Returns the UID (if already known).
+    pub fn uid(&self) -> Option<NonZeroU32> {
+        self.uid
+    }
+
+    /// Returns the flags (if already known).
+    pub fn flags(&self) -> Option<&[FlagFetch<'static>]> {
+        self.flags.as_deref()
+    }
+}
+
+impl MailboxView {
+    /// Creates an empty view.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a view from the result of `SELECT` or `EXAMINE`.
+    pub fn from_select(result: &SelectResult) -> Self {
+        Self {
+            exists: result.exists.unwrap_or_default(),
+            known: BTreeMap::new(),
+            recent: result.recent.unwrap_or_default(),
+            uid_validity: result.uid_validity,
+        }
+    }
+
+    /// Applies an untagged data response.
+    ///
+    /// Responses not affecting the view are ignored.
+    pub fn apply(&mut self, data: &Data<'_>) -> Result<(), MailboxViewError> {
+        match data {
+            Data::Exists(exists) => {
+                if *exists < self.exists {
+                    return Err(MailboxViewError::ExistsDecreased {
+                        exists: *exists,
+                        previous: self.exists,
+                    });
+                }
+
+                self.exists = *exists;
+            }
+            Data::Recent(recent) => self.recent = *recent,
+            Data::Expunge(seq) => {
+                let seq = self.check(*seq)?;
+
+                // Renumber all following (known) messages.
+                let following = self.known.split_off(&seq);
+                self.known.extend(
+                    following
+                        .into_iter()
+                        .filter(|(following, _)| *following != seq)
+                        .map(|(following, message)| (following - 1, message)),
+                );
+                self.exists -= 1;
+            }
+            Data::Fetch { seq, items } => {
+                let message = self.known.entry(self.check(*seq)?).or_default();
+
+                for item in items.as_ref() {
+                    match item {
+                        MessageDataItem::Uid(uid) => match message.uid {
+                            Some(known) if known != *uid => {
+                                return Err(MailboxViewError::UidChanged {
+                                    seq: *seq,
+                                    uid: *uid,
+                                    previous: known,
+                                });
+                            }
+                            _ => message.uid = Some(*uid),
+                        },
+                        MessageDataItem::Flags(flags) => {
+                            message.flags = Some(flags.clone().into_static());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of messages.
+    pub fn exists(&self) -> u32 {
+        self.exists
+    }
+
+    /// Returns the number of recent messages.
+    pub fn recent(&self) -> u32 {
+        self.recent
+    }
+
+    /// Returns the UID validity (if known).
+    pub fn uid_validity(&self) -> Option<NonZeroU32> {
+        self.uid_validity
+    }
+
+    /// Returns the message with the given message sequence number.
+    pub fn message(&self, seq: NonZeroU32) -> Option<&Message> {
+        if seq.get() <= self.exists {
+            Some(self.known.get(&seq.get()).unwrap_or(&UNKNOWN))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the UID of the message with the given message sequence number (if known).
+    pub fn uid(&self, seq: NonZeroU32) -> Option<NonZeroU32> {
+        self.message(seq).and_then(Message::uid)
+    }
+
+    /// Returns the message sequence number of the message with the given UID (if known).
+    pub fn seq(&self, uid: NonZeroU32) -> Option<NonZeroU32> {
+        self.known
+            .iter()
+            .find(|(_, message)| message.uid == Some(uid))
+            .and_then(|(seq, _)| NonZeroU32::new(*seq))
+    }
+
+    /// Returns all messages together with their message sequence number.
+    ///
+    /// Note: This includes messages without a known UID or flags.
+    pub fn messages(&self) -> impl Iterator<Item = (NonZeroU32, &Message)> {
+        (1..=self.exists)
+            .filter_map(NonZeroU32::new)
+            .map(|seq| (seq, self.known.get(&seq.get()).unwrap_or(&UNKNOWN)))
+    }
+
+    fn check(&self, seq: NonZeroU32) -> Result<u32, MailboxViewError> {
+        if seq.get() <= self.exists {
+            Ok(seq.get())
+        } else {
+            Err(MailboxViewError::UnknownSequenceNumber {
+                seq,
+                exists: self.exists(),
+            })
+        }
+    }
+}
+
+/// Error returned by [`MailboxView::apply`].
+///
+/// All errors indicate that the server (or the client) lost track of the mailbox. The view is not
+/// modified by a failed [`MailboxView::apply`] (except for `FETCH` items preceding the error).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MailboxViewError {
+    /// `EXISTS` reported fewer messages than known (without `EXPUNGE`).
+    ExistsDecreased { exists: u32, previous: u32 },
+    /// `EXPUNGE` or `FETCH` referenced a message sequence number greater than `EXISTS`.
+    UnknownSequenceNumber { seq: NonZeroU32, exists: u32 },
+    /// `FETCH` reported a different UID for a known message.
+    UidChanged {
+        seq: NonZeroU32,
+        uid: NonZeroU32,
+        previous: NonZeroU32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{core::Vec1, flag::Flag};
+
+    use super::*;
+
+    fn nz(value: u32) -> NonZeroU32 {
+        NonZeroU32::new(value).unwrap()
+    }
+
+    fn fetch_uid(seq: u32, uid: u32) -> Data<'static> {
+        Data::Fetch {
+            seq: nz(seq),
+            items: Vec1::from(MessageDataItem::Uid(nz(uid))),
+        }
+    }
+
+    #[test]
+    fn test_mailbox_view_expunge_renumbering() {
+        let mut view = MailboxView::new();
+
+        view.apply(&Data::Exists(9)).unwrap();
+        for seq in 1..=9 {
+            view.apply(&fetch_uid(seq, seq + 100)).unwrap();
+        }
+
+        // "If the last 5 messages in a 9-message mailbox are expunged, a 9-message mailbox
+        // could send 5 untagged EXPUNGE responses of '* 5 EXPUNGE'."
+        for _ in 0..5 {
+            view.apply(&Data::Expunge(nz(5))).unwrap();
+        }
+        assert_eq!(view.exists(), 4);
+        assert_eq!(view.uid(nz(4)), Some(nz(104)));
+
+        // Remove the first message: All following messages are renumbered.
+        view.apply(&Data::Expunge(nz(1))).unwrap();
+        assert_eq!(view.exists(), 3);
+        assert_eq!(view.uid(nz(1)), Some(nz(102)));
+        assert_eq!(view.seq(nz(104)), Some(nz(3)));
+        assert_eq!(view.seq(nz(101)), None);
+        assert_eq!(
+            view.messages()
+                .map(|(seq, message)| (seq.get(), message.uid().unwrap().get()))
+                .collect::<Vec<_>>(),
+            vec![(1, 102), (2, 103), (3, 104)]
+        );
+
+        // New message
+        view.apply(&Data::Exists(4)).unwrap();
+        assert_eq!(view.uid(nz(4)), None);
+    }
+
+    #[test]
+    fn test_mailbox_view_flags() {
+        let mut view = MailboxView::from_select(&SelectResult {
+            exists: Some(2),
+            recent: Some(1),
+            uid_validity: Some(nz(42)),
+            ..Default::default()
+        });
+        assert_eq!(view.exists(), 2);
+        assert_eq!(view.recent(), 1);
+        assert_eq!(view.uid_validity(), Some(nz(42)));
+
+        view.apply(&Data::Fetch {
+            seq: nz(2),
+            items: vec![
+                MessageDataItem::Uid(nz(7)),
+                MessageDataItem::Flags(vec![FlagFetch::Flag(Flag::Seen)]),
+            ]
+            .try_into()
+            .unwrap(),
+        })
+        .unwrap();
+        assert_eq!(
+            view.message(nz(2)).unwrap().flags(),
+            Some([FlagFetch::Flag(Flag::Seen)].as_ref())
+        );
+
+        view.apply(&Data::Expunge(nz(1))).unwrap();
+        assert_eq!(
+            view.message(nz(1)).unwrap().flags(),
+            Some([FlagFetch::Flag(Flag::Seen)].as_ref())
+        );
+    }
+
+    #[test]
+    fn test_mailbox_view_errors() {
+        let mut view = MailboxView::new();
+        view.apply(&Data::Exists(2)).unwrap();
+        view.apply(&fetch_uid(1, 10)).unwrap();
+
+        assert_eq!(
+            view.apply(&Data::Expunge(nz(3))),
+            Err(MailboxViewError::UnknownSequenceNumber {
+                seq: nz(3),
+                exists: 2
+            })
+        );
+        assert_eq!(
+            view.apply(&Data::Exists(1)),
+            Err(MailboxViewError::ExistsDecreased {
+                exists: 1,
+                previous: 2
+            })
+        );
+        assert_eq!(
+            view.apply(&fetch_uid(1, 11)),
+            Err(MailboxViewError::UidChanged {
+                seq: nz(1),
+                uid: nz(11),
+                previous: nz(10)
+            })
+        );
+        assert_eq!(view.exists(), 2);
+    }
+
+    #[test]
+    fn test_mailbox_view_huge_exists() {
+        let mut view = MailboxView::from_select(&SelectResult {
+            exists: Some(u32::MAX),
+            ..Default::default()
+        });
+        assert_eq!(view.exists(), u32::MAX);
+
+        view.apply(&fetch_uid(u32::MAX, 7)).unwrap();
+        view.apply(&fetch_uid(1, 5)).unwrap();
+        assert_eq!(view.uid(nz(u32::MAX - 1)), None);
+
+        view.apply(&Data::Expunge(nz(1))).unwrap();
+        assert_eq!(view.exists(), u32::MAX - 1);
+        assert_eq!(view.uid(nz(u32::MAX - 1)), Some(nz(7)));
+        assert_eq!(view.seq(nz(7)), Some(nz(u32::MAX - 1)));
+        assert_eq!(view.seq(nz(5)), None);
+        assert_eq!(view.message(nz(u32::MAX)), None);
+        assert_eq!(view.messages().next(), Some((nz(1), &Message::default())));
+
+        view.apply(&Data::Exists(u32::MAX)).unwrap();
+        assert_eq!(view.uid(nz(u32::MAX)), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Correlation of tagged responses with pending commands.
+//!
+//! # Example
+//!
+//! ```rust
+//! use imap_codec::{
+//!     flow::registry::CommandRegistry,
+//!     imap_types::{
+//!         command::{Command, CommandBody},
+//!         core::Tag,
+//!         response::Status,
+//!     },
+//! };
+//!
+//! let mut registry = CommandRegistry::new();
+//!
+//! registry
+//!     .register(Command::new("A1", CommandBody::Noop).unwrap())
+//!     .unwrap();
+//!
+//! // Received from the server.
+//! let Status::Tagged(tagged) =
+//!     Status::ok(Some(Tag::try_from("A1").unwrap()), None, "done").unwrap()
+//! else {
+//!     unreachable!()
+//! };
+//! let completed = registry.complete(tagged).unwrap();
+//!
+//! assert_eq!(completed.command.body, CommandBody::Noop);
+//! assert!(registry.is_empty());
+//! ```
+
+#[cfg(feature = "tag_generator")]
+use imap_types::command::CommandBody;
+#[cfg(feature = "tag_generator")]
+use imap_types::core::TagGenerator;
+use imap_types::{
+    command::Command,
+    core::Tag,
+    response::{StatusBody, Tagged},
+    IntoStatic,
+};
+
+/// Registry of pending (i.e., sent but not completed) commands.
+///
+/// Commands are registered by [`Tag`] and completed by the [`Tagged`] response with the same
+/// [`Tag`]. Duplicate and unknown tags are reported as errors.
+#[cfg_attr(
+    feature = "tag_generator",
+    doc = "",
+    doc = " With the `tag_generator` feature, [`CommandRegistry::register_body`] assigns unique",
+    doc = " tags using a [`TagGenerator`]."
+)]
+#[derive(Debug)]
+pub struct CommandRegistry {
+    /// Pending commands in the order of registration.
+    pending: Vec<Command<'static>>,
+    #[cfg(feature = "tag_generator")]
+    tag_generator: TagGenerator,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            #[cfg(feature = "tag_generator")]
+            tag_generator: TagGenerator::new(),
+        }
+    }
+
+    /// Registers a command.
+    ///
+    /// Fails when a command with the same tag is already pending.
+    pub fn register(&mut self, command: Command<'_>) -> Result<(), CommandRegistryError> {
+        if self.contains(&command.tag) {
+            return Err(CommandRegistryError::DuplicateTag {
+                command: Box::new(command.into_static()),
+            });
+        }
+
+        self.pending.push(command.into_static());
+
+        Ok(())
+    }
+
+    /// Registers a command body under a freshly generated tag and returns the command.
+    #[cfg(feature = "tag_generator")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tag_generator")))]
+    pub fn register_body(&mut self, body: CommandBody<'_>) -> Command<'static> {
+        // Generated tags are unique per generator. However, a conflict is still possible when
+        // commands with custom tags were registered.
+        let tag = loop {
+            let tag = self.tag_generator.generate();
+
+            if !self.contains(&tag) {
+                break tag;
+            }
+        };
+
+        let command = Command {
+            tag,
+            body: body.into_static(),
+        };
+        self.pending.push(command.clone());
+
+        command
+    }
+
+    /// Completes the pending command with the tag of the tagged response.
+    ///
+    /// Returns the original command together with the status.
+    pub fn complete(
+        &mut self,
+        tagged: Tagged<'_>,
+    ) -> Result<CompletedCommand, CommandRegistryError> {
+        match self.position(&tagged.tag) {
+            Some(index) => Ok(CompletedCommand {
+                command: self.pending.remove(index),
+                status: tagged.body.into_static(),
+            }),
+            None => Err(CommandRegistryError::UnknownTag {
+                tagged: Box::new(tagged.into_static()),
+            }),
+        }
+    }
+
+    /// Removes a pending command without completing it, e.g., when it couldn't be sent.
+    pub fn remove(&mut self, tag: &Tag) -> Option<Command<'static>> {
+        self.position(tag).map(|index| self.pending.remove(index))
+    }
+
+    /// Returns the pending command with the given tag.
+    pub fn get(&self, tag: &Tag) -> Option<&Command<'static>> {
+        self.pending.iter().find(|command| command.tag == *tag)
+    }
+
+    /// Returns whether a command with the given tag is pending.
+    pub fn contains(&self, tag: &Tag) -> bool {
+        self.position(tag).is_some()
+    }
+
+    /// Returns the pending commands in the order of registration.
+    pub fn pending(&self) -> impl Iterator<Item = &Command<'static>> {
+        self.pending.iter()
+    }
+
+    /// Returns the number of pending commands.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether no command is pending.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn position(&self, tag: &Tag) -> Option<usize> {
+        self.pending.iter().position(|command| command.tag == *tag)
+    }
+}
+
+/// Command completed through [`CommandRegistry::complete`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompletedCommand {
+    /// The original command.
+    pub command: Command<'static>,
+    /// The status of the tagged response.
+    pub status: StatusBody<'static>,
+}
+
+/// Error returned by [`CommandRegistry`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CommandRegistryError {
+    /// A command with the same tag is already pending.
+    DuplicateTag { command: Box<Command<'static>> },
+    /// No command with the tag of the tagged response is pending.
+    UnknownTag { tagged: Box<Tagged<'static>> },
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::{command::CommandBody, core::Text, response::StatusKind};
+
+    use super::*;
+
+    fn tagged(tag: &str, kind: StatusKind) -> Tagged<'static> {
+        Tagged {
+            tag: Tag::try_from(tag).unwrap().into_static(),
+            body: StatusBody {
+                kind,
+                code: None,
+                text: Text::try_from("...").unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_command_registry() {
+        let mut registry = CommandRegistry::new();
+
+        registry
+            .register(Command::new("A1", CommandBody::Noop).unwrap())
+            .unwrap();
+        registry
+            .register(Command::new("A2", CommandBody::Capability).unwrap())
+            .unwrap();
+        assert_eq!(registry.len(), 2);
+
+        // Duplicate tag
+        assert!(matches!(
+            registry.register(Command::new("A1", CommandBody::Logout).unwrap()),
+            Err(CommandRegistryError::DuplicateTag { .. })
+        ));
+        assert_eq!(
+            registry.get(&Tag::try_from("A1").unwrap()).unwrap().body,
+            CommandBody::Noop
+        );
+
+        // Out of order completion
+        let completed = registry.complete(tagged("A2", StatusKind::No)).unwrap();
+        assert_eq!(completed.command.body, CommandBody::Capability);
+        assert_eq!(completed.status.kind, StatusKind::No);
+
+        // Unknown tag
+        assert!(matches!(
+            registry.complete(tagged("A2", StatusKind::Ok)),
+            Err(CommandRegistryError::UnknownTag { .. })
+        ));
+
+        assert!(registry.remove(&Tag::try_from("A1").unwrap()).is_some());
+        assert!(registry.is_empty());
+    }
+
+    #[cfg(feature = "tag_generator")]
+    #[test]
+    fn test_command_registry_register_body() {
+        let mut registry = CommandRegistry::new();
+
+        let a = registry.register_body(CommandBody::Noop);
+        let b = registry.register_body(CommandBody::Noop);
+        assert_ne!(a.tag, b.tag);
+
+        // Generated tags are registered as well.
+        assert!(matches!(
+            registry.register(a.clone()),
+            Err(CommandRegistryError::DuplicateTag { .. })
+        ));
+
+        let completed = registry
+            .complete(Tagged {
+                tag: b.tag.clone(),
+                ..tagged("X", StatusKind::Ok)
+            })
+            .unwrap();
+        assert_eq!(completed.command, b);
+        assert_eq!(registry.pending().collect::<Vec<_>>(), vec![&a]);
+    }
+}
@@ -0,0 +1,974 @@
+//! Sans-I/O server flow.
+//!
+//! # Example
+//!
+//! ```rust
+//! use imap_codec::{
+//!     flow::server::{ServerFlow, ServerFlowEvent, ServerFlowOptions},
+//!     imap_types::response::{Greeting, Status},
+//! };
+//!
+//! let mut server = ServerFlow::new(
+//!     ServerFlowOptions::default(),
+//!     Greeting::ok(None, "Hello").unwrap(),
+//! );
+//! assert_eq!(server.take_output(), b"* OK Hello\r\n");
+//!
+//! // Bytes received from the client ...
+//! server.enqueue_input(b"A1 NOOP\r\n");
+//!
+//! // ... result in events.
+//! while let Some(event) = server.progress().unwrap() {
+//!     match event {
+//!         ServerFlowEvent::CommandReceived { command } => {
+//!             server.enqueue_status(Status::ok(Some(command.tag), None, "done").unwrap());
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//!
+//! // Bytes to send to the client.
+//! assert_eq!(server.take_output(), b"A1 OK done\r\n");
+//! ```
+
+#[cfg(any(feature = "compress", feature = "starttls"))]
+use imap_types::response::StatusKind;
+use imap_types::{
+    auth::AuthenticateData,
+    command::{Command, CommandBody},
+    core::{LiteralMode, Tag, Text},
+    response::{Code, CommandContinuationRequest, Data, Greeting, GreetingKind, Response, Status},
+    secret::Secret,
+    state::State,
+    IntoStatic,
+};
+
+#[cfg(feature = "compress")]
+use crate::compress::{CompressionLayer, DecompressError};
+use crate::{
+    decode::Decoder,
+    encode::Encoder,
+    fragmentizer::{
+        DecodeMessageError, ExceededLimit, FragmentInfo, Fragmentizer, LiteralAnnouncement,
+    },
+    AuthenticateDataCodec, CommandCodec, GreetingCodec, IdleDoneCodec, Quirks, ResponseCodec,
+};
+
+/// Options for [`ServerFlow`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ServerFlowOptions {
+    /// Maximum size of a single command (including literals) in bytes.
+    ///
+    /// Larger commands are discarded and reported as [`ServerFlowError::MessageTooLong`].
+    pub max_command_size: u32,
+    /// Maximum size of a single literal in bytes.
+    ///
+    /// Larger literals are rejected with `NO [TOOBIG]` and reported as
+    /// [`ServerFlowEvent::LiteralRejected`].
+    pub max_literal_size: u32,
+    /// Maximum length of a single line (excluding literals) in bytes.
+    ///
+    /// Commands with longer lines are discarded and reported as
+    /// [`ServerFlowError::LimitExceeded`] (see [`Fragmentizer::with_max_line_length`]). A
+    /// synchronizing literal announced by such a command is rejected with `NO [TOOBIG]`
+    /// instead and reported as [`ServerFlowEvent::LiteralRejected`].
+    pub max_line_length: Option<u32>,
+    /// Maximum number of literals in a single command.
+    ///
+    /// Commands with more literals are discarded and reported as
+    /// [`ServerFlowError::LimitExceeded`] (see [`Fragmentizer::with_max_literal_count`]). A
+    /// synchronizing literal exceeding the count is rejected with `NO [TOOBIG]` instead and
+    /// reported as [`ServerFlowEvent::LiteralRejected`].
+    pub max_literal_count: Option<u32>,
+    /// Maximum size of a single non-synchronizing literal in bytes, e.g., `Some(4096)` for
+    /// `LITERAL-`.
+    ///
+    /// Commands with larger non-synchronizing literals are discarded and reported as
+    /// [`ServerFlowError::LimitExceeded`] (see [`Fragmentizer::with_max_non_sync_literal_size`]).
+    pub max_non_sync_literal_size: Option<u32>,
+    /// Text of the command continuation request that accepts a synchronizing literal.
+    pub literal_accept_text: Text<'static>,
+    /// Text of the `NO [TOOBIG]` response that rejects a literal.
+    pub literal_reject_text: Text<'static>,
+    /// Quirks used for decoding (see [`Quirks`]).
+    pub quirks: Quirks,
+}
+
+impl Default for ServerFlowOptions {
+    fn default() -> Self {
+        Self {
+            // 25 MiB
+            max_command_size: 25 * 1024 * 1024,
+            // 25 MiB
+            max_literal_size: 25 * 1024 * 1024,
+            max_line_length: None,
+            max_literal_count: None,
+            max_non_sync_literal_size: None,
+            literal_accept_text: Text::unvalidated("..."),
+            literal_reject_text: Text::unvalidated("Literal too big"),
+            quirks: Quirks::default(),
+        }
+    }
+}
+
+/// Sans-I/O IMAP server.
+///
+/// The server ...
+///
+/// * sends the greeting,
+/// * accepts synchronizing literals with a command continuation request (or rejects them when
+///   they exceed [`ServerFlowOptions::max_literal_size`]),
+/// * decodes [`AuthenticateData`] after [`ServerFlow::authenticate_continue`],
+/// * decodes `DONE` after [`ServerFlow::idle_accept`], and
+/// * tracks the connection [`State`].
+///
+/// With the `compress` feature, a successful `COMPRESS DEFLATE` (i.e., a tagged `OK` passed to
+/// [`ServerFlow::enqueue_status`]) activates compression for all following input and output.
+#[cfg_attr(
+    feature = "starttls",
+    doc = "",
+    doc = " With the `starttls` feature, no further commands are processed after `STARTTLS` until",
+    doc = " it is completed. After the tagged `OK`, all bytes received in plaintext are discarded",
+    doc = " (see [`ServerFlowError::StartTlsInjection`]). Upgrade the connection right after taking",
+    doc = " the `OK` via [`ServerFlow::take_output`]."
+)]
+///
+/// Commands are handed out through [`ServerFlowEvent::CommandReceived`]. Every command must be
+/// completed through [`ServerFlow::enqueue_status`] with a tagged status. `AUTHENTICATE` and
+/// `IDLE` additionally allow [`ServerFlow::authenticate_continue`] and [`ServerFlow::idle_accept`]
+/// respectively.
+#[derive(Debug)]
+pub struct ServerFlow {
+    options: ServerFlowOptions,
+    fragmentizer: Fragmentizer,
+    command_codec: CommandCodec,
+    authenticate_data_codec: AuthenticateDataCodec,
+    response_codec: ResponseCodec,
+    idle_done_codec: IdleDoneCodec,
+    state: State<'static>,
+    output: Vec<u8>,
+    mode: Mode,
+    /// Commands that were received but not completed yet.
+    in_progress: Vec<Command<'static>>,
+    /// Length of a non-synchronizing literal that exceeded the maximum literal size.
+    ///
+    /// The current message is poisoned and answered when complete.
+    literal_too_big: Option<u32>,
+    #[cfg(feature = "compress")]
+    compression: Option<CompressionLayer>,
+    /// Bytes received in plaintext after `STARTTLS` (reported by the next progress).
+    #[cfg(feature = "starttls")]
+    starttls_discarded: Option<Secret<Vec<u8>>>,
+}
+
+#[derive(Debug)]
+enum Mode {
+    /// Expect commands.
+    Command,
+    /// `AUTHENTICATE` was received.
+    Authenticate {
+        tag: Tag<'static>,
+        /// Whether a command continuation request was sent, i.e., authenticate data is expected.
+        continued: bool,
+    },
+    /// `IDLE` was received.
+    Idle {
+        tag: Tag<'static>,
+        /// Whether the `IDLE` was accepted, i.e., `DONE` is expected.
+        accepted: bool,
+    },
+    /// `STARTTLS` was received.
+    #[cfg(feature = "starttls")]
+    StartTls { tag: Tag<'static> },
+}
+
+impl ServerFlow {
+    /// Creates a server and encodes the greeting into the output.
+    pub fn new(options: ServerFlowOptions, greeting: Greeting<'_>) -> Self {
+        let state = match greeting.kind {
+            GreetingKind::Ok => State::NotAuthenticated,
+            GreetingKind::PreAuth => State::Authenticated,
+            GreetingKind::Bye => State::Logout,
+        };
+
+        Self {
+            fragmentizer: Self::fragmentizer(&options),
+            command_codec: CommandCodec::new().with_quirks(options.quirks),
+            authenticate_data_codec: AuthenticateDataCodec::new().with_quirks(options.quirks),
+            response_codec: ResponseCodec::new().with_quirks(options.quirks),
+            idle_done_codec: IdleDoneCodec::new().with_quirks(options.quirks),
+            state,
+            output: GreetingCodec::new()
+                .with_quirks(options.quirks)
+                .encode(&greeting)
+                .dump(),
+            options,
+            mode: Mode::Command,
+            in_progress: Vec::new(),
+            literal_too_big: None,
+            #[cfg(feature = "compress")]
+            compression: None,
+            #[cfg(feature = "starttls")]
+            starttls_discarded: None,
+        }
+    }
+
+    fn fragmentizer(options: &ServerFlowOptions) -> Fragmentizer {
+        let mut fragmentizer = Fragmentizer::new(options.max_command_size);
+
+        if let Some(max_line_length) = options.max_line_length {
+            fragmentizer = fragmentizer.with_max_line_length(max_line_length);
+        }
+        if let Some(max_literal_count) = options.max_literal_count {
+            fragmentizer = fragmentizer.with_max_literal_count(max_literal_count);
+        }
+        if let Some(max_non_sync_literal_size) = options.max_non_sync_literal_size {
+            fragmentizer = fragmentizer.with_max_non_sync_literal_size(max_non_sync_literal_size);
+        }
+
+        fragmentizer
+    }
+
+    /// Returns the current connection state.
+    pub fn state(&self) -> &State<'static> {
+        &self.state
+    }
+
+    /// Enqueues bytes received from the client.
+    pub fn enqueue_input(&mut self, bytes: &[u8]) {
+        #[cfg(feature = "compress")]
+        if let Some(compression) = &mut self.compression {
+            compression.enqueue_input(bytes);
+            return;
+        }
+
+        self.fragmentizer.enqueue_bytes(bytes);
+    }
+
+    /// Takes the bytes that should be sent to the client.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        let output = std::mem::take(&mut self.output);
+
+        #[cfg(feature = "compress")]
+        if let Some(compression) = &mut self.compression {
+            return compression.compress(output);
+        }
+
+        output
+    }
+
+    /// Encodes untagged data into the output.
+    pub fn enqueue_data(&mut self, data: Data<'_>) {
+        self.enqueue_response(&Response::Data(data));
+    }
+
+    /// Encodes a status into the output.
+    ///
+    /// A tagged status completes the command with the same tag (including `AUTHENTICATE` and
+    /// `IDLE`) and may change the [`State`].
+    pub fn enqueue_status(&mut self, status: Status<'_>) {
+        #[cfg(feature = "compress")]
+        let mut activate_compression = false;
+        #[cfg(feature = "starttls")]
+        let mut discard_plaintext = false;
+
+        match &status {
+            Status::Tagged(tagged) => {
+                if let Some(index) = self
+                    .in_progress
+                    .iter()
+                    .position(|command| command.tag == tagged.tag)
+                {
+                    let command = self.in_progress.remove(index);
+
+                    self.state = self.state.next(&command.body, tagged.body.kind);
+
+                    #[cfg(feature = "compress")]
+                    if let (CommandBody::Compress { .. }, StatusKind::Ok) =
+                        (&command.body, tagged.body.kind)
+                    {
+                        activate_compression = true;
+                    }
+
+                    #[cfg(feature = "starttls")]
+                    if let (CommandBody::StartTLS, StatusKind::Ok) =
+                        (&command.body, tagged.body.kind)
+                    {
+                        discard_plaintext = true;
+                    }
+                }
+
+                if matches!(
+                    &self.mode,
+                    Mode::Authenticate { tag, .. } | Mode::Idle { tag, .. } if *tag == tagged.tag
+                ) {
+                    self.mode = Mode::Command;
+                }
+
+                #[cfg(feature = "starttls")]
+                if matches!(&self.mode, Mode::StartTls { tag } if *tag == tagged.tag) {
+                    self.mode = Mode::Command;
+                }
+            }
+            Status::Bye(_) => self.state = State::Logout,
+            Status::Untagged(_) => {}
+        }
+
+        self.enqueue_response(&Response::Status(status));
+
+        // Everything received in plaintext after the tagged OK must not be processed.
+        #[cfg(feature = "starttls")]
+        if discard_plaintext {
+            let discarded = self.fragmentizer.discard_unparsed_bytes();
+
+            if !discarded.declassify().is_empty() {
+                self.starttls_discarded = Some(discarded);
+            }
+        }
+
+        // Everything after the tagged OK is compressed.
+        #[cfg(feature = "compress")]
+        if activate_compression {
+            self.compression = Some(CompressionLayer::activate(
+                &mut self.fragmentizer,
+                &self.output,
+            ));
+        }
+    }
+
+    /// Requests (more) authenticate data during `AUTHENTICATE`.
+    ///
+    /// The answer is reported through [`ServerFlowEvent::AuthenticateDataReceived`].
+    pub fn authenticate_continue(
+        &mut self,
+        continuation_request: CommandContinuationRequest<'_>,
+    ) -> Result<(), ServerFlowError> {
+        match &mut self.mode {
+            Mode::Authenticate { continued, .. } if !*continued => {
+                *continued = true;
+            }
+            _ => return Err(ServerFlowError::AuthenticateNotInProgress),
+        }
+
+        self.enqueue_response(&Response::CommandContinuationRequest(continuation_request));
+
+        Ok(())
+    }
+
+    /// Accepts an `IDLE`.
+    ///
+    /// The end of the `IDLE` is reported through [`ServerFlowEvent::IdleDoneReceived`].
+    pub fn idle_accept(
+        &mut self,
+        continuation_request: CommandContinuationRequest<'_>,
+    ) -> Result<(), ServerFlowError> {
+        match &mut self.mode {
+            Mode::Idle { tag, accepted } if !*accepted => {
+                *accepted = true;
+                self.state = match std::mem::replace(&mut self.state, State::Greeting) {
+                    State::Selected(mailbox) => State::IdleSelected(tag.clone(), mailbox),
+                    _ => State::IdleAuthenticated(tag.clone()),
+                };
+            }
+            _ => return Err(ServerFlowError::IdleNotInProgress),
+        }
+
+        self.enqueue_response(&Response::CommandContinuationRequest(continuation_request));
+
+        Ok(())
+    }
+
+    /// Progresses the flow until the next event.
+    ///
+    /// Returns `Ok(None)` when more bytes need to be enqueued via [`ServerFlow::enqueue_input`].
+    /// Errors are not fatal, i.e., the flow can be progressed further.
+    ///
+    /// Note: Check [`ServerFlow::take_output`] after each call.
+    pub fn progress(&mut self) -> Result<Option<ServerFlowEvent>, ServerFlowError> {
+        #[cfg(feature = "starttls")]
+        if let Some(discarded) = self.starttls_discarded.take() {
+            return Err(ServerFlowError::StartTlsInjection { discarded });
+        }
+
+        loop {
+            // Bytes after `STARTTLS` are not processed before the upgrade.
+            #[cfg(feature = "starttls")]
+            if let Mode::StartTls { .. } = self.mode {
+                return Ok(None);
+            }
+
+            let Some(fragment_info) = self.fragmentizer.progress() else {
+                // Decompress more bytes only when needed (to bound the memory).
+                #[cfg(feature = "compress")]
+                if let Some(compression) = &mut self.compression {
+                    if compression
+                        .decompress_into(&mut self.fragmentizer)
+                        .map_err(|DecompressError| ServerFlowError::DecompressionFailed)?
+                    {
+                        continue;
+                    }
+                }
+
+                return Ok(None);
+            };
+
+            if let (
+                Mode::Command,
+                FragmentInfo::Line {
+                    announcement: Some(LiteralAnnouncement { mode, length }),
+                    ..
+                },
+            ) = (&self.mode, fragment_info)
+            {
+                if let Some(event) = self.handle_literal_announcement(mode, length) {
+                    return Ok(Some(event));
+                }
+
+                continue;
+            }
+
+            if !self.fragmentizer.is_message_complete() {
+                continue;
+            }
+
+            if let Some(length) = self.literal_too_big.take() {
+                if let Some(tag) = self.fragmentizer.decode_tag().map(IntoStatic::into_static) {
+                    self.reject_literal(tag.clone());
+                    return Ok(Some(ServerFlowEvent::LiteralRejected { tag, length }));
+                }
+            }
+
+            match &self.mode {
+                Mode::Command => return self.handle_command().map(Some),
+                Mode::Authenticate {
+                    tag,
+                    continued: true,
+                } => {
+                    let tag = tag.clone();
+                    return self.handle_authenticate_data(tag).map(Some);
+                }
+                Mode::Idle {
+                    tag,
+                    accepted: true,
+                } => {
+                    let tag = tag.clone();
+                    return self.handle_idle_done(tag).map(Some);
+                }
+                Mode::Authenticate { .. } | Mode::Idle { .. } => {
+                    return Err(ServerFlowError::UnexpectedMessage {
+                        discarded: Secret::new(self.fragmentizer.message_bytes().to_vec()),
+                    });
+                }
+                #[cfg(feature = "starttls")]
+                Mode::StartTls { .. } => unreachable!(),
+            }
+        }
+    }
+
+    fn handle_literal_announcement(
+        &mut self,
+        mode: LiteralMode,
+        length: u32,
+    ) -> Option<ServerFlowEvent> {
+        let limit_exceeded = self.fragmentizer.exceeded_limit().is_some();
+
+        if length <= self.options.max_literal_size && !limit_exceeded {
+            if mode == LiteralMode::Sync {
+                let continuation_request = CommandContinuationRequest::basic(
+                    None,
+                    self.options.literal_accept_text.clone(),
+                )
+                .unwrap();
+                self.enqueue_response(&Response::CommandContinuationRequest(continuation_request));
+            }
+
+            return None;
+        }
+
+        match (mode, self.fragmentizer.decode_tag()) {
+            (LiteralMode::Sync, Some(tag)) => {
+                // The client will not send the literal. Thus, we can skip the message safely.
+                let tag = tag.into_static();
+                self.fragmentizer.skip_message();
+                self.reject_literal(tag.clone());
+
+                Some(ServerFlowEvent::LiteralRejected { tag, length })
+            }
+            _ => {
+                // The literal will be sent anyway (or it's unclear what will follow).
+                // To be on the safe side, we continue fragmenting but prevent decoding.
+                self.fragmentizer.poison_message();
+                if !limit_exceeded {
+                    self.literal_too_big = Some(length);
+                }
+
+                None
+            }
+        }
+    }
+
+    fn reject_literal(&mut self, tag: Tag<'static>) {
+        let status = Status::no(
+            Some(tag),
+            Some(Code::TooBig),
+            self.options.literal_reject_text.clone(),
+        )
+        .unwrap();
+        self.enqueue_response(&Response::Status(status));
+    }
+
+    fn handle_command(&mut self) -> Result<ServerFlowEvent, ServerFlowError> {
+        let command = match self.fragmentizer.decode_message(&self.command_codec) {
+            Ok(command) => command.into_static(),
+            Err(err) => return Err(self.decode_error(err)),
+        };
+
+        match command.body {
+            CommandBody::Authenticate { .. } => {
+                self.mode = Mode::Authenticate {
+                    tag: command.tag.clone(),
+                    continued: false,
+                };
+            }
+            CommandBody::Idle => {
+                self.mode = Mode::Idle {
+                    tag: command.tag.clone(),
+                    accepted: false,
+                };
+            }
+            #[cfg(feature = "starttls")]
+            CommandBody::StartTLS => {
+                self.mode = Mode::StartTls {
+                    tag: command.tag.clone(),
+                };
+            }
+            _ => {}
+        }
+
+        self.in_progress.push(command.clone());
+
+        Ok(ServerFlowEvent::CommandReceived { command })
+    }
+
+    fn handle_authenticate_data(
+        &mut self,
+        tag: Tag<'static>,
+    ) -> Result<ServerFlowEvent, ServerFlowError> {
+        let authenticate_data = match self
+            .fragmentizer
+            .decode_message(&self.authenticate_data_codec)
+        {
+            Ok(authenticate_data) => authenticate_data.into_static(),
+            Err(err) => return Err(self.decode_error(err)),
+        };
+
+        if let Mode::Authenticate { continued, .. } = &mut self.mode {
+            *continued = false;
+        }
+
+        Ok(ServerFlowEvent::AuthenticateDataReceived {
+            tag,
+            authenticate_data,
+        })
+    }
+
+    fn handle_idle_done(&mut self, tag: Tag<'static>) -> Result<ServerFlowEvent, ServerFlowError> {
+        if let Err(err) = self.fragmentizer.decode_message(&self.idle_done_codec) {
+            return Err(self.decode_error(err));
+        }
+
+        if let Mode::Idle { accepted, .. } = &mut self.mode {
+            *accepted = false;
+        }
+
+        Ok(ServerFlowEvent::IdleDoneReceived { tag })
+    }
+
+    fn decode_error<C>(&self, err: DecodeMessageError<C>) -> ServerFlowError
+    where
+        C: Decoder,
+    {
+        let tag = self.fragmentizer.decode_tag().map(IntoStatic::into_static);
+        let discarded = Secret::new(self.fragmentizer.message_bytes().to_vec());
+
+        match err {
+            DecodeMessageError::MessageTooLong { .. } => {
+                ServerFlowError::MessageTooLong { tag, discarded }
+            }
+            DecodeMessageError::LimitExceeded { limit, .. } => ServerFlowError::LimitExceeded {
+                tag,
+                limit,
+                discarded,
+            },
+            DecodeMessageError::DecodingFailure(_)
+            | DecodeMessageError::DecodingRemainder { .. }
+            | DecodeMessageError::MessagePoisoned { .. } => {
+                ServerFlowError::MalformedMessage { tag, discarded }
+            }
+        }
+    }
+
+    fn enqueue_response(&mut self, response: &Response) {
+        // Note: Writing into a `Vec` can't fail.
+        self.response_codec
+            .dump_into(response, &mut self.output)
+            .unwrap();
+    }
+}
+
+/// Event emitted by [`ServerFlow::progress`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerFlowEvent {
+    /// A command was received.
+    ///
+    /// Complete it with [`ServerFlow::enqueue_status`].
+    CommandReceived { command: Command<'static> },
+    /// Authenticate data was received.
+    ///
+    /// Answer it with [`ServerFlow::authenticate_continue`] or [`ServerFlow::enqueue_status`].
+    AuthenticateDataReceived {
+        tag: Tag<'static>,
+        authenticate_data: AuthenticateData<'static>,
+    },
+    /// `DONE` was received.
+    ///
+    /// Complete the `IDLE` with [`ServerFlow::enqueue_status`].
+    IdleDoneReceived { tag: Tag<'static> },
+    /// A literal exceeded [`ServerFlowOptions::max_literal_size`] (or a synchronizing literal was
+    /// announced after another limit was exceeded).
+    ///
+    /// The command was already answered with `NO [TOOBIG]`.
+    LiteralRejected { tag: Tag<'static>, length: u32 },
+}
+
+/// Error returned by [`ServerFlow`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerFlowError {
+    /// A received message was malformed and discarded.
+    ///
+    /// The tag is recovered on a best effort basis and can be used to answer with `BAD`.
+    MalformedMessage {
+        tag: Option<Tag<'static>>,
+        discarded: Secret<Vec<u8>>,
+    },
+    /// A received message exceeded [`ServerFlowOptions::max_command_size`] and was discarded.
+    MessageTooLong {
+        tag: Option<Tag<'static>>,
+        discarded: Secret<Vec<u8>>,
+    },
+    /// A received message exceeded a limit configured in [`ServerFlowOptions`] (e.g.
+    /// [`ServerFlowOptions::max_line_length`]) and was discarded.
+    LimitExceeded {
+        tag: Option<Tag<'static>>,
+        limit: ExceededLimit,
+        discarded: Secret<Vec<u8>>,
+    },
+    /// A message was received during `AUTHENTICATE` or `IDLE` although none was expected.
+    UnexpectedMessage { discarded: Secret<Vec<u8>> },
+    /// [`ServerFlow::authenticate_continue`] was called outside of `AUTHENTICATE` (or twice).
+    AuthenticateNotInProgress,
+    /// [`ServerFlow::idle_accept`] was called outside of `IDLE` (or twice).
+    IdleNotInProgress,
+    /// Received bytes couldn't be decompressed after `COMPRESS`.
+    ///
+    /// Note: The connection is unusable and should be closed.
+    #[cfg(feature = "compress")]
+    DecompressionFailed,
+    /// The client sent bytes in plaintext after `STARTTLS`.
+    ///
+    /// The bytes were discarded. This indicates an injection attack (see CVE-2011-0411).
+    #[cfg(feature = "starttls")]
+    StartTlsInjection { discarded: Secret<Vec<u8>> },
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_types::mailbox::Mailbox;
+
+    use super::*;
+
+    fn receive(server: &mut ServerFlow, bytes: &[u8]) -> Vec<ServerFlowEvent> {
+        server.enqueue_input(bytes);
+
+        let mut events = Vec::new();
+        while let Some(event) = server.progress().unwrap() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn server() -> ServerFlow {
+        let mut server = ServerFlow::new(
+            ServerFlowOptions::default(),
+            Greeting::ok(None, "...").unwrap(),
+        );
+        assert_eq!(server.take_output(), b"* OK ...\r\n");
+        server
+    }
+
+    fn ok(tag: &str) -> Status<'static> {
+        Status::ok(Some(Tag::try_from(tag).unwrap().into_static()), None, "...").unwrap()
+    }
+
+    #[test]
+    fn test_server_flow_literal() {
+        let mut server = server();
+
+        assert!(receive(&mut server, b"A1 LOGIN alice {4}\r\n").is_empty());
+        assert_eq!(server.take_output(), b"+ ...\r\n");
+
+        let events = receive(&mut server, b"pass\r\nA2 LOGIN alice {4+}\r\npass\r\n");
+        assert!(server.take_output().is_empty());
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            ServerFlowEvent::CommandReceived { command } if command.tag.as_ref() == "A1"
+        ));
+
+        server.enqueue_status(ok("A1"));
+        assert_eq!(server.state(), &State::Authenticated);
+    }
+
+    #[test]
+    fn test_server_flow_literal_too_big() {
+        let mut server = ServerFlow::new(
+            ServerFlowOptions {
+                max_literal_size: 3,
+                ..Default::default()
+            },
+            Greeting::ok(None, "...").unwrap(),
+        );
+        let _ = server.take_output();
+
+        let events = receive(&mut server, b"A1 LOGIN alice {4}\r\nA2 NOOP\r\n");
+        assert_eq!(
+            events[0],
+            ServerFlowEvent::LiteralRejected {
+                tag: Tag::try_from("A1").unwrap(),
+                length: 4,
+            }
+        );
+        assert!(matches!(
+            &events[1],
+            ServerFlowEvent::CommandReceived { command } if command.tag.as_ref() == "A2"
+        ));
+        assert_eq!(server.take_output(), b"A1 NO [TOOBIG] Literal too big\r\n");
+
+        let events = receive(&mut server, b"A3 LOGIN alice {4+}\r\npass\r\n");
+        assert_eq!(
+            events[0],
+            ServerFlowEvent::LiteralRejected {
+                tag: Tag::try_from("A3").unwrap(),
+                length: 4,
+            }
+        );
+        assert_eq!(server.take_output(), b"A3 NO [TOOBIG] Literal too big\r\n");
+    }
+
+    #[test]
+    fn test_server_flow_quirks() {
+        let mut server = ServerFlow::new(
+            ServerFlowOptions {
+                quirks: Quirks::none(),
+                ..Default::default()
+            },
+            Greeting::ok(None, "...").unwrap(),
+        );
+        server.enqueue_input(b"A1 NOOP\n");
+        assert!(matches!(
+            server.progress(),
+            Err(ServerFlowError::MalformedMessage { .. })
+        ));
+
+        let mut server = ServerFlow::new(
+            ServerFlowOptions {
+                quirks: Quirks::all(),
+                ..Default::default()
+            },
+            Greeting::ok(None, "...").unwrap(),
+        );
+        let events = receive(&mut server, b"A1 NOOP\n");
+        assert!(matches!(
+            &events[0],
+            ServerFlowEvent::CommandReceived { command } if command.tag.as_ref() == "A1"
+        ));
+    }
+
+    #[test]
+    fn test_server_flow_limit_exceeded() {
+        let mut server = ServerFlow::new(
+            ServerFlowOptions {
+                max_line_length: Some(20),
+                max_non_sync_literal_size: Some(4),
+                ..Default::default()
+            },
+            Greeting::ok(None, "...").unwrap(),
+        );
+        let _ = server.take_output();
+
+        server.enqueue_input(b"A1 LOGIN alice secret\r\nA2 LOGIN a {5+}\r\nabcde\r\nA3 NOOP\r\n");
+        assert!(matches!(
+            server.progress(),
+            Err(ServerFlowError::LimitExceeded {
+                tag: Some(tag),
+                limit: ExceededLimit::LineLength { max: 20 },
+                ..
+            }) if tag.as_ref() == "A1"
+        ));
+        assert!(matches!(
+            server.progress(),
+            Err(ServerFlowError::LimitExceeded {
+                tag: Some(tag),
+                limit: ExceededLimit::NonSyncLiteralSize { max: 4, length: 5 },
+                ..
+            }) if tag.as_ref() == "A2"
+        ));
+        assert!(matches!(
+            server.progress(),
+            Ok(Some(ServerFlowEvent::CommandReceived { command })) if command.tag.as_ref() == "A3"
+        ));
+        assert_eq!(server.take_output(), b"");
+
+        // The client waits for a continuation request, so the command can be rejected early.
+        server.enqueue_input(b"A4 LOGIN a-very-long-username {30}\r\nA5 NOOP\r\n");
+        assert_eq!(
+            server.progress(),
+            Ok(Some(ServerFlowEvent::LiteralRejected {
+                tag: Tag::try_from("A4").unwrap(),
+                length: 30,
+            }))
+        );
+        assert!(matches!(
+            server.progress(),
+            Ok(Some(ServerFlowEvent::CommandReceived { command })) if command.tag.as_ref() == "A5"
+        ));
+        assert_eq!(server.take_output(), b"A4 NO [TOOBIG] Literal too big\r\n");
+    }
+
+    #[test]
+    fn test_server_flow_authenticate() {
+        let mut server = server();
+
+        let events = receive(&mut server, b"A1 AUTHENTICATE PLAIN\r\n");
+        assert!(matches!(
+            &events[0],
+            ServerFlowEvent::CommandReceived {
+                command: Command {
+                    body: CommandBody::Authenticate { .. },
+                    ..
+                }
+            }
+        ));
+
+        server
+            .authenticate_continue(CommandContinuationRequest::base64(b"".as_ref()))
+            .unwrap();
+        assert_eq!(server.take_output(), b"+ \r\n");
+        assert_eq!(
+            server.authenticate_continue(CommandContinuationRequest::base64(b"".as_ref())),
+            Err(ServerFlowError::AuthenticateNotInProgress)
+        );
+
+        let events = receive(&mut server, b"AGFsaWNlAHBhc3M=\r\n");
+        assert_eq!(
+            events[0],
+            ServerFlowEvent::AuthenticateDataReceived {
+                tag: Tag::try_from("A1").unwrap(),
+                authenticate_data: AuthenticateData::r#continue(b"\x00alice\x00pass".to_vec()),
+            }
+        );
+
+        server.enqueue_status(ok("A1"));
+        assert_eq!(server.state(), &State::Authenticated);
+
+        let events = receive(&mut server, b"A2 NOOP\r\n");
+        assert!(matches!(
+            &events[0],
+            ServerFlowEvent::CommandReceived { command } if command.tag.as_ref() == "A2"
+        ));
+    }
+
+    #[test]
+    fn test_server_flow_idle() {
+        let mut server = ServerFlow::new(
+            ServerFlowOptions::default(),
+            Greeting::preauth(None, "...").unwrap(),
+        );
+
+        receive(&mut server, b"A1 SELECT INBOX\r\n");
+        server.enqueue_status(ok("A1"));
+        assert_eq!(server.state(), &State::Selected(Mailbox::Inbox));
+
+        receive(&mut server, b"A2 IDLE\r\n");
+        server
+            .idle_accept(CommandContinuationRequest::basic(None, "idling").unwrap())
+            .unwrap();
+        assert_eq!(
+            server.state(),
+            &State::IdleSelected(Tag::try_from("A2").unwrap(), Mailbox::Inbox)
+        );
+
+        let events = receive(&mut server, b"DONE\r\n");
+        assert_eq!(
+            events[0],
+            ServerFlowEvent::IdleDoneReceived {
+                tag: Tag::try_from("A2").unwrap()
+            }
+        );
+
+        server.enqueue_status(ok("A2"));
+        assert_eq!(server.state(), &State::Selected(Mailbox::Inbox));
+
+        let output = server.take_output();
+        assert!(output.ends_with(b"+ idling\r\nA2 OK ...\r\n"));
+    }
+
+    #[test]
+    fn test_server_flow_errors() {
+        let mut server = server();
+
+        server.enqueue_input(b"A1 xxx\r\n");
+        assert!(matches!(
+            server.progress(),
+            Err(ServerFlowError::MalformedMessage { tag: Some(tag), .. }) if tag.as_ref() == "A1"
+        ));
+
+        receive(&mut server, b"A2 SELECT INBOX\r\n");
+        server.enqueue_status(Status::no(Some(Tag::try_from("A2").unwrap()), None, "...").unwrap());
+        assert_eq!(server.state(), &State::NotAuthenticated);
+        assert_eq!(
+            server.idle_accept(CommandContinuationRequest::base64(b"".as_ref())),
+            Err(ServerFlowError::IdleNotInProgress)
+        );
+    }
+
+    #[cfg(feature = "starttls")]
+    #[test]
+    fn test_server_flow_starttls_injection() {
+        let mut server = server();
+
+        let events = receive(&mut server, b"A1 STARTTLS\r\nA2 LOGIN alice pass\r\n");
+        assert_eq!(
+            events,
+            vec![ServerFlowEvent::CommandReceived {
+                command: Command::new("A1", CommandBody::StartTLS).unwrap()
+            }]
+        );
+
+        server.enqueue_status(ok("A1"));
+        assert_eq!(server.take_output(), b"A1 OK ...\r\n");
+        assert_eq!(
+            server.progress(),
+            Err(ServerFlowError::StartTlsInjection {
+                discarded: Secret::new(b"A2 LOGIN alice pass\r\n".to_vec())
+            })
+        );
+        assert_eq!(server.progress(), Ok(None));
+        assert_eq!(server.state(), &State::NotAuthenticated);
+
+        // After the upgrade
+        let events = receive(&mut server, b"A3 NOOP\r\n");
+        assert_eq!(events.len(), 1);
+    }
+}
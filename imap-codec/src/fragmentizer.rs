@@ -77,6 +77,29 @@ pub struct Fragmentizer {
     /// Parser for the next fragment of the current message. Is `None` if no fragment is expected
     /// because the message is complete.
     parser: Option<Parser>,
+    /// Literals longer than this are passed to a [`LiteralSink`] instead of the message buffer.
+    spool_threshold: Option<u32>,
+    /// Handle of the literal that is currently spooled.
+    spooling: Option<SpoolHandle>,
+    /// Literals spooled for the current message.
+    spooled: SpooledLiterals,
+    /// Handle for the next spooled literal.
+    next_spool_handle: u64,
+    /// Limits in addition to the maximum message size.
+    limits: Limits,
+    /// Number of literals announced by the current message.
+    literal_count: u32,
+    /// The first limit exceeded by the current message.
+    exceeded_limit: Option<ExceededLimit>,
+}
+
+/// Limits configured via the `with_max_*` methods of [`Fragmentizer`].
+#[derive(Clone, Copy, Debug, Default)]
+struct Limits {
+    line_length: Option<u32>,
+    literal_size: Option<u32>,
+    literal_count: Option<u32>,
+    non_sync_literal_size: Option<u32>,
 }
 
 impl Fragmentizer {
@@ -91,6 +114,13 @@ impl Fragmentizer {
             message_poisoned: false,
             message_buffer: Vec::new(),
             parser: Some(Parser::Line(LineParser::new(0))),
+            spool_threshold: None,
+            spooling: None,
+            spooled: SpooledLiterals::default(),
+            next_spool_handle: 0,
+            limits: Limits::default(),
+            literal_count: 0,
+            exceeded_limit: None,
         }
     }
 
@@ -108,16 +138,81 @@ impl Fragmentizer {
             message_poisoned: false,
             message_buffer: Vec::new(),
             parser: Some(Parser::Line(LineParser::new(0))),
+            spool_threshold: None,
+            spooling: None,
+            spooled: SpooledLiterals::default(),
+            next_spool_handle: 0,
+            limits: Limits::default(),
+            literal_count: 0,
+            exceeded_limit: None,
         }
     }
 
+    /// Enables spooling of literals that are longer than `threshold` bytes.
+    ///
+    /// The bytes of these literals are not added to the message buffer. Instead, they are passed
+    /// to the [`LiteralSink`] given to [`Fragmentizer::progress_with_sink`], e.g., a file or a
+    /// hasher. The message buffer contains a short placeholder in place of the literal (and the
+    /// literal announcement is adjusted accordingly), so the message can still be decoded.
+    /// The placeholder of a decoded literal can be resolved via [`Fragmentizer::spooled_literals`].
+    ///
+    /// To keep placeholders unambiguous, a message is poisoned when the peer sends the
+    /// placeholder prefix `SPOOLED-LITERAL-` itself (see [`Fragmentizer::poison_message`]).
+    ///
+    /// Spooled literals don't count towards the maximum message size.
+    pub fn with_literal_spooling(mut self, threshold: u32) -> Self {
+        self.spool_threshold = Some(threshold);
+        self
+    }
+
+    /// Limits the length of each line (including the line ending, but excluding literals).
+    ///
+    /// "[...] a client should limit the length of the command lines it generates to approximately
+    /// 8192 octets (including all quoted strings but not including literals)."
+    /// ([RFC 7162, 4](https://www.rfc-editor.org/rfc/rfc7162#section-4))
+    pub fn with_max_line_length(mut self, max_line_length: u32) -> Self {
+        self.limits.line_length = Some(max_line_length);
+        self
+    }
+
+    /// Limits the announced size of each literal.
+    pub fn with_max_literal_size(mut self, max_literal_size: u32) -> Self {
+        self.limits.literal_size = Some(max_literal_size);
+        self
+    }
+
+    /// Limits the number of literals per message.
+    pub fn with_max_literal_count(mut self, max_literal_count: u32) -> Self {
+        self.limits.literal_count = Some(max_literal_count);
+        self
+    }
+
+    /// Limits the announced size of each non-synchronizing literal.
+    ///
+    /// "LITERAL- [...] only allows non-synchronizing literals of 4096 bytes or less."
+    /// ([RFC 7888, 5](https://www.rfc-editor.org/rfc/rfc7888#section-5))
+    pub fn with_max_non_sync_literal_size(mut self, max_non_sync_literal_size: u32) -> Self {
+        self.limits.non_sync_literal_size = Some(max_non_sync_literal_size);
+        self
+    }
+
     /// Continue parsing the current message until the next fragment is detected.
     ///
     /// Returns `None` if more bytes need to be enqueued via [`Fragmentizer::enqueue_bytes`].
     /// If [`Fragmentizer::is_message_complete`] returns true after this function was called,
     /// then the message was fully parsed. The following call of this function will then start
     /// the next message.
+    ///
+    /// Note: If literal spooling is enabled, the bytes of spooled literals are discarded. Use
+    /// [`Fragmentizer::progress_with_sink`] instead.
     pub fn progress(&mut self) -> Option<FragmentInfo> {
+        self.progress_with_sink(&mut ())
+    }
+
+    /// Like [`Fragmentizer::progress`], but passes the bytes of spooled literals to `sink`.
+    ///
+    /// See [`Fragmentizer::with_literal_spooling`].
+    pub fn progress_with_sink(&mut self, sink: &mut dyn LiteralSink) -> Option<FragmentInfo> {
         let parser = match &mut self.parser {
             Some(parser) => {
                 // Continue current message
@@ -128,16 +223,83 @@ impl Fragmentizer {
                 self.max_message_size_exceeded = false;
                 self.message_poisoned = false;
                 self.message_buffer.clear();
+                self.spooled.handles.clear();
+                self.literal_count = 0;
+                self.exceeded_limit = None;
                 self.parser.insert(Parser::Line(LineParser::new(0)))
             }
         };
 
         // Progress fragment
-        let (parsed_byte_count, fragment) = match parser {
+        let (parsed_byte_count, mut fragment) = match parser {
             Parser::Line(parser) => parser.parse(&self.unparsed_buffer),
             Parser::Literal(parser) => parser.parse(&self.unparsed_buffer),
         };
-        self.dequeue_parsed_bytes(parsed_byte_count);
+        let line = match parser {
+            Parser::Line(parser) => Some((parser.start, parser.end - parser.start)),
+            Parser::Literal(_) => None,
+        };
+
+        let spooled = self.spooling.is_some();
+
+        match self.spooling {
+            Some(handle) => {
+                self.spool_parsed_bytes(parsed_byte_count, handle, sink);
+
+                if let Some(FragmentInfo::Literal { start, .. }) = fragment {
+                    // The message buffer only contains the placeholder
+                    sink.finish(handle);
+                    self.spooling = None;
+                    fragment = Some(FragmentInfo::Literal {
+                        start,
+                        end: start + SPOOL_PLACEHOLDER_LEN,
+                    });
+                }
+            }
+            None => self.dequeue_parsed_bytes(parsed_byte_count),
+        }
+
+        if let Some((line_start, line_length)) = line {
+            self.check_line_length(line_start, line_length);
+        }
+        if let (Some(fragment), false) = (fragment, spooled) {
+            self.check_spool_placeholder(fragment);
+        }
+        if let Some(FragmentInfo::Line {
+            announcement: Some(announcement),
+            ..
+        }) = fragment
+        {
+            self.check_literal(announcement);
+        }
+
+        if let Some(FragmentInfo::Line {
+            start,
+            announcement: Some(announcement),
+            ending,
+            ..
+        }) = fragment
+        {
+            if self.should_spool(announcement) {
+                let handle = self.start_spooling();
+                fragment = Some(FragmentInfo::Line {
+                    start,
+                    end: self.message_buffer.len(),
+                    announcement: Some(announcement),
+                    ending,
+                });
+                self.message_buffer
+                    .extend_from_slice(&spool_placeholder(handle));
+
+                // The placeholder is subject to the size limit
+                if let Some(max_message_size) = self.max_message_size {
+                    if self.message_buffer.len() > max_message_size as usize {
+                        self.message_buffer.truncate(max_message_size as usize);
+                        self.max_message_size_exceeded = true;
+                    }
+                }
+            }
+        }
 
         if let Some(fragment) = fragment {
             self.parser = match fragment {
@@ -168,6 +330,37 @@ impl Fragmentizer {
         self.unparsed_buffer.extend(bytes);
     }
 
+    /// Removes and returns the enqueued bytes that are not parsed yet.
+    ///
+    /// This is required when the transport changes between two messages, e.g., after `COMPRESS`.
+    #[cfg(feature = "compress")]
+    pub(crate) fn take_unparsed_bytes(&mut self) -> Vec<u8> {
+        self.unparsed_buffer.drain(..).collect()
+    }
+
+    /// Removes and returns all received bytes that were not processed yet.
+    ///
+    /// Call this at the point where the connection is upgraded via `STARTTLS`, i.e., right after
+    /// the tagged `OK` was sent (server) or received (client). Bytes that were pipelined after
+    /// `STARTTLS` in plaintext must not be processed after the upgrade. Otherwise, an attacker
+    /// could inject commands or responses (see CVE-2011-0411). This includes the bytes of an
+    /// incomplete message.
+    ///
+    /// A non-empty result indicates an attack (or a broken peer).
+    #[cfg(feature = "starttls")]
+    pub fn discard_unparsed_bytes(&mut self) -> Secret<Vec<u8>> {
+        let mut discarded = Vec::new();
+
+        if !self.is_message_complete() {
+            discarded.extend_from_slice(&self.message_buffer);
+            self.skip_message();
+        }
+
+        discarded.extend(self.unparsed_buffer.drain(..));
+
+        Secret::new(discarded)
+    }
+
     /// Returns the bytes for a fragment of the current message.
     pub fn fragment_bytes(&self, fragment_info: FragmentInfo) -> &[u8] {
         let (start, end) = match fragment_info {
@@ -205,6 +398,21 @@ impl Fragmentizer {
         self.max_message_size_exceeded
     }
 
+    /// Returns the first limit exceeded by the current message (if any).
+    ///
+    /// The limits are configured via [`Fragmentizer::with_max_line_length`],
+    /// [`Fragmentizer::with_max_literal_size`], [`Fragmentizer::with_max_literal_count`], and
+    /// [`Fragmentizer::with_max_non_sync_literal_size`]. Like with the maximum message size,
+    /// fragmentation continues normally, but the following bytes of the message are dropped and
+    /// [`Fragmentizer::decode_message`] will fail.
+    ///
+    /// Note: Literal limits are checked when the literal is announced, i.e., right after the
+    /// line fragment was returned by [`Fragmentizer::progress`]. This allows a server to reject a
+    /// synchronizing literal before the client sends it.
+    pub fn exceeded_limit(&self) -> Option<ExceededLimit> {
+        self.exceeded_limit
+    }
+
     /// Returns whether the current message was explicitly poisoned to prevent decoding.
     pub fn is_message_poisoned(&self) -> bool {
         self.message_poisoned
@@ -222,6 +430,10 @@ impl Fragmentizer {
         self.message_poisoned = false;
         self.message_buffer.clear();
         self.parser = Some(Parser::Line(LineParser::new(0)));
+        self.spooling = None;
+        self.spooled.handles.clear();
+        self.literal_count = 0;
+        self.exceeded_limit = None;
     }
 
     /// Poisons the current message to prevent its decoding.
@@ -235,6 +447,15 @@ impl Fragmentizer {
         self.message_poisoned = true;
     }
 
+    /// Returns the literals spooled for the current message.
+    ///
+    /// Use them to resolve the placeholders in the message returned by
+    /// [`Fragmentizer::decode_message`]. Clone them to keep them together with the message, e.g.,
+    /// after [`IntoStatic::into_static`](imap_types::IntoStatic::into_static).
+    pub fn spooled_literals(&self) -> &SpooledLiterals {
+        &self.spooled
+    }
+
     /// Tries to decode the [`Tag`] for the current message.
     ///
     /// Note that decoding the [`Tag`] is on best effort basis. Some message types don't have
@@ -250,6 +471,9 @@ impl Fragmentizer {
     /// You usually want to call this method once [`Fragmentizer::is_message_complete`] returns
     /// true. Which decoder should be used depends on the state of the IMAP conversation. The
     /// caller is responsible for tracking this state and choosing the decoder.
+    ///
+    /// Note: If literal spooling is enabled, spooled literals are decoded as placeholders. These
+    /// are resolved to their [`SpoolHandle`]s via [`Fragmentizer::spooled_literals`].
     pub fn decode_message<'a, C: Decoder>(
         &'a self,
         codec: &C,
@@ -260,6 +484,13 @@ impl Fragmentizer {
             });
         }
 
+        if let Some(limit) = self.exceeded_limit {
+            return Err(DecodeMessageError::LimitExceeded {
+                limit,
+                initial: Secret::new(&self.message_buffer),
+            });
+        }
+
         if self.message_poisoned {
             return Err(DecodeMessageError::MessagePoisoned {
                 discarded: Secret::new(&self.message_buffer),
@@ -284,6 +515,11 @@ impl Fragmentizer {
     fn dequeue_parsed_bytes(&mut self, parsed_byte_count: usize) {
         // This will remove the parsed bytes even if we don't add them to the message buffer
         let parsed_bytes = self.unparsed_buffer.drain(..parsed_byte_count);
+
+        if self.exceeded_limit.is_some() {
+            return;
+        }
+
         // How many bytes can we add to the message buffer?
         let remaining_size = self
             .max_message_size
@@ -301,6 +537,199 @@ impl Fragmentizer {
             }
         }
     }
+
+    fn spool_parsed_bytes(
+        &mut self,
+        parsed_byte_count: usize,
+        handle: SpoolHandle,
+        sink: &mut dyn LiteralSink,
+    ) {
+        let (front, back) = self.unparsed_buffer.as_slices();
+        let front_count = parsed_byte_count.min(front.len());
+
+        if front_count > 0 {
+            sink.write(handle, &front[..front_count]);
+        }
+        if parsed_byte_count > front_count {
+            sink.write(handle, &back[..parsed_byte_count - front_count]);
+        }
+
+        self.unparsed_buffer.drain(..parsed_byte_count);
+    }
+
+    fn should_spool(&self, announcement: LiteralAnnouncement) -> bool {
+        match self.spool_threshold {
+            Some(threshold) => {
+                announcement.length > threshold
+                    && !self.max_message_size_exceeded
+                    && self.exceeded_limit.is_none()
+            }
+            None => false,
+        }
+    }
+
+    fn check_line_length(&mut self, line_start: usize, line_length: usize) {
+        let Some(max) = self.limits.line_length else {
+            return;
+        };
+
+        if line_length > max as usize && self.exceeded_limit.is_none() {
+            // Drop the bytes exceeding the limit
+            self.message_buffer
+                .truncate(line_start.saturating_add(max as usize));
+            self.exceeded_limit = Some(ExceededLimit::LineLength { max });
+        }
+    }
+
+    /// Poisons the message if the peer sent something that looks like a placeholder.
+    fn check_spool_placeholder(&mut self, fragment: FragmentInfo) {
+        if self.spool_threshold.is_none() {
+            return;
+        }
+
+        let (start, end) = match fragment {
+            FragmentInfo::Line { start, end, .. } | FragmentInfo::Literal { start, end } => {
+                (start, end)
+            }
+        };
+        let end = end.min(self.message_buffer.len());
+
+        if let Some(bytes) = self.message_buffer.get(start..end) {
+            if bytes
+                .windows(SPOOL_PLACEHOLDER_PREFIX.len())
+                .any(|window| window == SPOOL_PLACEHOLDER_PREFIX)
+            {
+                self.message_poisoned = true;
+            }
+        }
+    }
+
+    fn check_literal(&mut self, announcement: LiteralAnnouncement) {
+        self.literal_count += 1;
+
+        if self.exceeded_limit.is_some() {
+            return;
+        }
+
+        let length = announcement.length;
+
+        self.exceeded_limit = match self.limits {
+            Limits {
+                literal_count: Some(max),
+                ..
+            } if self.literal_count > max => Some(ExceededLimit::LiteralCount { max }),
+            Limits {
+                literal_size: Some(max),
+                ..
+            } if length > max => Some(ExceededLimit::LiteralSize { max, length }),
+            Limits {
+                non_sync_literal_size: Some(max),
+                ..
+            } if announcement.mode == LiteralMode::NonSync && length > max => {
+                Some(ExceededLimit::NonSyncLiteralSize { max, length })
+            }
+            _ => None,
+        };
+    }
+
+    /// Replaces the announced length in the message buffer with the placeholder length.
+    fn start_spooling(&mut self) -> SpoolHandle {
+        // The message buffer ends with `{<length>}\r\n` or `{<length>+}\r\n` (or `\n`)
+        let open = self
+            .message_buffer
+            .iter()
+            .rposition(|b| *b == b'{')
+            .unwrap();
+        let digits_end = open
+            + 1
+            + self.message_buffer[open + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+        self.message_buffer.splice(
+            open + 1..digits_end,
+            SPOOL_PLACEHOLDER_LEN.to_string().into_bytes(),
+        );
+
+        let handle = SpoolHandle(self.next_spool_handle);
+        self.next_spool_handle += 1;
+        self.spooling = Some(handle);
+        self.spooled.handles.push(handle);
+        handle
+    }
+}
+
+/// Receiver for the bytes of spooled literals.
+///
+/// See [`Fragmentizer::with_literal_spooling`].
+pub trait LiteralSink {
+    /// Receives the next bytes of a spooled literal.
+    fn write(&mut self, handle: SpoolHandle, bytes: &[u8]);
+
+    /// Called after all bytes of a spooled literal were written.
+    fn finish(&mut self, handle: SpoolHandle) {
+        let _ = handle;
+    }
+}
+
+/// Discards all bytes.
+impl LiteralSink for () {
+    fn write(&mut self, _: SpoolHandle, _: &[u8]) {}
+}
+
+/// Identifies a literal passed to a [`LiteralSink`].
+///
+/// Handles are unique for a [`Fragmentizer`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SpoolHandle(u64);
+
+impl SpoolHandle {
+    /// Returns the handle as number.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+/// Literals spooled for a message.
+///
+/// See [`Fragmentizer::with_literal_spooling`] and [`Fragmentizer::spooled_literals`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SpooledLiterals {
+    handles: Vec<SpoolHandle>,
+}
+
+impl SpooledLiterals {
+    /// Returns the handles of the spooled literals (in order).
+    pub fn handles(&self) -> &[SpoolHandle] {
+        &self.handles
+    }
+
+    /// Returns the handle of a spooled literal if `data` is its placeholder.
+    ///
+    /// Use this to resolve the data of a decoded literal (e.g. in a `BODY[]` or `APPEND`).
+    /// Placeholders are identified by their content, so `data` may also be taken from a copy of
+    /// the decoded message.
+    pub fn resolve(&self, data: &[u8]) -> Option<SpoolHandle> {
+        if data.len() != SPOOL_PLACEHOLDER_LEN {
+            return None;
+        }
+
+        self.handles
+            .iter()
+            .find(|handle| spool_placeholder(**handle) == data)
+            .copied()
+    }
+}
+
+const SPOOL_PLACEHOLDER_PREFIX: &[u8] = b"SPOOLED-LITERAL-";
+
+const SPOOL_PLACEHOLDER_LEN: usize = 36;
+
+/// Placeholder in the message buffer for a spooled literal, e.g., `SPOOLED-LITERAL-00000000000000000042`.
+fn spool_placeholder(handle: SpoolHandle) -> Vec<u8> {
+    let mut placeholder = SPOOL_PLACEHOLDER_PREFIX.to_vec();
+    placeholder.extend(format!("{:020}", handle.0).into_bytes());
+    placeholder
 }
 
 /// Stateful parser for the next fragment.
@@ -578,6 +1007,21 @@ pub enum LineEnding {
     CrLf,
 }
 
+/// A limit of the [`Fragmentizer`] exceeded by a message.
+///
+/// See [`Fragmentizer::exceeded_limit`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ExceededLimit {
+    /// A line is longer than `max` bytes.
+    LineLength { max: u32 },
+    /// A literal of `length` bytes is larger than `max` bytes.
+    LiteralSize { max: u32, length: u32 },
+    /// The message has more than `max` literals.
+    LiteralCount { max: u32 },
+    /// A non-synchronizing literal of `length` bytes is larger than `max` bytes.
+    NonSyncLiteralSize { max: u32, length: u32 },
+}
+
 /// An error returned by [`Fragmentizer::decode_message`].
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DecodeMessageError<'a, C: Decoder> {
@@ -592,6 +1036,11 @@ pub enum DecodeMessageError<'a, C: Decoder> {
     },
     /// Max message size was exceeded and bytes were dropped.
     MessageTooLong { initial: Secret<&'a [u8]> },
+    /// Another limit was exceeded and bytes were dropped.
+    LimitExceeded {
+        limit: ExceededLimit,
+        initial: Secret<&'a [u8]>,
+    },
     /// The message was explicitly poisoned to prevent decoding.
     MessagePoisoned { discarded: Secret<&'a [u8]> },
 }
@@ -621,14 +1070,19 @@ mod tests {
     use imap_types::{
         command::{Command, CommandBody},
         core::{LiteralMode, Tag},
+        extensions::binary::LiteralOrLiteral8,
         secret::Secret,
+        IntoStatic,
     };
 
     use super::{
-        parse_tag, FragmentInfo, Fragmentizer, LineEnding, LineParser, LiteralAnnouncement,
+        parse_tag, spool_placeholder, ExceededLimit, FragmentInfo, Fragmentizer, LineEnding,
+        LineParser, LiteralAnnouncement, LiteralSink, SpoolHandle,
     };
     use crate::{
-        decode::ResponseDecodeError, fragmentizer::DecodeMessageError, CommandCodec, ResponseCodec,
+        decode::{DecodeFailure, DecodeFailureKind, ResponseDecodeError},
+        fragmentizer::DecodeMessageError,
+        CommandCodec, ResponseCodec,
     };
 
     #[test]
@@ -1257,7 +1711,11 @@ mod tests {
         assert_eq!(
             fragmentizer.decode_message(&response_codec),
             Err(DecodeMessageError::DecodingFailure(
-                ResponseDecodeError::Failed
+                ResponseDecodeError::Failed(DecodeFailure {
+                    offset: 3,
+                    rule: Some("response-tagged"),
+                    kind: DecodeFailureKind::Syntax,
+                })
             )),
         );
 
@@ -1430,6 +1888,254 @@ mod tests {
         assert!(!fragmentizer.is_message_poisoned());
     }
 
+    #[test]
+    fn fragmentizer_max_line_length() {
+        let command_codec = CommandCodec::new();
+
+        let mut fragmentizer = Fragmentizer::without_max_message_size().with_max_line_length(16);
+        fragmentizer.enqueue_bytes(b"A1 LOGIN alice secret\r\n");
+        fragmentizer.enqueue_bytes(b"A2 LOGIN {11}\r\nalice alice secret\r\n");
+
+        fragmentizer.progress();
+        assert!(fragmentizer.is_message_complete());
+        assert_eq!(
+            fragmentizer.exceeded_limit(),
+            Some(ExceededLimit::LineLength { max: 16 })
+        );
+        assert_eq!(
+            fragmentizer.decode_message(&command_codec),
+            Err(DecodeMessageError::LimitExceeded {
+                limit: ExceededLimit::LineLength { max: 16 },
+                initial: Secret::new(b"A1 LOGIN alice s"),
+            }),
+        );
+
+        // Literals don't count towards the line length
+        while fragmentizer.progress().is_some() && !fragmentizer.is_message_complete() {}
+        assert_eq!(fragmentizer.exceeded_limit(), None);
+        assert!(fragmentizer.decode_message(&command_codec).is_ok());
+    }
+
+    #[test]
+    fn fragmentizer_literal_limits() {
+        let mut fragmentizer = Fragmentizer::new(1024)
+            .with_max_literal_size(5)
+            .with_max_non_sync_literal_size(3)
+            .with_max_literal_count(2);
+        fragmentizer.enqueue_bytes(b"A1 LOGIN {6}\r\n");
+        fragmentizer.enqueue_bytes(b"A2 LOGIN {4+}\r\nabcd {3+}\r\nabc\r\n");
+        fragmentizer.enqueue_bytes(b"A3 LOGIN {1}\r\na {1}\r\nb\r\n");
+        fragmentizer.enqueue_bytes(b"A4 APPEND INBOX {1}\r\na {1}\r\nb {1}\r\nc\r\n");
+
+        // The limit is exceeded right after the announcement
+        fragmentizer.progress();
+        assert_eq!(
+            fragmentizer.exceeded_limit(),
+            Some(ExceededLimit::LiteralSize { max: 5, length: 6 })
+        );
+
+        // The server rejects the literal
+        fragmentizer.skip_message();
+        assert_eq!(fragmentizer.exceeded_limit(), None);
+
+        let mut progress = || {
+            while fragmentizer.progress().is_some() && !fragmentizer.is_message_complete() {}
+            (
+                fragmentizer.exceeded_limit(),
+                fragmentizer.message_bytes().to_vec(),
+            )
+        };
+        assert_eq!(
+            progress(),
+            (
+                Some(ExceededLimit::NonSyncLiteralSize { max: 3, length: 4 }),
+                b"A2 LOGIN {4+}\r\n".to_vec()
+            )
+        );
+        assert_eq!(
+            progress(),
+            (None, b"A3 LOGIN {1}\r\na {1}\r\nb\r\n".to_vec())
+        );
+        assert_eq!(
+            progress(),
+            (
+                Some(ExceededLimit::LiteralCount { max: 2 }),
+                b"A4 APPEND INBOX {1}\r\na {1}\r\nb {1}\r\n".to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn fragmentizer_spool_literals() {
+        #[derive(Default)]
+        struct Sink {
+            spooled: Vec<(SpoolHandle, Vec<u8>, bool)>,
+        }
+
+        impl LiteralSink for Sink {
+            fn write(&mut self, handle: SpoolHandle, bytes: &[u8]) {
+                match self.spooled.last_mut() {
+                    Some((last, data, false)) if *last == handle => data.extend(bytes),
+                    _ => self.spooled.push((handle, bytes.to_vec(), false)),
+                }
+            }
+
+            fn finish(&mut self, handle: SpoolHandle) {
+                let (last, _, finished) = self.spooled.last_mut().unwrap();
+                assert_eq!(*last, handle);
+                *finished = true;
+            }
+        }
+
+        let command_codec = CommandCodec::new();
+        let mut sink = Sink::default();
+        let mut fragmentizer = Fragmentizer::new(80).with_literal_spooling(10);
+
+        let message = vec![b'x'; 1000];
+        fragmentizer.enqueue_bytes(b"A1 APPEND INBOX {1000}\r\n");
+        for chunk in message.chunks(300) {
+            fragmentizer.enqueue_bytes(chunk);
+            while fragmentizer.progress_with_sink(&mut sink).is_some() {}
+        }
+        fragmentizer.enqueue_bytes(b"\r\nA2 LOGIN {5+}\r\nalice {1001+}\r\n");
+        fragmentizer.enqueue_bytes(&[b'y'; 1001]);
+        fragmentizer.enqueue_bytes(b"\r\n");
+
+        // The spooled literal doesn't count towards the maximum message size
+        while !fragmentizer.is_message_complete() {
+            fragmentizer.progress_with_sink(&mut sink).unwrap();
+        }
+        assert!(!fragmentizer.is_max_message_size_exceeded());
+        assert_eq!(
+            fragmentizer.message_bytes(),
+            b"A1 APPEND INBOX {36}\r\nSPOOLED-LITERAL-00000000000000000000\r\n"
+        );
+        let handle = fragmentizer.spooled_literals().handles()[0];
+        assert_eq!(sink.spooled, vec![(handle, message, true)]);
+
+        let Ok(Command {
+            body:
+                CommandBody::Append {
+                    message: LiteralOrLiteral8::Literal(message),
+                    ..
+                },
+            ..
+        }) = fragmentizer
+            .decode_message(&command_codec)
+            .map(IntoStatic::into_static)
+        else {
+            panic!("expected APPEND");
+        };
+        // The placeholder is resolved in a copy of the message, too
+        let spooled = fragmentizer.spooled_literals().clone();
+        assert_eq!(spooled.resolve(message.as_ref()), Some(handle));
+
+        // Short literals are not spooled
+        let mut fragments = Vec::new();
+        while !fragmentizer.is_message_complete() || fragments.is_empty() {
+            let fragment = fragmentizer.progress_with_sink(&mut sink).unwrap();
+            fragments.push(fragmentizer.fragment_bytes(fragment).to_vec());
+        }
+        assert_eq!(
+            fragments,
+            vec![
+                b"A2 LOGIN {5+}\r\n".to_vec(),
+                b"alice".to_vec(),
+                b" {36+}\r\n".to_vec(),
+                b"SPOOLED-LITERAL-00000000000000000001".to_vec(),
+                b"\r\n".to_vec(),
+            ]
+        );
+        assert_eq!(sink.spooled[1].1, vec![b'y'; 1001]);
+        let spooled = fragmentizer.spooled_literals();
+        assert_eq!(spooled.resolve(b"alice"), None);
+        assert_eq!(spooled.resolve(&spool_placeholder(handle)), None);
+        assert_eq!(
+            spooled.resolve(&spool_placeholder(spooled.handles()[0])),
+            Some(spooled.handles()[0])
+        );
+        assert!(fragmentizer.decode_message(&command_codec).is_ok());
+
+        // A message with something that looks like a placeholder is poisoned ...
+        let mut fragmentizer = Fragmentizer::new(1024).with_literal_spooling(36);
+        for message in [
+            b"A1 APPEND INBOX {36}\r\nSPOOLED-LITERAL-00000000000000000000\r\n".as_ref(),
+            b"A2 LOGIN SPOOLED-LITERAL-00000000000000000000 {37+}\r\nzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz\r\n",
+        ] {
+            fragmentizer.enqueue_bytes(message);
+            fragmentizer.progress_with_sink(&mut sink).unwrap();
+            while !fragmentizer.is_message_complete() {
+                fragmentizer.progress_with_sink(&mut sink).unwrap();
+            }
+            assert!(fragmentizer.is_message_poisoned());
+            assert!(matches!(
+                fragmentizer.decode_message(&command_codec),
+                Err(DecodeMessageError::MessagePoisoned { .. })
+            ));
+        }
+
+        // ... but only if spooling is enabled
+        let mut fragmentizer = Fragmentizer::new(1024);
+        fragmentizer
+            .enqueue_bytes(b"A1 APPEND INBOX {36}\r\nSPOOLED-LITERAL-00000000000000000000\r\n");
+        while !fragmentizer.is_message_complete() {
+            fragmentizer.progress().unwrap();
+        }
+        assert!(fragmentizer.decode_message(&command_codec).is_ok());
+
+        // The placeholder is subject to the maximum message size
+        let mut fragmentizer = Fragmentizer::new(30).with_literal_spooling(10);
+        fragmentizer.enqueue_bytes(b"A1 APPEND INBOX {1000}\r\n");
+        fragmentizer.enqueue_bytes(&[b'x'; 1000]);
+        fragmentizer.enqueue_bytes(b"\r\n");
+        while !fragmentizer.is_message_complete() {
+            fragmentizer.progress_with_sink(&mut sink).unwrap();
+        }
+        assert!(fragmentizer.is_max_message_size_exceeded());
+        assert_eq!(
+            fragmentizer.message_bytes(),
+            b"A1 APPEND INBOX {36}\r\nSPOOLED-"
+        );
+    }
+
+    #[cfg(feature = "starttls")]
+    #[test]
+    fn fragmentizer_discard_unparsed_bytes() {
+        let command_codec = CommandCodec::new();
+
+        let mut fragmentizer = Fragmentizer::new(1024);
+        fragmentizer.enqueue_bytes(b"A1 STARTTLS\r\nA2 LOGIN {5}\r\nalice");
+
+        fragmentizer.progress();
+        assert_eq!(
+            fragmentizer.decode_message(&command_codec),
+            Ok(Command::new("A1", CommandBody::StartTLS).unwrap()),
+        );
+        assert_eq!(
+            fragmentizer.discard_unparsed_bytes(),
+            Secret::new(b"A2 LOGIN {5}\r\nalice".to_vec())
+        );
+        assert_eq!(fragmentizer.progress(), None);
+
+        // An incomplete message is discarded as well
+        fragmentizer.enqueue_bytes(b"A3 STARTTLS\r\nA4 LOGIN {5}\r\nali");
+        fragmentizer.progress();
+        assert!(fragmentizer.is_message_complete());
+        fragmentizer.progress();
+        assert!(!fragmentizer.is_message_complete());
+        assert_eq!(
+            fragmentizer.discard_unparsed_bytes(),
+            Secret::new(b"A4 LOGIN {5}\r\nali".to_vec())
+        );
+
+        fragmentizer.enqueue_bytes(b"A5 NOOP\r\n");
+        fragmentizer.progress();
+        assert_eq!(
+            fragmentizer.decode_message(&command_codec),
+            Ok(Command::new("A5", CommandBody::Noop).unwrap()),
+        );
+    }
+
     #[track_caller]
     fn assert_not_line(not_a_line_bytes: &[u8]) {
         let mut line_parser = LineParser::new(0);
@@ -87,16 +87,21 @@
 //! | quirk_crlf_relaxed    | Make `\r` in `\r\n` optional.  | No                 |
 //! | quirk_rectify_numbers | Rectify (invalid) numbers.     | No                 |
 //! | quirk_missing_text    | Rectify missing `text` element.| No                 |
+//! | compress              | COMPRESS=DEFLATE stream layer. | No                 |
 //!
 //! ## Quirks
 //!
-//! Features starting with `quirk_` are used to cope with existing interoperability issues.
+//! Quirks are used to cope with existing interoperability issues.
 //! Unfortunately, we already observed some standard violations, such as, negative numbers, and missing syntax elements.
 //! Our policy is as follows: If we see an interoperability issue, we file an issue in the corresponding implementation.
 //! If, for any reason, the issue cannot be fixed, *and* the implementation is "important enough", e.g.,  because a user of
-//! imap-codec can't otherwise access their emails, we may add a `quirk_` feature to quickly resolve the problem.
+//! imap-codec can't otherwise access their emails, we may add a quirk to quickly resolve the problem.
 //! Of course, imap-codec should never violate the IMAP standard itself. So, we need to do this carefully.
 //!
+//! Quirks are configured per codec via [`Quirks`], e.g., `ResponseCodec::new().with_quirks(Quirks::all())`.
+//! Thus, a single application can be strict towards a well-behaved peer and lenient towards a broken one.
+//! Features starting with `quirk_` enable the corresponding quirk by default.
+//!
 //! [imap-types]: https://docs.rs/imap-types/latest/imap_types
 //! [imap-types features]: https://docs.rs/imap-types/latest/imap_types/#features
 //! [IMAP4rev1]: https://tools.ietf.org/html/rfc3501
@@ -127,6 +132,7 @@ mod extensions;
 mod fetch;
 mod flag;
 mod mailbox;
+mod quirks;
 mod response;
 mod search;
 mod sequence;
@@ -134,6 +140,11 @@ mod status;
 #[cfg(test)]
 mod testing;
 
+#[cfg(feature = "compress")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compress")))]
+pub mod compress;
+pub mod fetch_parser;
+pub mod flow;
 pub mod fragmentizer;
 #[cfg(feature = "fuzz")]
 pub mod fuzz {
@@ -143,3 +154,4 @@ pub mod fuzz {
 pub use codec::*;
 // Re-export.
 pub use imap_types;
+pub use quirks::Quirks;
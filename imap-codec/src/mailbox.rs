@@ -9,21 +9,26 @@ use imap_types::{
 use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case, take_while1},
-    combinator::{map, opt, value},
+    combinator::{cond, map, opt, value},
+    error::context,
     multi::many0,
     sequence::{delimited, preceded, terminated, tuple},
 };
 
 #[cfg(feature = "ext_metadata")]
 use crate::extensions::metadata::metadata_resp;
+#[cfg(feature = "ext_xlist")]
+use crate::extensions::xlist::xlist_data;
 use crate::{
     core::{astring, nil, number, nz_number, quoted_char, string},
     decode::IMAPResult,
     extensions::{
+        multisearch::esearch_response,
         quota::{quota_response, quotaroot_response},
         thread::thread_data,
     },
     flag::{flag_list, mbx_list_flags},
+    quirks,
     status::status_att_list,
 };
 
@@ -54,7 +59,7 @@ pub(crate) fn list_mailbox(input: &[u8]) -> IMAPResult<&[u8], ListMailbox> {
 ///
 /// Refer to section 5.1 for further semantic details of mailbox names.
 pub(crate) fn mailbox(input: &[u8]) -> IMAPResult<&[u8], Mailbox> {
-    map(astring, Mailbox::from)(input)
+    context("mailbox", map(astring, Mailbox::from))(input)
 }
 
 /// ```abnf
@@ -64,6 +69,8 @@ pub(crate) fn mailbox(input: &[u8]) -> IMAPResult<&[u8], Mailbox> {
 ///                "SEARCH" *(SP nz-number) /
 ///                "STATUS" SP mailbox SP "(" [status-att-list] ")" /
 ///                "METADATA" SP mailbox SP (entry-values / entry-list) / ; RFC 5464
+///                esearch-response /                                     ; RFC 4731
+///                xlist-data /                                           ; XLIST
 ///                number SP "EXISTS" /
 ///                number SP "RECENT"
 /// ```
@@ -95,15 +102,15 @@ pub(crate) fn mailbox_data(input: &[u8]) -> IMAPResult<&[u8], Data> {
             Data::Sort,
         ),
         thread_data,
+        esearch_response,
+        #[cfg(feature = "ext_xlist")]
+        xlist_data,
         map(
             tuple((
                 tag_no_case(b"STATUS "),
                 mailbox,
                 delimited(tag(b" ("), opt(status_att_list), tag(b")")),
-                #[cfg(feature = "quirk_trailing_space")]
-                opt(sp),
-                #[cfg(not(feature = "quirk_trailing_space"))]
-                nom::combinator::success(()),
+                cond(quirks::current().trailing_space, opt(sp)),
             )),
             |(_, mailbox, items, _)| Data::Status {
                 mailbox,
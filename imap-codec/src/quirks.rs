@@ -0,0 +1,101 @@
+//! Runtime configuration of quirks.
+//!
+//! The quirks are passed to the parsers through a thread-local that is set by the codecs for the
+//! duration of a single [`Decoder::decode`](crate::decode::Decoder::decode) call.
+
+use std::cell::Cell;
+
+use abnf_core::streaming::{crlf as crlf_strict, crlf_relaxed};
+use nom::combinator::value;
+
+use crate::decode::IMAPResult;
+
+/// Quirks to cope with existing interoperability issues.
+///
+/// Quirks are configured per codec, e.g., `ResponseCodec::new().with_quirks(Quirks::all())`.
+/// The default enables the quirks selected through the `quirk_*` features.
+///
+/// See [Quirks](crate#quirks) for our policy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Quirks {
+    /// Make `\r` in `\r\n` optional.
+    pub crlf_relaxed: bool,
+    /// Rectify missing `text` element.
+    pub missing_text: bool,
+    /// Rectify (invalid) numbers.
+    pub rectify_numbers: bool,
+    /// Accept spaces between envelope addresses in `FETCH` data response.
+    pub spaces_between_addresses: bool,
+    /// Accept a trailing space in `STATUS` data response.
+    pub trailing_space: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            crlf_relaxed: cfg!(feature = "quirk_crlf_relaxed"),
+            missing_text: cfg!(feature = "quirk_missing_text"),
+            rectify_numbers: cfg!(feature = "quirk_rectify_numbers"),
+            spaces_between_addresses: cfg!(feature = "quirk_spaces_between_addresses"),
+            trailing_space: cfg!(feature = "quirk_trailing_space"),
+        }
+    }
+}
+
+impl Quirks {
+    /// No quirks, i.e., strict parsing.
+    pub const fn none() -> Self {
+        Self {
+            crlf_relaxed: false,
+            missing_text: false,
+            rectify_numbers: false,
+            spaces_between_addresses: false,
+            trailing_space: false,
+        }
+    }
+
+    /// All quirks.
+    pub const fn all() -> Self {
+        Self {
+            crlf_relaxed: true,
+            missing_text: true,
+            rectify_numbers: true,
+            spaces_between_addresses: true,
+            trailing_space: true,
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<Quirks> = Cell::new(Quirks::default());
+}
+
+/// Returns the quirks of the current decoding.
+pub(crate) fn current() -> Quirks {
+    CURRENT.with(Cell::get)
+}
+
+/// Calls `f` with the given quirks (and restores the previous quirks afterwards).
+pub(crate) fn with<T>(quirks: Quirks, f: impl FnOnce() -> T) -> T {
+    struct Restore(Quirks);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CURRENT.with(|current| current.replace(quirks)));
+
+    f()
+}
+
+/// `CRLF` (or `LF` with [`Quirks::crlf_relaxed`]).
+pub(crate) fn crlf(input: &[u8]) -> IMAPResult<&[u8], ()> {
+    if current().crlf_relaxed {
+        value((), crlf_relaxed)(input)
+    } else {
+        value((), crlf_strict)(input)
+    }
+}
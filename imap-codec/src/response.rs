@@ -1,22 +1,17 @@
-#[cfg(not(feature = "quirk_crlf_relaxed"))]
-use abnf_core::streaming::crlf;
-#[cfg(feature = "quirk_crlf_relaxed")]
-use abnf_core::streaming::crlf_relaxed as crlf;
 use abnf_core::streaming::sp;
 use base64::{engine::general_purpose::STANDARD as _base64, Engine};
 use imap_types::{
     core::{Text, Vec1},
     response::{
         Bye, Capability, Code, CodeOther, CommandContinuationRequest, Data, Greeting, GreetingKind,
-        Response, Status, StatusBody, StatusKind, Tagged,
+        Response, Status, StatusBody, StatusKind, Tagged, Unparsed,
     },
 };
-#[cfg(feature = "quirk_missing_text")]
-use nom::combinator::peek;
 use nom::{
     branch::alt,
-    bytes::streaming::{tag, tag_no_case, take_until, take_while},
-    combinator::{map, map_res, opt, value},
+    bytes::streaming::{tag, tag_no_case, take, take_till, take_until, take_while},
+    combinator::{map, map_res, opt, peek, value, verify},
+    error::context,
     multi::separated_list1,
     sequence::{delimited, preceded, terminated, tuple},
 };
@@ -25,6 +20,8 @@ use nom::{
 use crate::extensions::id::id_response;
 #[cfg(feature = "ext_metadata")]
 use crate::extensions::metadata::metadata_code;
+#[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
+use crate::extensions::referral::resp_code_referral;
 use crate::{
     core::{atom, charset, nz_number, tag_imap, text},
     decode::IMAPResult,
@@ -35,19 +32,23 @@ use crate::{
     fetch::msg_att,
     flag::flag_perm,
     mailbox::mailbox_data,
+    quirks::{self, crlf},
 };
 
 // ----- greeting -----
 
 /// `greeting = "*" SP (resp-cond-auth / resp-cond-bye) CRLF`
 pub(crate) fn greeting(input: &[u8]) -> IMAPResult<&[u8], Greeting> {
-    let mut parser = delimited(
-        tag(b"* "),
-        alt((
-            resp_cond_auth,
-            map(resp_cond_bye, |resp_text| (GreetingKind::Bye, resp_text)),
-        )),
-        crlf,
+    let mut parser = context(
+        "greeting",
+        delimited(
+            tag(b"* "),
+            alt((
+                resp_cond_auth,
+                map(resp_cond_bye, |resp_text| (GreetingKind::Bye, resp_text)),
+            )),
+            crlf,
+        ),
     );
 
     let (remaining, (kind, (code, text))) = parser(input)?;
@@ -99,16 +100,16 @@ pub(crate) fn resp_text(input: &[u8]) -> IMAPResult<&[u8], (Option<Code>, Text)>
                     Some,
                 ),
             ),
-            #[cfg(not(feature = "quirk_missing_text"))]
-            preceded(sp, text),
-            #[cfg(feature = "quirk_missing_text")]
             alt((
                 preceded(sp, text),
-                map(peek(crlf), |_| {
-                    log::warn!("Rectified missing `text` to \"...\"");
+                map(
+                    verify(peek(crlf), |_: &()| quirks::current().missing_text),
+                    |_| {
+                        log::warn!("Rectified missing `text` to \"...\"");
 
-                    Text::unvalidated("...")
-                }),
+                        Text::unvalidated("...")
+                    },
+                ),
             )),
         ))(input)
     } else {
@@ -128,6 +129,7 @@ pub(crate) fn resp_text(input: &[u8]) -> IMAPResult<&[u8], (Option<Code>, Text)>
 ///                  "UIDNEXT" SP nz-number /
 ///                  "UIDVALIDITY" SP nz-number /
 ///                  "UNSEEN" SP nz-number /
+///                  "REFERRAL" SP imapurl / ; RFC 2221, RFC 2193
 ///                  "COMPRESSIONACTIVE" / ; RFC 4978
 ///                  "OVERQUOTA" /         ; RFC 9208
 ///                  "TOOBIG" /            ; RFC 4469
@@ -138,62 +140,103 @@ pub(crate) fn resp_text(input: &[u8]) -> IMAPResult<&[u8], (Option<Code>, Text)>
 ///                    "NOPRIVATE"
 ///                  ) /
 ///                  "UNKNOWN-CTE" /       ; RFC 3516
+///                  "UNAVAILABLE" / "AUTHENTICATIONFAILED" / "AUTHORIZATIONFAILED" /
+///                  "EXPIRED" / "PRIVACYREQUIRED" / "CONTACTADMIN" / "NOPERM" /
+///                  "INUSE" / "EXPUNGEISSUED" / "CORRUPTION" / "SERVERBUG" /
+///                  "CLIENTBUG" / "CANNOT" / "LIMIT" / "ALREADYEXISTS" /
+///                  "NONEXISTENT" /       ; RFC 5530
 ///                  atom [SP 1*<any TEXT-CHAR except "]">]
 /// ```
 ///
 /// Note: See errata id: 261
 pub(crate) fn resp_text_code(input: &[u8]) -> IMAPResult<&[u8], Code> {
-    alt((
-        value(Code::Alert, tag_no_case(b"ALERT")),
-        map(
-            preceded(
-                tag_no_case(b"BADCHARSET"),
-                opt(delimited(
-                    tag(b" ("),
-                    separated_list1(sp, charset),
-                    tag(b")"),
-                )),
-            ),
-            |maybe_charsets| Code::BadCharset {
-                allowed: maybe_charsets.unwrap_or_default(),
-            },
-        ),
-        map(capability_data, Code::Capability),
-        value(Code::Parse, tag_no_case(b"PARSE")),
-        map(
-            preceded(
-                tag_no_case(b"PERMANENTFLAGS "),
-                delimited(
-                    tag(b"("),
-                    map(opt(separated_list1(sp, flag_perm)), |maybe_flags| {
-                        maybe_flags.unwrap_or_default()
-                    }),
-                    tag(b")"),
+    context(
+        "resp-text-code",
+        alt((
+            value(Code::Alert, tag_no_case(b"ALERT")),
+            map(
+                preceded(
+                    tag_no_case(b"BADCHARSET"),
+                    opt(delimited(
+                        tag(b" ("),
+                        separated_list1(sp, charset),
+                        tag(b")"),
+                    )),
                 ),
+                |maybe_charsets| Code::BadCharset {
+                    allowed: maybe_charsets.unwrap_or_default(),
+                },
             ),
-            Code::PermanentFlags,
-        ),
-        value(Code::ReadOnly, tag_no_case(b"READ-ONLY")),
-        value(Code::ReadWrite, tag_no_case(b"READ-WRITE")),
-        value(Code::TryCreate, tag_no_case(b"TRYCREATE")),
-        map(preceded(tag_no_case(b"UIDNEXT "), nz_number), Code::UidNext),
-        map(
-            preceded(tag_no_case(b"UIDVALIDITY "), nz_number),
-            Code::UidValidity,
+            map(capability_data, Code::Capability),
+            value(Code::Parse, tag_no_case(b"PARSE")),
+            map(
+                preceded(
+                    tag_no_case(b"PERMANENTFLAGS "),
+                    delimited(
+                        tag(b"("),
+                        map(opt(separated_list1(sp, flag_perm)), |maybe_flags| {
+                            maybe_flags.unwrap_or_default()
+                        }),
+                        tag(b")"),
+                    ),
+                ),
+                Code::PermanentFlags,
+            ),
+            value(Code::ReadOnly, tag_no_case(b"READ-ONLY")),
+            value(Code::ReadWrite, tag_no_case(b"READ-WRITE")),
+            value(Code::TryCreate, tag_no_case(b"TRYCREATE")),
+            map(preceded(tag_no_case(b"UIDNEXT "), nz_number), Code::UidNext),
+            map(
+                preceded(tag_no_case(b"UIDVALIDITY "), nz_number),
+                Code::UidValidity,
+            ),
+            map(preceded(tag_no_case(b"UNSEEN "), nz_number), Code::Unseen),
+            #[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
+            resp_code_referral,
+            value(Code::CompressionActive, tag_no_case(b"COMPRESSIONACTIVE")),
+            value(Code::OverQuota, tag_no_case(b"OVERQUOTA")),
+            value(Code::TooBig, tag_no_case(b"TOOBIG")),
+            #[cfg(feature = "ext_metadata")]
+            map(
+                preceded(tag_no_case("METADATA "), metadata_code),
+                Code::Metadata,
+            ),
+            value(Code::UnknownCte, tag_no_case(b"UNKNOWN-CTE")),
+            resp_code_apnd,
+            resp_code_copy,
+            value(Code::UidNotSticky, tag_no_case(b"UIDNOTSTICKY")),
+            resp_text_code_rfc5530,
+        )),
+    )(input)
+}
+
+/// Response codes from RFC 5530.
+///
+/// Note: `OVERQUOTA` is also listed in RFC 5530 but is parsed in [`resp_text_code`].
+fn resp_text_code_rfc5530(input: &[u8]) -> IMAPResult<&[u8], Code> {
+    alt((
+        value(Code::Unavailable, tag_no_case(b"UNAVAILABLE")),
+        value(
+            Code::AuthenticationFailed,
+            tag_no_case(b"AUTHENTICATIONFAILED"),
         ),
-        map(preceded(tag_no_case(b"UNSEEN "), nz_number), Code::Unseen),
-        value(Code::CompressionActive, tag_no_case(b"COMPRESSIONACTIVE")),
-        value(Code::OverQuota, tag_no_case(b"OVERQUOTA")),
-        value(Code::TooBig, tag_no_case(b"TOOBIG")),
-        #[cfg(feature = "ext_metadata")]
-        map(
-            preceded(tag_no_case("METADATA "), metadata_code),
-            Code::Metadata,
+        value(
+            Code::AuthorizationFailed,
+            tag_no_case(b"AUTHORIZATIONFAILED"),
         ),
-        value(Code::UnknownCte, tag_no_case(b"UNKNOWN-CTE")),
-        resp_code_apnd,
-        resp_code_copy,
-        value(Code::UidNotSticky, tag_no_case(b"UIDNOTSTICKY")),
+        value(Code::Expired, tag_no_case(b"EXPIRED")),
+        value(Code::PrivacyRequired, tag_no_case(b"PRIVACYREQUIRED")),
+        value(Code::ContactAdmin, tag_no_case(b"CONTACTADMIN")),
+        value(Code::NoPerm, tag_no_case(b"NOPERM")),
+        value(Code::InUse, tag_no_case(b"INUSE")),
+        value(Code::ExpungeIssued, tag_no_case(b"EXPUNGEISSUED")),
+        value(Code::Corruption, tag_no_case(b"CORRUPTION")),
+        value(Code::ServerBug, tag_no_case(b"SERVERBUG")),
+        value(Code::ClientBug, tag_no_case(b"CLIENTBUG")),
+        value(Code::Cannot, tag_no_case(b"CANNOT")),
+        value(Code::Limit, tag_no_case(b"LIMIT")),
+        value(Code::AlreadyExists, tag_no_case(b"ALREADYEXISTS")),
+        value(Code::NonExistent, tag_no_case(b"NONEXISTENT")),
     ))(input)
 }
 
@@ -212,7 +255,7 @@ pub(crate) fn capability_data(input: &[u8]) -> IMAPResult<&[u8], Vec1<Capability
 ///               "COMPRESS=" algorithm / ; RFC 4978
 ///               atom`
 pub(crate) fn capability(input: &[u8]) -> IMAPResult<&[u8], Capability> {
-    map(atom, Capability::from)(input)
+    context("capability", map(atom, Capability::from))(input)
 }
 
 /// `resp-cond-bye = "BYE" SP resp-text`
@@ -236,6 +279,93 @@ pub(crate) fn response(input: &[u8]) -> IMAPResult<&[u8], Response> {
     ))(input)
 }
 
+/// Lenient fallback for responses that can't be parsed.
+///
+/// An untagged response is kept as [`Unparsed`]. A tagged response is recovered as a status with
+/// `tag SP ("OK" / "NO" / "BAD")`, so that its command can still be completed. The rest of the
+/// line (including a malformed code) is kept as text.
+pub(crate) fn response_lenient(input: &[u8]) -> IMAPResult<&[u8], Response> {
+    alt((
+        map(unparsed, Response::Unparsed),
+        map(response_tagged_recovered, Response::Status),
+    ))(input)
+}
+
+/// Recognizes `"*" SP` followed by all lines and literals of the response.
+pub(crate) fn unparsed(input: &[u8]) -> IMAPResult<&[u8], Unparsed> {
+    let (mut remaining, _) = terminated(tag(b"*"), sp)(input)?;
+
+    loop {
+        let (rem, line) = terminated(take_till(|b| b == b'\r' || b == b'\n'), crlf)(remaining)?;
+
+        match literal_length(line) {
+            Some(length) => (remaining, _) = take(length)(rem)?,
+            None => {
+                let raw = &input[..input.len() - rem.len()];
+                return Ok((rem, Unparsed::unvalidated(raw)));
+            }
+        }
+    }
+}
+
+/// Recognizes `tag SP ("OK" / "NO" / "BAD") [SP *(any but CR / LF)] CRLF`.
+fn response_tagged_recovered(input: &[u8]) -> IMAPResult<&[u8], Status> {
+    let mut parser = tuple((
+        tag_imap,
+        sp,
+        alt((
+            value(StatusKind::Ok, tag_no_case("OK")),
+            value(StatusKind::No, tag_no_case("NO")),
+            value(StatusKind::Bad, tag_no_case("BAD")),
+        )),
+        opt(preceded(sp, take_till(|b| b == b'\r' || b == b'\n'))),
+        crlf,
+    ));
+
+    let (remaining, (tag, _, kind, rest, _)) = parser(input)?;
+
+    let text = match rest.map(Text::try_from) {
+        Some(Ok(text)) => text,
+        _ => {
+            log::warn!(
+                "Rectified missing or invalid `text` of tagged status `{}` to \"...\"",
+                tag.as_ref()
+            );
+
+            Text::unvalidated("...")
+        }
+    };
+
+    Ok((
+        remaining,
+        Status::Tagged(Tagged {
+            tag,
+            body: StatusBody {
+                kind,
+                code: None,
+                text,
+            },
+        }),
+    ))
+}
+
+/// Returns the length of the literal announced at the end of `line` (if any).
+fn literal_length(line: &[u8]) -> Option<u32> {
+    let line = line.strip_suffix(b"}")?;
+    let digits = &line[line.iter().rposition(|b| *b == b'{')? + 1..];
+    // LITERAL+ and LITERAL-
+    let digits = digits.strip_suffix(b"+").unwrap_or(digits);
+
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    // # Safety
+    //
+    // `unwrap` is safe because `digits` contains ASCII-only characters.
+    std::str::from_utf8(digits).unwrap().parse().ok()
+}
+
 /// `continue-req = "+" SP (resp-text / base64) CRLF`
 pub(crate) fn continue_req(input: &[u8]) -> IMAPResult<&[u8], CommandContinuationRequest> {
     // We can't map the output of `resp_text` directly to `Continue::basic()` because we might end
@@ -248,29 +378,31 @@ pub(crate) fn continue_req(input: &[u8]) -> IMAPResult<&[u8], CommandContinuatio
         Basic(B),
     }
 
-    let mut parser = tuple((
-        tag(b"+ "),
-        alt((
-            #[cfg(not(feature = "quirk_crlf_relaxed"))]
-            map(
-                map_res(take_until("\r\n"), |input| _base64.decode(input)),
-                Either::Base64,
-            ),
-            #[cfg(feature = "quirk_crlf_relaxed")]
-            map(
-                map_res(take_until("\n"), |input: &[u8]| {
-                    if !input.is_empty() && input[input.len().saturating_sub(1)] == b'\r' {
-                        _base64.decode(&input[..input.len().saturating_sub(1)])
-                    } else {
-                        _base64.decode(input)
-                    }
-                }),
-                Either::Base64,
-            ),
-            map(resp_text, Either::Basic),
+    let mut parser = context(
+        "continue-req",
+        tuple((
+            tag(b"+ "),
+            alt((
+                map(
+                    map_res(
+                        |input| {
+                            if quirks::current().crlf_relaxed {
+                                map(take_until("\n"), |input: &[u8]| {
+                                    input.strip_suffix(b"\r").unwrap_or(input)
+                                })(input)
+                            } else {
+                                take_until("\r\n")(input)
+                            }
+                        },
+                        |input| _base64.decode(input),
+                    ),
+                    Either::Base64,
+                ),
+                map(resp_text, Either::Basic),
+            )),
+            crlf,
         )),
-        crlf,
-    ));
+    );
 
     let (remaining, (_, either, _)) = parser(input)?;
 
@@ -293,27 +425,30 @@ pub(crate) fn continue_req(input: &[u8]) -> IMAPResult<&[u8], CommandContinuatio
 ///                  ) CRLF
 /// ```
 pub(crate) fn response_data(input: &[u8]) -> IMAPResult<&[u8], Response> {
-    delimited(
-        tag(b"* "),
-        alt((
-            map(resp_cond_state, |(kind, code, text)| {
-                Response::Status(Status::Untagged(StatusBody { kind, code, text }))
-            }),
-            map(resp_cond_bye, |(code, text)| {
-                Response::Status(Status::Bye(Bye { code, text }))
-            }),
-            map(mailbox_data, Response::Data),
-            map(message_data, Response::Data),
-            map(capability_data, |caps| {
-                Response::Data(Data::Capability(caps))
-            }),
-            map(enable_data, Response::Data),
-            #[cfg(feature = "ext_id")]
-            map(id_response, |parameters| {
-                Response::Data(Data::Id { parameters })
-            }),
-        )),
-        crlf,
+    context(
+        "response-data",
+        delimited(
+            tag(b"* "),
+            alt((
+                map(resp_cond_state, |(kind, code, text)| {
+                    Response::Status(Status::Untagged(StatusBody { kind, code, text }))
+                }),
+                map(resp_cond_bye, |(code, text)| {
+                    Response::Status(Status::Bye(Bye { code, text }))
+                }),
+                map(mailbox_data, Response::Data),
+                map(message_data, Response::Data),
+                map(capability_data, |caps| {
+                    Response::Data(Data::Capability(caps))
+                }),
+                map(enable_data, Response::Data),
+                #[cfg(feature = "ext_id")]
+                map(id_response, |parameters| {
+                    Response::Data(Data::Id { parameters })
+                }),
+            )),
+            crlf,
+        ),
     )(input)
 }
 
@@ -342,7 +477,10 @@ pub(crate) fn response_done(input: &[u8]) -> IMAPResult<&[u8], Status> {
 
 /// `response-tagged = tag SP resp-cond-state CRLF`
 pub(crate) fn response_tagged(input: &[u8]) -> IMAPResult<&[u8], Status> {
-    let mut parser = tuple((tag_imap, sp, resp_cond_state, crlf));
+    let mut parser = context(
+        "response-tagged",
+        tuple((tag_imap, sp, resp_cond_state, crlf)),
+    );
 
     let (remaining, (tag, _, (kind, code, text), _)) = parser(input)?;
 
@@ -392,7 +530,10 @@ mod tests {
     };
 
     use super::*;
-    use crate::testing::{kat_inverse_greeting, kat_inverse_response, known_answer_test_encode};
+    use crate::{
+        testing::{kat_inverse_greeting, kat_inverse_response, known_answer_test_encode},
+        Quirks,
+    };
 
     #[test]
     fn test_kat_inverse_greeting() {
@@ -570,6 +711,40 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_kat_inverse_response_code_rfc5530() {
+        let tests = [
+            (b"UNAVAILABLE".as_ref(), Code::Unavailable),
+            (b"AUTHENTICATIONFAILED", Code::AuthenticationFailed),
+            (b"AUTHORIZATIONFAILED", Code::AuthorizationFailed),
+            (b"EXPIRED", Code::Expired),
+            (b"PRIVACYREQUIRED", Code::PrivacyRequired),
+            (b"CONTACTADMIN", Code::ContactAdmin),
+            (b"NOPERM", Code::NoPerm),
+            (b"INUSE", Code::InUse),
+            (b"EXPUNGEISSUED", Code::ExpungeIssued),
+            (b"CORRUPTION", Code::Corruption),
+            (b"SERVERBUG", Code::ServerBug),
+            (b"CLIENTBUG", Code::ClientBug),
+            (b"CANNOT", Code::Cannot),
+            (b"LIMIT", Code::Limit),
+            (b"ALREADYEXISTS", Code::AlreadyExists),
+            (b"NONEXISTENT", Code::NonExistent),
+        ];
+
+        for (raw, code) in tests {
+            let input = [b"A1 NO [".as_ref(), raw, b"] ...\r\n"].concat();
+
+            kat_inverse_response(&[(
+                input.as_ref(),
+                b"".as_ref(),
+                Response::Status(
+                    Status::no(Some(Tag::try_from("A1").unwrap()), Some(code), "...").unwrap(),
+                ),
+            )]);
+        }
+    }
+
     /*
     // TODO(#184)
     #[test]
@@ -673,6 +848,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unparsed() {
+        let (rem, got) = unparsed(b"* XVENDOR {3}\r\nabc ~{1}\r\nd\r\nA1 OK ...\r\n").unwrap();
+        assert_eq!(rem, b"A1 OK ...\r\n");
+        assert_eq!(got.raw(), b"* XVENDOR {3}\r\nabc ~{1}\r\nd\r\n");
+        known_answer_test_encode((
+            Response::Unparsed(got),
+            b"* XVENDOR {3}\r\nabc ~{1}\r\nd\r\n",
+        ));
+
+        // Not a literal announcement.
+        let (rem, got) = unparsed(b"* XVENDOR {x}\r\n").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(got.raw(), b"* XVENDOR {x}\r\n");
+
+        assert!(unparsed(b"+ ...\r\n").is_err());
+        assert!(unparsed(b"A1 XVENDOR\r\n").is_err());
+        assert!(matches!(
+            unparsed(b"* XVENDOR {3}\r\nab"),
+            Err(nom::Err::Incomplete(_))
+        ));
+    }
+
     #[test]
     fn test_parse_response_negative() {
         let tests = [
@@ -687,20 +885,18 @@ mod tests {
 
     #[test]
     fn test_parse_resp_text_quirk() {
-        #[cfg(not(feature = "quirk_missing_text"))]
-        {
+        quirks::with(Quirks::none(), || {
             assert!(resp_text(b"[IMAP4rev1]\r\n").is_err());
             assert!(resp_text(b"[IMAP4rev1]\r\n").is_err());
             assert!(resp_text(b"[IMAP4rev1] \r\n").is_err());
             assert!(resp_text(b"[IMAP4rev1]  \r\n").is_ok());
-        }
+        });
 
-        #[cfg(feature = "quirk_missing_text")]
-        {
+        quirks::with(Quirks::all(), || {
             assert!(resp_text(b"[IMAP4rev1]\r\n").is_ok());
             assert!(resp_text(b"[IMAP4rev1] \r\n").is_err());
             assert!(resp_text(b"[IMAP4rev1]  \r\n").is_ok());
-        }
+        });
     }
 
     #[test]
@@ -708,14 +904,12 @@ mod tests {
         assert!(response_data(b"* STATUS INBOX (MESSAGES 100 UNSEEN 0)\r\n").is_ok());
         assert!(response_data(b"* STATUS INBOX (MESSAGES 100 UNSEEN 0)  \r\n").is_err());
 
-        #[cfg(not(feature = "quirk_trailing_space"))]
-        {
+        quirks::with(Quirks::none(), || {
             assert!(response_data(b"* STATUS INBOX (MESSAGES 100 UNSEEN 0) \r\n").is_err());
-        }
+        });
 
-        #[cfg(feature = "quirk_trailing_space")]
-        {
+        quirks::with(Quirks::all(), || {
             assert!(response_data(b"* STATUS INBOX (MESSAGES 100 UNSEEN 0) \r\n").is_ok());
-        }
+        });
     }
 }
@@ -8,6 +8,7 @@ use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
     combinator::{map, map_opt, opt, value},
+    error::context,
     multi::separated_list1,
     sequence::{delimited, separated_pair, tuple},
 };
@@ -84,6 +85,7 @@ pub(crate) fn search(input: &[u8]) -> IMAPResult<&[u8], CommandBody> {
 ///               "SMALLER" SP number /
 ///               "UID" SP sequence-set /
 ///               "UNDRAFT" /
+///               "X-GM-RAW" SP astring / ; X-GM-EXT-1
 ///               sequence-set /
 ///               "(" search-key *(SP search-key) ")"`
 ///
@@ -99,121 +101,130 @@ fn search_key_limited(input: &[u8], remaining_recursion: usize) -> IMAPResult<&[
     if remaining_recursion == 0 {
         return Err(nom::Err::Failure(IMAPParseError {
             input,
+            rule: None,
             kind: IMAPErrorKind::RecursionLimitExceeded,
         }));
     }
 
     let search_key = |input| search_key_limited(input, remaining_recursion.saturating_sub(1));
 
-    alt((
+    context(
+        "search-key",
         alt((
-            value(SearchKey::All, tag_no_case(b"ALL")),
-            value(SearchKey::Answered, tag_no_case(b"ANSWERED")),
-            map(tuple((tag_no_case(b"BCC"), sp, astring)), |(_, _, val)| {
-                SearchKey::Bcc(val)
-            }),
-            map(
-                tuple((tag_no_case(b"BEFORE"), sp, map_opt(date, |date| date))),
-                |(_, _, date)| SearchKey::Before(date),
-            ),
-            map(tuple((tag_no_case(b"BODY"), sp, astring)), |(_, _, val)| {
-                SearchKey::Body(val)
-            }),
-            map(tuple((tag_no_case(b"CC"), sp, astring)), |(_, _, val)| {
-                SearchKey::Cc(val)
-            }),
-            value(SearchKey::Deleted, tag_no_case(b"DELETED")),
-            value(SearchKey::Flagged, tag_no_case(b"FLAGGED")),
-            map(tuple((tag_no_case(b"FROM"), sp, astring)), |(_, _, val)| {
-                SearchKey::From(val)
-            }),
-            map(
-                // Note: `flag_keyword` parser returns `Flag`. Because Rust does not have first-class enum variants
-                // it is not possible to fix SearchKey(Flag::Keyword), but only SearchKey(Flag).
-                // Thus `SearchKey::Keyword(Atom)` is used instead. This is, why we use also `atom` parser here and not `flag_keyword` parser.
-                tuple((tag_no_case(b"KEYWORD"), sp, atom)),
-                |(_, _, val)| SearchKey::Keyword(val),
-            ),
-            value(SearchKey::New, tag_no_case(b"NEW")),
-            value(SearchKey::Old, tag_no_case(b"OLD")),
-            map(
-                tuple((tag_no_case(b"ON"), sp, map_opt(date, |date| date))),
-                |(_, _, date)| SearchKey::On(date),
-            ),
-            value(SearchKey::Recent, tag_no_case(b"RECENT")),
-            value(SearchKey::Seen, tag_no_case(b"SEEN")),
-            map(
-                tuple((tag_no_case(b"SINCE"), sp, map_opt(date, |date| date))),
-                |(_, _, date)| SearchKey::Since(date),
-            ),
-            map(
-                tuple((tag_no_case(b"SUBJECT"), sp, astring)),
-                |(_, _, val)| SearchKey::Subject(val),
-            ),
-            map(tuple((tag_no_case(b"TEXT"), sp, astring)), |(_, _, val)| {
-                SearchKey::Text(val)
-            }),
-            map(tuple((tag_no_case(b"TO"), sp, astring)), |(_, _, val)| {
-                SearchKey::To(val)
-            }),
-        )),
-        alt((
-            value(SearchKey::Unanswered, tag_no_case(b"UNANSWERED")),
-            value(SearchKey::Undeleted, tag_no_case(b"UNDELETED")),
-            value(SearchKey::Unflagged, tag_no_case(b"UNFLAGGED")),
-            map(
-                // Note: `flag_keyword` parser returns `Flag`. Because Rust does not have first-class enum variants
-                // it is not possible to fix SearchKey(Flag::Keyword), but only SearchKey(Flag).
-                // Thus `SearchKey::Keyword(Atom)` is used instead. This is, why we use also `atom` parser here and not `flag_keyword` parser.
-                tuple((tag_no_case(b"UNKEYWORD"), sp, atom)),
-                |(_, _, val)| SearchKey::Unkeyword(val),
-            ),
-            value(SearchKey::Unseen, tag_no_case(b"UNSEEN")),
-            value(SearchKey::Draft, tag_no_case(b"DRAFT")),
-            map(
-                tuple((tag_no_case(b"HEADER"), sp, header_fld_name, sp, astring)),
-                |(_, _, key, _, val)| SearchKey::Header(key, val),
-            ),
-            map(
-                tuple((tag_no_case(b"LARGER"), sp, number)),
-                |(_, _, val)| SearchKey::Larger(val),
-            ),
-            map(
-                tuple((tag_no_case(b"NOT"), sp, search_key)),
-                |(_, _, val)| SearchKey::Not(Box::new(val)),
-            ),
-            map(
-                tuple((tag_no_case(b"OR"), sp, search_key, sp, search_key)),
-                |(_, _, alt1, _, alt2)| SearchKey::Or(Box::new(alt1), Box::new(alt2)),
-            ),
-            map(
-                tuple((tag_no_case(b"SENTBEFORE"), sp, map_opt(date, |date| date))),
-                |(_, _, date)| SearchKey::SentBefore(date),
-            ),
-            map(
-                tuple((tag_no_case(b"SENTON"), sp, map_opt(date, |date| date))),
-                |(_, _, date)| SearchKey::SentOn(date),
-            ),
-            map(
-                tuple((tag_no_case(b"SENTSINCE"), sp, map_opt(date, |date| date))),
-                |(_, _, date)| SearchKey::SentSince(date),
-            ),
-            map(
-                tuple((tag_no_case(b"SMALLER"), sp, number)),
-                |(_, _, val)| SearchKey::Smaller(val),
-            ),
-            map(
-                tuple((tag_no_case(b"UID"), sp, sequence_set)),
-                |(_, _, val)| SearchKey::Uid(val),
-            ),
-            value(SearchKey::Undraft, tag_no_case(b"UNDRAFT")),
-            map(sequence_set, SearchKey::SequenceSet),
-            map(
-                delimited(tag(b"("), separated_list1(sp, search_key), tag(b")")),
-                |val| SearchKey::And(Vec1::unvalidated(val)),
-            ),
+            alt((
+                value(SearchKey::All, tag_no_case(b"ALL")),
+                value(SearchKey::Answered, tag_no_case(b"ANSWERED")),
+                map(tuple((tag_no_case(b"BCC"), sp, astring)), |(_, _, val)| {
+                    SearchKey::Bcc(val)
+                }),
+                map(
+                    tuple((tag_no_case(b"BEFORE"), sp, map_opt(date, |date| date))),
+                    |(_, _, date)| SearchKey::Before(date),
+                ),
+                map(tuple((tag_no_case(b"BODY"), sp, astring)), |(_, _, val)| {
+                    SearchKey::Body(val)
+                }),
+                map(tuple((tag_no_case(b"CC"), sp, astring)), |(_, _, val)| {
+                    SearchKey::Cc(val)
+                }),
+                value(SearchKey::Deleted, tag_no_case(b"DELETED")),
+                value(SearchKey::Flagged, tag_no_case(b"FLAGGED")),
+                map(tuple((tag_no_case(b"FROM"), sp, astring)), |(_, _, val)| {
+                    SearchKey::From(val)
+                }),
+                map(
+                    // Note: `flag_keyword` parser returns `Flag`. Because Rust does not have first-class enum variants
+                    // it is not possible to fix SearchKey(Flag::Keyword), but only SearchKey(Flag).
+                    // Thus `SearchKey::Keyword(Atom)` is used instead. This is, why we use also `atom` parser here and not `flag_keyword` parser.
+                    tuple((tag_no_case(b"KEYWORD"), sp, atom)),
+                    |(_, _, val)| SearchKey::Keyword(val),
+                ),
+                value(SearchKey::New, tag_no_case(b"NEW")),
+                value(SearchKey::Old, tag_no_case(b"OLD")),
+                map(
+                    tuple((tag_no_case(b"ON"), sp, map_opt(date, |date| date))),
+                    |(_, _, date)| SearchKey::On(date),
+                ),
+                value(SearchKey::Recent, tag_no_case(b"RECENT")),
+                value(SearchKey::Seen, tag_no_case(b"SEEN")),
+                map(
+                    tuple((tag_no_case(b"SINCE"), sp, map_opt(date, |date| date))),
+                    |(_, _, date)| SearchKey::Since(date),
+                ),
+                map(
+                    tuple((tag_no_case(b"SUBJECT"), sp, astring)),
+                    |(_, _, val)| SearchKey::Subject(val),
+                ),
+                map(tuple((tag_no_case(b"TEXT"), sp, astring)), |(_, _, val)| {
+                    SearchKey::Text(val)
+                }),
+                map(tuple((tag_no_case(b"TO"), sp, astring)), |(_, _, val)| {
+                    SearchKey::To(val)
+                }),
+            )),
+            alt((
+                value(SearchKey::Unanswered, tag_no_case(b"UNANSWERED")),
+                value(SearchKey::Undeleted, tag_no_case(b"UNDELETED")),
+                value(SearchKey::Unflagged, tag_no_case(b"UNFLAGGED")),
+                map(
+                    // Note: `flag_keyword` parser returns `Flag`. Because Rust does not have first-class enum variants
+                    // it is not possible to fix SearchKey(Flag::Keyword), but only SearchKey(Flag).
+                    // Thus `SearchKey::Keyword(Atom)` is used instead. This is, why we use also `atom` parser here and not `flag_keyword` parser.
+                    tuple((tag_no_case(b"UNKEYWORD"), sp, atom)),
+                    |(_, _, val)| SearchKey::Unkeyword(val),
+                ),
+                value(SearchKey::Unseen, tag_no_case(b"UNSEEN")),
+                value(SearchKey::Draft, tag_no_case(b"DRAFT")),
+                map(
+                    tuple((tag_no_case(b"HEADER"), sp, header_fld_name, sp, astring)),
+                    |(_, _, key, _, val)| SearchKey::Header(key, val),
+                ),
+                map(
+                    tuple((tag_no_case(b"LARGER"), sp, number)),
+                    |(_, _, val)| SearchKey::Larger(val),
+                ),
+                map(
+                    tuple((tag_no_case(b"NOT"), sp, search_key)),
+                    |(_, _, val)| SearchKey::Not(Box::new(val)),
+                ),
+                map(
+                    tuple((tag_no_case(b"OR"), sp, search_key, sp, search_key)),
+                    |(_, _, alt1, _, alt2)| SearchKey::Or(Box::new(alt1), Box::new(alt2)),
+                ),
+                map(
+                    tuple((tag_no_case(b"SENTBEFORE"), sp, map_opt(date, |date| date))),
+                    |(_, _, date)| SearchKey::SentBefore(date),
+                ),
+                map(
+                    tuple((tag_no_case(b"SENTON"), sp, map_opt(date, |date| date))),
+                    |(_, _, date)| SearchKey::SentOn(date),
+                ),
+                map(
+                    tuple((tag_no_case(b"SENTSINCE"), sp, map_opt(date, |date| date))),
+                    |(_, _, date)| SearchKey::SentSince(date),
+                ),
+                map(
+                    tuple((tag_no_case(b"SMALLER"), sp, number)),
+                    |(_, _, val)| SearchKey::Smaller(val),
+                ),
+                map(
+                    tuple((tag_no_case(b"UID"), sp, sequence_set)),
+                    |(_, _, val)| SearchKey::Uid(val),
+                ),
+                value(SearchKey::Undraft, tag_no_case(b"UNDRAFT")),
+                #[cfg(feature = "ext_gmail")]
+                map(
+                    tuple((tag_no_case(b"X-GM-RAW"), sp, astring)),
+                    |(_, _, val)| SearchKey::XGmRaw(val),
+                ),
+                map(sequence_set, SearchKey::SequenceSet),
+                map(
+                    delimited(tag(b"("), separated_list1(sp, search_key), tag(b")")),
+                    |val| SearchKey::And(Vec1::unvalidated(val)),
+                ),
+            )),
         )),
-    ))(input)
+    )(input)
 }
 
 /// ```abnf
@@ -6,6 +6,7 @@ use nom::{
     branch::alt,
     bytes::streaming::tag,
     combinator::{map, value},
+    error::context,
     multi::separated_list1,
     sequence::tuple,
 };
@@ -32,16 +33,19 @@ use crate::{core::nz_number, decode::IMAPResult};
 ///
 /// `sequence-set = (seq-number / seq-range) *("," (seq-number / seq-range))`
 pub(crate) fn sequence_set(input: &[u8]) -> IMAPResult<&[u8], SequenceSet> {
-    map(
-        separated_list1(
-            tag(b","),
-            alt((
-                // Ordering is important!
-                map(seq_range, |(from, to)| Sequence::Range(from, to)),
-                map(seq_number, Sequence::Single),
-            )),
+    context(
+        "sequence-set",
+        map(
+            separated_list1(
+                tag(b","),
+                alt((
+                    // Ordering is important!
+                    map(seq_range, |(from, to)| Sequence::Range(from, to)),
+                    map(seq_number, Sequence::Single),
+                )),
+            ),
+            |set| SequenceSet(Vec1::unvalidated(set)),
         ),
-        |set| SequenceSet(Vec1::unvalidated(set)),
     )(input)
 }
 
@@ -73,6 +73,10 @@ fn test_lines_of_trace(trace: &[u8]) {
                 assert!(rem.is_empty());
                 println!("Parsed      {:?}", parsed);
                 let serialized = CommandCodec::default().encode(&parsed).dump();
+                assert_eq!(
+                    CommandCodec::default().encoded_len(&parsed).total(),
+                    serialized.len()
+                );
                 println!(
                     "Serialized: {}",
                     String::from_utf8_lossy(&serialized).trim()
@@ -88,6 +92,10 @@ fn test_lines_of_trace(trace: &[u8]) {
                 println!("Parsed:     {:?}", parsed);
                 assert!(rem.is_empty());
                 let serialized = ResponseCodec::default().encode(&parsed).dump();
+                assert_eq!(
+                    ResponseCodec::default().encoded_len(&parsed).total(),
+                    serialized.len()
+                );
                 println!(
                     "Serialized: {}",
                     String::from_utf8_lossy(&serialized).trim()
@@ -1,6 +1,12 @@
 use arbitrary::{Arbitrary, Unstructured};
 use chrono::{FixedOffset, TimeZone};
 
+#[cfg(feature = "ext_id")]
+use crate::extensions::id::{IdKey, IdParameters};
+#[cfg(feature = "ext_metadata")]
+use crate::extensions::metadata::Entry;
+#[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
+use crate::extensions::referral::ImapUrl;
 use crate::{
     auth::AuthMechanism,
     body::{
@@ -16,7 +22,7 @@ use crate::{
     mailbox::{ListCharString, Mailbox, MailboxOther},
     response::{
         Bye, Capability, Code, CodeOther, CommandContinuationRequestBasic, Greeting, GreetingKind,
-        Status, StatusBody, StatusKind, Tagged,
+        Status, StatusBody, StatusKind, Tagged, Unparsed,
     },
     search::SearchKey,
     sequence::SequenceSet,
@@ -72,6 +78,14 @@ impl_arbitrary_try_from! { MailboxOther<'a>, AString<'a> }
 impl_arbitrary_try_from! { CapabilityEnable<'a>, &str }
 impl_arbitrary_try_from! { Resource<'a>, &str }
 impl_arbitrary_try_from! { AuthMechanism<'a>, &str }
+#[cfg(feature = "ext_id")]
+impl_arbitrary_try_from! { IdKey<'a>, IString<'a> }
+#[cfg(feature = "ext_id")]
+impl_arbitrary_try_from! { IdParameters<'a>, Vec<(IdKey<'a>, NString<'a>)> }
+#[cfg(feature = "ext_metadata")]
+impl_arbitrary_try_from! { Entry<'a>, AString<'a> }
+#[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
+impl_arbitrary_try_from! { ImapUrl<'a>, &str }
 impl_arbitrary_try_from_t! { Vec1<T>, Vec<T> }
 impl_arbitrary_try_from_t! { Vec2<T>, Vec<T> }
 
@@ -180,6 +194,13 @@ impl<'a> Arbitrary<'a> for CodeOther<'a> {
     }
 }
 
+impl<'a> Arbitrary<'a> for Unparsed<'a> {
+    fn arbitrary(_: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        // `Unparsed` is a fallback of lenient decoding and can't be decoded (strictly) again.
+        Err(arbitrary::Error::IncorrectFormat)
+    }
+}
+
 impl<'a> Arbitrary<'a> for SearchKey<'a> {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         #[cfg(not(feature = "arbitrary_simplified"))]
@@ -10,8 +10,10 @@ use bounded_static_derive::ToStatic;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "ext_gmail")]
+use crate::extensions::gmail::GmailLabel;
 #[cfg(feature = "ext_id")]
-use crate::core::{IString, NString};
+use crate::extensions::id::IdParameters;
 #[cfg(feature = "ext_metadata")]
 use crate::extensions::metadata::{Entry, EntryValue, GetMetadataOption};
 use crate::{
@@ -20,8 +22,13 @@ use crate::{
     core::{AString, Charset, Literal, Tag, Vec1},
     datetime::DateTime,
     extensions::{
-        binary::LiteralOrLiteral8, compress::CompressionAlgorithm, enable::CapabilityEnable,
-        quota::QuotaSet, sort::SortCriterion, thread::ThreadingAlgorithm,
+        binary::LiteralOrLiteral8,
+        compress::CompressionAlgorithm,
+        enable::CapabilityEnable,
+        multisearch::{SearchReturnOption, SourceMailboxes},
+        quota::QuotaSet,
+        sort::SortCriterion,
+        thread::ThreadingAlgorithm,
     },
     fetch::MacroOrMessageDataItemNames,
     flag::{Flag, StoreResponse, StoreType},
@@ -1116,6 +1123,45 @@ pub enum CommandBody<'a> {
         uid: bool,
     },
 
+    /// ESEARCH command.
+    ///
+    /// The ESEARCH command is a variant of SEARCH that searches multiple mailboxes at once.
+    ///
+    /// Data:
+    /// * untagged responses: ESEARCH
+    ///
+    /// Result:
+    /// * OK - search completed
+    /// * NO - search error: can't search that charset or criteria
+    /// * BAD - command unknown or arguments invalid
+    ///
+    /// # Example (IMAP)
+    ///
+    /// ```imap
+    /// C: tag1 ESEARCH IN (mailboxes "folder1" subtree-one "folder2") unseen
+    /// S: * ESEARCH (TAG "tag1" MAILBOX "folder1" UIDVALIDITY 1) UID ALL 4001,4003,4005,4007,4009
+    /// S: * ESEARCH (TAG "tag1" MAILBOX "folder2/banana" UIDVALIDITY 503) UID ALL 3001:3004,3788
+    /// S: tag1 OK done
+    /// ```
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the MULTISEARCH capability.
+    /// </div>
+    ESearch {
+        /// Mailboxes to search in.
+        ///
+        /// Note: `None` means the currently selected mailbox.
+        source: Option<Vec1<SourceMailboxes<'a>>>,
+        /// Result options.
+        ///
+        /// Note: An empty list is equivalent to `ALL`.
+        return_options: Vec<SearchReturnOption>,
+        /// Charset.
+        charset: Option<Charset<'a>>,
+        /// Criteria.
+        criteria: Vec1<SearchKey<'a>>,
+    },
+
     /// ### 6.4.5.  FETCH Command
     ///
     /// * Arguments:
@@ -1491,6 +1537,31 @@ pub enum CommandBody<'a> {
         uid: bool,
     },
 
+    #[cfg(feature = "ext_gmail")]
+    /// Alter the Gmail labels of messages (STORE with `X-GM-LABELS`).
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the X-GM-EXT-1 capability.
+    /// </div>
+    ///
+    /// ```imap
+    /// C: A003 STORE 4 +X-GM-LABELS (\Trash foo)
+    /// S: * 4 FETCH (X-GM-LABELS (\Trash foo))
+    /// S: A003 OK STORE (Success)
+    /// ```
+    StoreXGmLabels {
+        /// Set of messages.
+        sequence_set: SequenceSet,
+        /// Kind of storage, i.e., replace, add, or remove.
+        kind: StoreType,
+        /// Kind of response, i.e., answer or silent.
+        response: StoreResponse,
+        /// Labels.
+        labels: Vec<GmailLabel<'a>>,
+        /// Use UID variant.
+        uid: bool,
+    },
+
     #[cfg(feature = "ext_id")]
     /// ID command.
     ///
@@ -1499,7 +1570,9 @@ pub enum CommandBody<'a> {
     /// </div>
     Id {
         /// Parameters.
-        parameters: Option<Vec<(IString<'a>, NString<'a>)>>,
+        ///
+        /// Note: `NIL` and `()` are both represented as `None`.
+        parameters: Option<IdParameters<'a>>,
     },
 
     #[cfg(feature = "ext_metadata")]
@@ -1524,6 +1597,28 @@ pub enum CommandBody<'a> {
         mailbox: Mailbox<'a>,
         entries: Vec1<Entry<'a>>,
     },
+
+    #[cfg(feature = "ext_xlist")]
+    /// XLIST command.
+    ///
+    /// Same as LIST, but the server returns XLIST responses that include special-use attributes.
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the XLIST capability.
+    /// </div>
+    ///
+    /// ```imap
+    /// C: A001 XLIST "" "*"
+    /// S: * XLIST (\HasNoChildren \Inbox) "/" "Inbox"
+    /// S: * XLIST (\HasNoChildren \Sent) "/" "[Gmail]/Sent Mail"
+    /// S: A001 OK XLIST completed
+    /// ```
+    XList {
+        /// Reference.
+        reference: Mailbox<'a>,
+        /// Mailbox (wildcard).
+        mailbox_wildcard: ListMailbox<'a>,
+    },
 }
 
 impl<'a> CommandBody<'a> {
@@ -1788,6 +1883,7 @@ impl<'a> CommandBody<'a> {
             Self::Select { .. } => "SELECT",
             Self::Sort { .. } => "SORT",
             Self::Thread { .. } => "THREAD",
+            Self::ESearch { .. } => "ESEARCH",
             Self::Unselect => "UNSELECT",
             Self::Examine { .. } => "EXAMINE",
             Self::Create { .. } => "CREATE",
@@ -1814,12 +1910,16 @@ impl<'a> CommandBody<'a> {
             Self::GetQuotaRoot { .. } => "GETQUOTAROOT",
             Self::SetQuota { .. } => "SETQUOTA",
             Self::Move { .. } => "MOVE",
+            #[cfg(feature = "ext_gmail")]
+            Self::StoreXGmLabels { .. } => "STORE",
             #[cfg(feature = "ext_id")]
             Self::Id { .. } => "ID",
             #[cfg(feature = "ext_metadata")]
             Self::SetMetadata { .. } => "SETMETADATA",
             #[cfg(feature = "ext_metadata")]
             Self::GetMetadata { .. } => "GETMETADATA",
+            #[cfg(feature = "ext_xlist")]
+            Self::XList { .. } => "XLIST",
         }
     }
 }
@@ -0,0 +1,82 @@
+//! Gmail IMAP Extensions (X-GM-EXT-1)
+//!
+//! This extends ...
+//!
+//! * [`Capability`](crate::response::Capability) with a new variant:
+//!
+//!     - [`Capability::XGmExt1`](crate::response::Capability::XGmExt1)
+//!
+//! * [`CommandBody`] with a new variant:
+//!
+//!     - [`CommandBody::StoreXGmLabels`]
+//!
+//! * [`MessageDataItemName`](crate::fetch::MessageDataItemName) with new variants:
+//!
+//!     - [`MessageDataItemName::XGmMsgId`](crate::fetch::MessageDataItemName::XGmMsgId)
+//!     - [`MessageDataItemName::XGmThrId`](crate::fetch::MessageDataItemName::XGmThrId)
+//!     - [`MessageDataItemName::XGmLabels`](crate::fetch::MessageDataItemName::XGmLabels)
+//!
+//! * [`MessageDataItem`](crate::fetch::MessageDataItem) with new variants:
+//!
+//!     - [`MessageDataItem::XGmMsgId`](crate::fetch::MessageDataItem::XGmMsgId)
+//!     - [`MessageDataItem::XGmThrId`](crate::fetch::MessageDataItem::XGmThrId)
+//!     - [`MessageDataItem::XGmLabels`](crate::fetch::MessageDataItem::XGmLabels)
+//!
+//! * [`SearchKey`](crate::search::SearchKey) with a new variant:
+//!
+//!     - [`SearchKey::XGmRaw`](crate::search::SearchKey::XGmRaw)
+//!
+//! See <https://developers.google.com/gmail/imap/imap-extensions>.
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+use bounded_static_derive::ToStatic;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::CommandBody,
+    core::{AString, Atom},
+    flag::{StoreResponse, StoreType},
+    sequence::SequenceSet,
+};
+
+impl<'a> CommandBody<'a> {
+    /// Construct a STORE command that alters the Gmail labels of messages.
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the X-GM-EXT-1 capability.
+    /// </div>
+    pub fn store_x_gm_labels<S>(
+        sequence_set: S,
+        kind: StoreType,
+        response: StoreResponse,
+        labels: Vec<GmailLabel<'a>>,
+        uid: bool,
+    ) -> Result<Self, S::Error>
+    where
+        S: TryInto<SequenceSet>,
+    {
+        Ok(CommandBody::StoreXGmLabels {
+            sequence_set: sequence_set.try_into()?,
+            kind,
+            response,
+            labels,
+            uid,
+        })
+    }
+}
+
+/// A Gmail label.
+///
+/// Gmail exposes its system labels, e.g., `\Inbox`, `\Important`, or `\Sent`, with a leading
+/// backslash. All other labels are user-defined and sent as astring.
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub enum GmailLabel<'a> {
+    /// A system label (without the leading backslash).
+    System(Atom<'a>),
+    /// A user-defined label.
+    User(AString<'a>),
+}
@@ -0,0 +1,290 @@
+//! IMAP4 ID extension
+//!
+//! This extends ...
+//!
+//! * [`Capability`](crate::response::Capability) with a new variant:
+//!
+//!     - [`Capability::Id`](crate::response::Capability::Id)
+//!
+//! * [`CommandBody`] with a new variant:
+//!
+//!     - [`CommandBody::Id`]
+//!
+//! * [`Data`] with a new variant:
+//!
+//!     - [`Data::Id`]
+//!
+//! Note: `NIL` and an empty list `()` carry the same meaning ("no information") and are both
+//! represented as `None`. Thus, [`IdParameters`] is never empty.
+
+use bounded_static_derive::ToStatic;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::CommandBody,
+    core::{impl_try_from, IString, NString},
+    extensions::id::error::IdParametersError,
+    response::Data,
+};
+
+impl<'a> CommandBody<'a> {
+    /// Construct an ID command.
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the ID capability.
+    /// </div>
+    pub fn id(parameters: Option<IdParameters<'a>>) -> Self {
+        CommandBody::Id { parameters }
+    }
+}
+
+impl<'a> Data<'a> {
+    /// Construct an ID response.
+    pub fn id(parameters: Option<IdParameters<'a>>) -> Self {
+        Data::Id { parameters }
+    }
+}
+
+/// Field-value pairs of an ID command or response.
+///
+/// Rules (RFC 2971, section 3.3):
+///
+/// * At least one and at most 30 field-value pairs
+/// * Fields must not be longer than 30 octets
+/// * Values must not be longer than 1024 octets
+/// * Fields must not be sent more than once (fields are compared case-insensitively)
+///
+/// The rules are enforced by [`IdParameters::try_from`] for outgoing data. Received data that
+/// violates the limits is still accepted (see [`IdParameters::lenient`]), but is never empty.
+///
+/// The pairs are kept in insertion order.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<(IdKey<'a>, NString<'a>)>"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub struct IdParameters<'a>(Vec<(IdKey<'a>, NString<'a>)>);
+
+impl<'a> IdParameters<'a> {
+    /// Maximum number of field-value pairs.
+    pub const MAX_PAIRS: usize = 30;
+    /// Maximum length of a field in octets.
+    pub const MAX_KEY_LENGTH: usize = 30;
+    /// Maximum length of a value in octets.
+    pub const MAX_VALUE_LENGTH: usize = 1024;
+
+    /// Checks the rules of RFC 2971 (see [`IdParameters`]).
+    pub fn validate(value: &[(IdKey<'a>, NString<'a>)]) -> Result<(), IdParametersError> {
+        if value.is_empty() {
+            return Err(IdParametersError::Empty);
+        }
+
+        if value.len() > Self::MAX_PAIRS {
+            return Err(IdParametersError::TooManyPairs { got: value.len() });
+        }
+
+        for (index, (key, val)) in value.iter().enumerate() {
+            let key_length = key.as_ref().len();
+            if key_length > Self::MAX_KEY_LENGTH {
+                return Err(IdParametersError::KeyTooLong {
+                    index,
+                    got: key_length,
+                });
+            }
+
+            let value_length = val.0.as_ref().map(|val| val.as_ref().len()).unwrap_or(0);
+            if value_length > Self::MAX_VALUE_LENGTH {
+                return Err(IdParametersError::ValueTooLong {
+                    index,
+                    got: value_length,
+                });
+            }
+
+            if value[..index]
+                .iter()
+                .any(|(other, _)| other.as_ref().eq_ignore_ascii_case(key.as_ref()))
+            {
+                return Err(IdParametersError::DuplicateKey { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Constructs ID parameters without validation.
+    ///
+    /// # Warning: IMAP conformance
+    ///
+    /// The caller must ensure that `inner` is valid according to [`Self::validate`]. Failing to do
+    /// so may create invalid/unparsable IMAP messages, or even produce unintended protocol flows.
+    /// Do not call this constructor with untrusted data.
+    ///
+    /// Note: This method will `panic!` on wrong input in debug builds.
+    pub fn unvalidated(inner: Vec<(IdKey<'a>, NString<'a>)>) -> Self {
+        #[cfg(debug_assertions)]
+        Self::validate(&inner).unwrap();
+
+        Self(inner)
+    }
+
+    /// Constructs ID parameters that may violate the limits of RFC 2971.
+    ///
+    /// This is meant for received data, so that a slightly non-conforming peer doesn't break the
+    /// session. Only empty parameters are rejected (use `None` instead). Use
+    /// [`IdParameters::try_from`] for outgoing data.
+    pub fn lenient(inner: Vec<(IdKey<'a>, NString<'a>)>) -> Result<Self, IdParametersError> {
+        if inner.is_empty() {
+            return Err(IdParametersError::Empty);
+        }
+
+        Ok(Self(inner))
+    }
+
+    /// Returns the value of the given field (if present).
+    ///
+    /// If a (non-conforming) peer sent the field more than once, the first value is returned.
+    pub fn get(&self, key: &IdKey) -> Option<&NString<'a>> {
+        self.0
+            .iter()
+            .find(|(other, _)| other.as_ref().eq_ignore_ascii_case(key.as_ref()))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the field-value pairs (in insertion order).
+    pub fn into_inner(self) -> Vec<(IdKey<'a>, NString<'a>)> {
+        self.0
+    }
+}
+
+impl<'a> TryFrom<Vec<(IdKey<'a>, NString<'a>)>> for IdParameters<'a> {
+    type Error = IdParametersError;
+
+    fn try_from(inner: Vec<(IdKey<'a>, NString<'a>)>) -> Result<Self, Self::Error> {
+        Self::validate(&inner)?;
+
+        Ok(Self(inner))
+    }
+}
+
+impl<'a> IntoIterator for IdParameters<'a> {
+    type Item = (IdKey<'a>, NString<'a>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> AsRef<[(IdKey<'a>, NString<'a>)]> for IdParameters<'a> {
+    fn as_ref(&self) -> &[(IdKey<'a>, NString<'a>)] {
+        &self.0
+    }
+}
+
+/// Field of an ID field-value pair.
+///
+/// The well-known fields are defined in RFC 2971, section 3.3.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub enum IdKey<'a> {
+    /// Name of the program (`name`).
+    Name,
+    /// Version number of the program (`version`).
+    Version,
+    /// Name of the operating system (`os`).
+    Os,
+    /// Version of the operating system (`os-version`).
+    OsVersion,
+    /// Vendor of the client/server (`vendor`).
+    Vendor,
+    /// URL to contact for support (`support-url`).
+    SupportUrl,
+    /// Postal address of contact/vendor (`address`).
+    Address,
+    /// Date program was released (`date`).
+    Date,
+    /// Command used to start the program (`command`).
+    Command,
+    /// Arguments supplied on the command line, if any (`arguments`).
+    Arguments,
+    /// Description of environment, i.e., UNIX environment variables or Windows registry settings (`environment`).
+    Environment,
+    /// Other field.
+    Other(IdKeyOther<'a>),
+}
+
+/// An (unknown) field.
+///
+/// It's guaranteed that this type can't represent any field from [`IdKey`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub struct IdKeyOther<'a>(IString<'a>);
+
+impl<'a> IdKeyOther<'a> {
+    pub fn inner(&self) -> &IString<'a> {
+        &self.0
+    }
+}
+
+impl_try_from!(IString<'a>, 'a, &'a [u8], IdKey<'a>);
+impl_try_from!(IString<'a>, 'a, Vec<u8>, IdKey<'a>);
+impl_try_from!(IString<'a>, 'a, &'a str, IdKey<'a>);
+impl_try_from!(IString<'a>, 'a, String, IdKey<'a>);
+
+impl<'a> From<IString<'a>> for IdKey<'a> {
+    fn from(value: IString<'a>) -> Self {
+        let lowercase = value.as_ref().to_ascii_lowercase();
+
+        match lowercase.as_slice() {
+            b"name" => Self::Name,
+            b"version" => Self::Version,
+            b"os" => Self::Os,
+            b"os-version" => Self::OsVersion,
+            b"vendor" => Self::Vendor,
+            b"support-url" => Self::SupportUrl,
+            b"address" => Self::Address,
+            b"date" => Self::Date,
+            b"command" => Self::Command,
+            b"arguments" => Self::Arguments,
+            b"environment" => Self::Environment,
+            _ => Self::Other(IdKeyOther(value)),
+        }
+    }
+}
+
+impl<'a> AsRef<[u8]> for IdKey<'a> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Name => b"name",
+            Self::Version => b"version",
+            Self::Os => b"os",
+            Self::OsVersion => b"os-version",
+            Self::Vendor => b"vendor",
+            Self::SupportUrl => b"support-url",
+            Self::Address => b"address",
+            Self::Date => b"date",
+            Self::Command => b"command",
+            Self::Arguments => b"arguments",
+            Self::Environment => b"environment",
+            Self::Other(other) => other.0.as_ref(),
+        }
+    }
+}
+
+/// Error-related types.
+pub mod error {
+    use thiserror::Error;
+
+    #[derive(Clone, Debug, Eq, Error, Hash, Ord, PartialEq, PartialOrd)]
+    pub enum IdParametersError {
+        #[error("Must not be empty (use `None` instead)")]
+        Empty,
+        #[error("Must not have more than 30 field-value pairs (got {got})")]
+        TooManyPairs { got: usize },
+        #[error("Field at index {index} must not be longer than 30 octets (got {got})")]
+        KeyTooLong { index: usize, got: usize },
+        #[error("Value at index {index} must not be longer than 1024 octets (got {got})")]
+        ValueTooLong { index: usize, got: usize },
+        #[error("Field at index {index} was already used")]
+        DuplicateKey { index: usize },
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 #[cfg(feature = "arbitrary")]
 use arbitrary::Arbitrary;
 use bounded_static_derive::ToStatic;
@@ -5,8 +7,8 @@ use bounded_static_derive::ToStatic;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::{AString, NString8, Vec1},
-    error::ValidationError,
+    core::{AString, AtomExt, NString8, Vec1},
+    error::{ValidationError, ValidationErrorKind},
 };
 
 #[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
@@ -18,27 +20,179 @@ pub struct EntryValue<'a> {
 }
 
 /// Slash-separated path to entry.
-#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+///
+/// Rules (RFC 5464, section 3.2):
+///
+/// * Must start with `/private` or `/shared` (compared case-insensitively)
+/// * Must not contain two consecutive slashes (`//`)
+/// * Must not end with a slash
+/// * Must not contain `*`, `%`, or control characters
+///
+/// The rules are enforced by the constructors (see [`Entry::validate`]). Received entries that
+/// violate the rules are still accepted (see [`Entry::lenient`]).
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "AString<'a>"))]
 #[derive(Clone, Debug, Eq, Hash, PartialEq, ToStatic)]
 pub struct Entry<'a>(AString<'a>);
 
 impl<'a> Entry<'a> {
+    /// Checks the rules of RFC 5464 (see [`Entry`]).
+    pub fn validate(value: impl AsRef<[u8]>) -> Result<(), ValidationError> {
+        let value = value.as_ref();
+
+        let rest = if let Some(rest) = strip_prefix_ignore_ascii_case(value, b"/private") {
+            rest
+        } else if let Some(rest) = strip_prefix_ignore_ascii_case(value, b"/shared") {
+            rest
+        } else {
+            return Err(ValidationError::new(ValidationErrorKind::Invalid));
+        };
+
+        if !(rest.is_empty() || rest.starts_with(b"/")) {
+            return Err(ValidationError::new(ValidationErrorKind::Invalid));
+        }
+
+        if let Some(at) = value
+            .iter()
+            .position(|b| matches!(b, b'*' | b'%' | 0x00..=0x1f | 0x7f))
+        {
+            return Err(ValidationError::new(ValidationErrorKind::InvalidByteAt {
+                byte: value[at],
+                at,
+            }));
+        }
+
+        if value.windows(2).any(|window| window == b"//") || value.ends_with(b"/") {
+            return Err(ValidationError::new(ValidationErrorKind::Invalid));
+        }
+
+        Ok(())
+    }
+
+    /// Constructs an entry without validation.
+    ///
+    /// # Warning: IMAP conformance
+    ///
+    /// The caller must ensure that `inner` is valid according to [`Self::validate`]. Failing to do
+    /// so may create invalid/unparsable IMAP messages, or even produce unintended protocol flows.
+    /// Do not call this constructor with untrusted data.
+    ///
+    /// Note: This method will `panic!` on wrong input in debug builds.
+    pub fn unvalidated(inner: AString<'a>) -> Self {
+        #[cfg(debug_assertions)]
+        Self::validate(inner.as_ref()).unwrap();
+
+        Self(inner)
+    }
+
+    /// Constructs an entry that may violate the rules of RFC 5464.
+    ///
+    /// This is meant for received data, so that a single non-conforming entry doesn't break a
+    /// whole `METADATA` response. Use [`Entry::try_from`] for outgoing data.
+    pub fn lenient(inner: AString<'a>) -> Self {
+        Self(inner)
+    }
+
     pub fn inner(&self) -> &AString<'a> {
         &self.0
     }
+
+    /// `/private/comment`
+    pub fn private_comment() -> Entry<'static> {
+        Entry::unvalidated(AString::Atom(AtomExt::unvalidated("/private/comment")))
+    }
+
+    /// `/shared/comment`
+    pub fn shared_comment() -> Entry<'static> {
+        Entry::unvalidated(AString::Atom(AtomExt::unvalidated("/shared/comment")))
+    }
+
+    /// `/shared/admin`
+    ///
+    /// Note: This entry is only defined for the server (i.e., with an empty mailbox name).
+    pub fn shared_admin() -> Entry<'static> {
+        Entry::unvalidated(AString::Atom(AtomExt::unvalidated("/shared/admin")))
+    }
+
+    /// `/private/vendor/<vendor>/<path>`
+    ///
+    /// `vendor` must be a single path segment, `path` may contain (single) slashes.
+    pub fn private_vendor(vendor: &str, path: &str) -> Result<Entry<'static>, ValidationError> {
+        Self::vendor("/private", vendor, path)
+    }
+
+    /// `/shared/vendor/<vendor>/<path>`
+    ///
+    /// `vendor` must be a single path segment, `path` may contain (single) slashes.
+    pub fn shared_vendor(vendor: &str, path: &str) -> Result<Entry<'static>, ValidationError> {
+        Self::vendor("/shared", vendor, path)
+    }
+
+    fn vendor(prefix: &str, vendor: &str, path: &str) -> Result<Entry<'static>, ValidationError> {
+        if vendor.contains('/') {
+            return Err(ValidationError::new(ValidationErrorKind::Invalid));
+        }
+
+        Entry::try_from(format!("{prefix}/vendor/{vendor}/{path}"))
+    }
+
+    /// Returns `true` if the entry is in the `/private` namespace.
+    pub fn is_private(&self) -> bool {
+        strip_prefix_ignore_ascii_case(self.as_ref(), b"/private")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(b"/"))
+    }
+
+    /// Returns `true` if the entry is in the `/shared` namespace.
+    pub fn is_shared(&self) -> bool {
+        strip_prefix_ignore_ascii_case(self.as_ref(), b"/shared")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(b"/"))
+    }
+
+    /// Returns the path segments of the entry, e.g., `["shared", "vendor", "foo"]` for
+    /// `/shared/vendor/foo`.
+    ///
+    /// Note: Empty segments of a (non-conforming) received entry are skipped.
+    pub fn segments(&self) -> impl Iterator<Item = &[u8]> {
+        self.as_ref()
+            .split(|b| *b == b'/')
+            .filter(|segment| !segment.is_empty())
+    }
+}
+
+fn strip_prefix_ignore_ascii_case<'v>(value: &'v [u8], prefix: &[u8]) -> Option<&'v [u8]> {
+    match value.split_at_checked(prefix.len()) {
+        Some((head, rest)) if head.eq_ignore_ascii_case(prefix) => Some(rest),
+        _ => None,
+    }
 }
 
 impl<'a> TryFrom<AString<'a>> for Entry<'a> {
     type Error = ValidationError;
 
     fn try_from(value: AString<'a>) -> Result<Self, Self::Error> {
-        // TODO(#449): Currently, we do no validation. Let's gather more
-        //             practical experience before settling on a representation.
+        Self::validate(value.as_ref())?;
+
         Ok(Self(value))
     }
 }
 
+macro_rules! impl_try_from_entry {
+    ($from:ty) => {
+        impl<'a> TryFrom<$from> for Entry<'a> {
+            type Error = ValidationError;
+
+            fn try_from(value: $from) -> Result<Self, Self::Error> {
+                Self::try_from(AString::try_from(value)?)
+            }
+        }
+    };
+}
+
+impl_try_from_entry!(&'a [u8]);
+impl_try_from_entry!(Vec<u8>);
+impl_try_from_entry!(&'a str);
+impl_try_from_entry!(String);
+
 impl AsRef<[u8]> for Entry<'_> {
     fn as_ref(&self) -> &[u8] {
         self.0.as_ref()
@@ -90,3 +244,252 @@ pub enum MetadataResponse<'a> {
     WithValues(Vec1<EntryValue<'a>>),
     WithoutValues(Vec1<Entry<'a>>),
 }
+
+/// Nested view of metadata entries.
+///
+/// A [`MetadataTree`] folds the [`MetadataResponse`]s of a `GETMETADATA` command into a tree
+/// whose nodes are the path segments of the entries, e.g., `/shared/vendor/foo` is stored
+/// under `shared` -> `vendor` -> `foo`.
+///
+/// Entries that are not covered by the requested entries and [`Depth`] are ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetadataTree<'a> {
+    requested: Vec<Entry<'a>>,
+    depth: Depth,
+    root: MetadataNode<'a>,
+}
+
+impl<'a> MetadataTree<'a> {
+    /// Create an (empty) tree for a `GETMETADATA` command with the given entries and depth.
+    ///
+    /// Use [`Depth::Null`] when the command had no `DEPTH` option.
+    pub fn new(requested: Vec<Entry<'a>>, depth: Depth) -> Self {
+        Self {
+            requested,
+            depth,
+            root: MetadataNode::default(),
+        }
+    }
+
+    /// Returns `true` if the entry is covered by the requested entries and depth.
+    pub fn covers(&self, entry: &Entry) -> bool {
+        let segments: Vec<&[u8]> = entry.segments().collect();
+
+        self.requested.iter().any(|requested| {
+            let prefix: Vec<&[u8]> = requested.segments().collect();
+
+            if !segments.starts_with(&prefix) {
+                return false;
+            }
+
+            match self.depth {
+                Depth::Null => segments.len() == prefix.len(),
+                Depth::One => segments.len() <= prefix.len() + 1,
+                Depth::Infinity => true,
+            }
+        })
+    }
+
+    /// Insert a single entry value.
+    ///
+    /// Returns `false` (and ignores the value) when the entry is not covered.
+    pub fn insert(&mut self, entry: &Entry, value: NString8<'a>) -> bool {
+        if !self.covers(entry) {
+            return false;
+        }
+
+        self.root.node_mut(entry).value = Some(value);
+
+        true
+    }
+
+    /// Fold a `METADATA` response into the tree.
+    ///
+    /// Entries without values (unsolicited `METADATA` responses) create a node but leave its
+    /// value untouched.
+    pub fn fold(&mut self, response: MetadataResponse<'a>) {
+        match response {
+            MetadataResponse::WithValues(entry_values) => {
+                for EntryValue { entry, value } in entry_values.into_inner() {
+                    self.insert(&entry, value);
+                }
+            }
+            MetadataResponse::WithoutValues(entries) => {
+                for entry in entries.into_inner() {
+                    if self.covers(&entry) {
+                        self.root.node_mut(&entry);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the node of the given entry (if present).
+    pub fn node(&self, entry: &Entry) -> Option<&MetadataNode<'a>> {
+        entry
+            .segments()
+            .try_fold(&self.root, |node, segment| node.child(segment))
+    }
+
+    /// Returns the value of the given entry (if present).
+    pub fn get(&self, entry: &Entry) -> Option<&NString8<'a>> {
+        self.node(entry).and_then(MetadataNode::value)
+    }
+
+    /// Returns the root node, i.e., the parent of `private` and `shared`.
+    pub fn root(&self) -> &MetadataNode<'a> {
+        &self.root
+    }
+}
+
+/// A node in a [`MetadataTree`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MetadataNode<'a> {
+    value: Option<NString8<'a>>,
+    children: BTreeMap<Vec<u8>, MetadataNode<'a>>,
+}
+
+impl<'a> MetadataNode<'a> {
+    /// Returns the value of this node (if known).
+    pub fn value(&self) -> Option<&NString8<'a>> {
+        self.value.as_ref()
+    }
+
+    /// Returns the child with the given path segment (if present).
+    pub fn child(&self, segment: &[u8]) -> Option<&MetadataNode<'a>> {
+        self.children.get(segment)
+    }
+
+    /// Returns all children ordered by their path segment.
+    pub fn children(&self) -> impl Iterator<Item = (&[u8], &MetadataNode<'a>)> {
+        self.children
+            .iter()
+            .map(|(segment, node)| (segment.as_slice(), node))
+    }
+
+    fn node_mut(&mut self, entry: &Entry) -> &mut MetadataNode<'a> {
+        entry.segments().fold(self, |node, segment| {
+            node.children.entry(segment.to_vec()).or_default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::NString;
+
+    #[test]
+    fn test_conversion_entry() {
+        for valid in [
+            "/private",
+            "/shared",
+            "/private/comment",
+            "/shared/vendor/vendor.example/foo",
+            "/Shared/comment",
+            "/PRIVATE",
+        ] {
+            assert!(Entry::try_from(valid).is_ok(), "{valid}");
+        }
+
+        for invalid in [
+            "",
+            "/",
+            "comment",
+            "/test",
+            "/privatefoo",
+            "/shared/",
+            "/shared//comment",
+            "/shared/comment/",
+            "/shared/*",
+            "/private/%",
+            "/private/a\x7fb",
+        ] {
+            assert!(Entry::try_from(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_entry_helpers() {
+        assert_eq!(
+            Entry::private_comment(),
+            Entry::try_from("/private/comment").unwrap()
+        );
+        assert_eq!(
+            Entry::shared_vendor("vendor.example", "a/b").unwrap(),
+            Entry::try_from("/shared/vendor/vendor.example/a/b").unwrap()
+        );
+        assert!(Entry::shared_vendor("vendor/example", "a").is_err());
+        assert!(Entry::private_vendor("vendor.example", "").is_err());
+
+        let entry = Entry::shared_vendor("vendor.example", "a").unwrap();
+        assert!(entry.is_shared());
+        assert!(!entry.is_private());
+        assert!(Entry::try_from("/Shared/comment").unwrap().is_shared());
+        assert_eq!(
+            entry.segments().collect::<Vec<_>>(),
+            vec![&b"shared"[..], b"vendor", b"vendor.example", b"a"]
+        );
+    }
+
+    #[test]
+    fn test_metadata_tree() {
+        let value = |v: &'static str| NString8::NString(NString::try_from(v).unwrap());
+        let response = MetadataResponse::WithValues(
+            Vec1::try_from(vec![
+                EntryValue {
+                    entry: Entry::try_from("/shared/vendor").unwrap(),
+                    value: value("a"),
+                },
+                EntryValue {
+                    entry: Entry::try_from("/shared/vendor/x").unwrap(),
+                    value: value("b"),
+                },
+                EntryValue {
+                    entry: Entry::try_from("/shared/vendor/x/y").unwrap(),
+                    value: value("c"),
+                },
+                EntryValue {
+                    entry: Entry::private_comment(),
+                    value: value("d"),
+                },
+            ])
+            .unwrap(),
+        );
+
+        let requested = vec![Entry::try_from("/shared/vendor").unwrap()];
+
+        let mut tree = MetadataTree::new(requested.clone(), Depth::Null);
+        tree.fold(response.clone());
+        assert_eq!(
+            tree.get(&Entry::try_from("/shared/vendor").unwrap()),
+            Some(&value("a"))
+        );
+        assert!(tree
+            .node(&Entry::try_from("/shared/vendor/x").unwrap())
+            .is_none());
+
+        let mut tree = MetadataTree::new(requested.clone(), Depth::One);
+        tree.fold(response.clone());
+        assert_eq!(
+            tree.get(&Entry::try_from("/shared/vendor/x").unwrap()),
+            Some(&value("b"))
+        );
+        assert!(tree
+            .node(&Entry::try_from("/shared/vendor/x/y").unwrap())
+            .is_none());
+
+        let mut tree = MetadataTree::new(requested, Depth::Infinity);
+        tree.fold(response);
+        let vendor = tree
+            .node(&Entry::try_from("/shared/vendor").unwrap())
+            .unwrap();
+        assert_eq!(vendor.children().count(), 1);
+        assert_eq!(
+            vendor.child(b"x").unwrap().child(b"y").unwrap().value(),
+            Some(&value("c"))
+        );
+        assert!(tree.get(&Entry::private_comment()).is_none());
+        assert_eq!(tree.root().children().count(), 1);
+    }
+}
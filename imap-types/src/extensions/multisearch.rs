@@ -0,0 +1,137 @@
+//! IMAP MULTISEARCH Extension
+//!
+//! This extends ...
+//!
+//! * [`Capability`](crate::response::Capability) with a new variant:
+//!
+//!     - [`Capability::MultiSearch`](crate::response::Capability::MultiSearch)
+//!
+//! * [`CommandBody`] with a new variant:
+//!
+//!     - [`CommandBody::ESearch`]
+//!
+//! * [`Data`] with a new variant:
+//!
+//!     - [`Data::ESearch`]
+//!
+//! The ESEARCH response (RFC 4731) is extended with the `MAILBOX` and `UIDVALIDITY` correlators
+//! (see [`SearchCorrelator`]), so that results of a multi-mailbox search can be attributed to a
+//! mailbox.
+
+use std::num::NonZeroU32;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+use bounded_static_derive::ToStatic;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::CommandBody,
+    core::{Charset, Tag, Vec1},
+    mailbox::Mailbox,
+    response::Data,
+    search::SearchKey,
+    sequence::SequenceSet,
+};
+
+impl<'a> CommandBody<'a> {
+    /// Construct an ESEARCH command.
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the MULTISEARCH capability.
+    /// </div>
+    pub fn esearch(
+        source: Option<Vec1<SourceMailboxes<'a>>>,
+        return_options: Vec<SearchReturnOption>,
+        charset: Option<Charset<'a>>,
+        criteria: Vec1<SearchKey<'a>>,
+    ) -> Self {
+        CommandBody::ESearch {
+            source,
+            return_options,
+            charset,
+            criteria,
+        }
+    }
+}
+
+impl<'a> Data<'a> {
+    /// Construct an ESEARCH response.
+    pub fn esearch(
+        correlator: Option<SearchCorrelator<'a>>,
+        uid: bool,
+        data: Vec<SearchReturnData>,
+    ) -> Self {
+        Self::ESearch {
+            correlator,
+            uid,
+            data,
+        }
+    }
+}
+
+/// Set of mailboxes to search in.
+///
+/// Note: The mailboxes are given "as is", i.e., without applying the reference name
+/// or wildcards as done in LIST.
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub enum SourceMailboxes<'a> {
+    /// All selectable mailboxes for which the user is considered the owner.
+    Personal,
+    /// All mailboxes that receive messages addressed to the user, e.g., INBOX.
+    Inboxes,
+    /// All mailboxes subscribed to by the user.
+    Subscribed,
+    /// The specified mailboxes and all selectable mailboxes that are subordinate to them.
+    Subtree(Vec1<Mailbox<'a>>),
+    /// The specified mailboxes and all selectable child mailboxes, one hierarchy level down.
+    SubtreeOne(Vec1<Mailbox<'a>>),
+    /// Exactly the specified mailboxes.
+    Mailboxes(Vec1<Mailbox<'a>>),
+}
+
+/// Result option for an extended search.
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ToStatic)]
+pub enum SearchReturnOption {
+    /// Return the lowest message number/UID that satisfies the criteria.
+    Min,
+    /// Return the highest message number/UID that satisfies the criteria.
+    Max,
+    /// Return all message numbers/UIDs that satisfy the criteria.
+    All,
+    /// Return the number of messages that satisfy the criteria.
+    Count,
+}
+
+/// Correlates an ESEARCH response with the command (and mailbox) it belongs to.
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub struct SearchCorrelator<'a> {
+    /// Tag of the command that caused the response.
+    pub tag: Tag<'a>,
+    /// Mailbox (and its UIDVALIDITY) the results belong to.
+    ///
+    /// Note: This is always present in responses to a [`CommandBody::ESearch`].
+    pub mailbox: Option<(Mailbox<'a>, NonZeroU32)>,
+}
+
+/// Result of an extended search.
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub enum SearchReturnData {
+    /// Lowest message number/UID that satisfies the criteria.
+    Min(NonZeroU32),
+    /// Highest message number/UID that satisfies the criteria.
+    Max(NonZeroU32),
+    /// All message numbers/UIDs that satisfy the criteria.
+    All(SequenceSet),
+    /// Number of messages that satisfy the criteria.
+    Count(u32),
+}
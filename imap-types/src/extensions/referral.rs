@@ -0,0 +1,623 @@
+//! IMAP4 Login Referrals and IMAP4 Mailbox Referrals
+//!
+//! This extends ...
+//!
+//! * [`Code`](crate::response::Code) with a new variant:
+//!
+//!     - [`Code::Referral`](crate::response::Code::Referral)
+//!
+//! A referral points to another server (and, optionally, to a mailbox on this server) using an
+//! IMAP URL ([RFC 5092](https://datatracker.ietf.org/doc/html/rfc5092)), e.g., ...
+//!
+//! ```text
+//! S: A001 NO [REFERRAL imap://user;AUTH=*@SERVER2/] Specified user is invalid on this server. Try SERVER2.
+//! S: A002 NO [REFERRAL imap://user@SERVER2/SHARED/FOO] Remote mailbox. Try SERVER2.
+//! ```
+//!
+//! [`ImapUrl`] only supports the parts required for referrals, i.e., server (user, authentication
+//! mechanism, host, port) and mailbox. URLs referring to messages or searches are rejected.
+//!
+//! The mailbox name in the URL is UTF-8 (percent-encoded) and converted from and to modified UTF-7
+//! as used by IMAP, e.g., `imap://SERVER2/Entw%C3%BCrfe` refers to the mailbox `Entw&APw-rfe`.
+
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter},
+};
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+use base64::{
+    alphabet::IMAP_MUTF7,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use bounded_static::IntoBoundedStatic;
+use bounded_static_derive::ToStatic;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{auth::AuthMechanism, extensions::referral::error::ImapUrlError, mailbox::Mailbox};
+
+/// An IMAP URL as used in referrals.
+///
+/// ```abnf
+/// imapurl   = "imap://" iserver "/" [enc-mailbox]
+///
+/// iserver   = [iuserinfo "@"] host [":" port]
+///
+/// iuserinfo = enc-user [iauth] / [enc-user] iauth
+///
+/// iauth     = ";AUTH=" ( "*" / enc-auth-type )
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub struct ImapUrl<'a> {
+    host: Cow<'a, str>,
+    port: Option<u16>,
+    user: Option<Cow<'a, str>>,
+    auth: Option<UrlAuth<'a>>,
+    mailbox: Option<Mailbox<'a>>,
+}
+
+/// Authentication mechanism in an IMAP URL.
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ToStatic)]
+pub enum UrlAuth<'a> {
+    /// Any mechanism the client supports (`;AUTH=*`).
+    Any,
+    /// A specific mechanism.
+    Mechanism(AuthMechanism<'a>),
+}
+
+impl<'a> ImapUrl<'a> {
+    pub fn new<H>(
+        host: H,
+        port: Option<u16>,
+        user: Option<Cow<'a, str>>,
+        auth: Option<UrlAuth<'a>>,
+        mailbox: Option<Mailbox<'a>>,
+    ) -> Result<Self, ImapUrlError>
+    where
+        H: Into<Cow<'a, str>>,
+    {
+        let host = host.into();
+
+        if !is_host(&host) {
+            return Err(ImapUrlError::Host);
+        }
+
+        if matches!(&user, Some(user) if user.is_empty()) {
+            return Err(ImapUrlError::User);
+        }
+
+        if let Some(Mailbox::Other(other)) = &mailbox {
+            if decode_modified_utf7(other.as_ref()).is_none() {
+                return Err(ImapUrlError::Mailbox);
+            }
+        }
+
+        Ok(Self {
+            host,
+            port,
+            user,
+            auth,
+            mailbox,
+        })
+    }
+
+    /// Returns the host, i.e., a registered name, an IPv4 address, or an IP literal in brackets.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Returns the (percent-decoded) user.
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    pub fn auth(&self) -> Option<&UrlAuth<'a>> {
+        self.auth.as_ref()
+    }
+
+    /// Returns the mailbox (percent-decoded and converted to modified UTF-7).
+    ///
+    /// This is `None` for login referrals.
+    pub fn mailbox(&self) -> Option<&Mailbox<'a>> {
+        self.mailbox.as_ref()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ImapUrl<'a> {
+    type Error = ImapUrlError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let rest = match value.get(..7) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("imap://") => &value[7..],
+            _ => return Err(ImapUrlError::Scheme),
+        };
+
+        let (server, path) = match rest.split_once('/') {
+            Some((server, path)) => (server, Some(path)),
+            None => (rest, None),
+        };
+
+        let (userinfo, hostport) = match server.split_once('@') {
+            Some((userinfo, hostport)) => (Some(userinfo), hostport),
+            None => (None, server),
+        };
+
+        let (user, auth) = match userinfo {
+            Some(userinfo) => {
+                let (user, auth) = match userinfo.find(';') {
+                    Some(at) => {
+                        let (user, iauth) = userinfo.split_at(at);
+
+                        match iauth.get(..6) {
+                            Some(prefix) if prefix.eq_ignore_ascii_case(";AUTH=") => {
+                                (user, Some(&iauth[6..]))
+                            }
+                            _ => return Err(ImapUrlError::Auth),
+                        }
+                    }
+                    None => (userinfo, None),
+                };
+
+                let user = match user {
+                    "" if auth.is_some() => None,
+                    user => Some(Cow::Owned(
+                        percent_decode(user, is_achar).ok_or(ImapUrlError::User)?,
+                    )),
+                };
+
+                let auth = match auth {
+                    Some("*") => Some(UrlAuth::Any),
+                    Some(auth) => {
+                        let mechanism = percent_decode(auth, is_achar)
+                            .and_then(|auth| AuthMechanism::try_from(auth).ok())
+                            .ok_or(ImapUrlError::Auth)?;
+
+                        Some(UrlAuth::Mechanism(mechanism))
+                    }
+                    None => None,
+                };
+
+                (user, auth)
+            }
+            None => (None, None),
+        };
+
+        let (host, port) = {
+            // An IP literal contains colons, so we search for the port after it.
+            let end = if hostport.starts_with('[') {
+                hostport
+                    .find(']')
+                    .map(|at| at + 1)
+                    .unwrap_or(hostport.len())
+            } else {
+                0
+            };
+
+            match hostport[end..].find(':') {
+                Some(at) => {
+                    let (host, port) = hostport.split_at(end + at);
+                    let port = &port[1..];
+
+                    if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(ImapUrlError::Port);
+                    }
+
+                    (host, Some(port.parse().map_err(|_| ImapUrlError::Port)?))
+                }
+                None => (hostport, None),
+            }
+        };
+
+        let mailbox = match path {
+            Some(path) if !path.is_empty() => {
+                let mailbox = percent_decode(path, is_bchar).ok_or(ImapUrlError::Mailbox)?;
+                let mailbox = encode_modified_utf7(&mailbox);
+
+                Some(Mailbox::try_from(mailbox).map_err(|_| ImapUrlError::Mailbox)?)
+            }
+            _ => None,
+        };
+
+        Self::new(host, port, user, auth, mailbox)
+    }
+}
+
+impl TryFrom<String> for ImapUrl<'static> {
+    type Error = ImapUrlError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let url = ImapUrl::try_from(value.as_str())?;
+
+        Ok(url.into_static())
+    }
+}
+
+impl<'a> Display for ImapUrl<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("imap://")?;
+
+        if let Some(user) = &self.user {
+            f.write_str(&percent_encode(user.as_bytes(), is_achar))?;
+        }
+
+        match &self.auth {
+            Some(UrlAuth::Any) => f.write_str(";AUTH=*")?,
+            Some(UrlAuth::Mechanism(mechanism)) => {
+                f.write_str(";AUTH=")?;
+                f.write_str(&percent_encode(mechanism.as_ref().as_bytes(), is_achar))?;
+            }
+            None => {}
+        }
+
+        if self.user.is_some() || self.auth.is_some() {
+            f.write_str("@")?;
+        }
+
+        f.write_str(&self.host)?;
+
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+
+        f.write_str("/")?;
+
+        match &self.mailbox {
+            Some(Mailbox::Inbox) => f.write_str("INBOX"),
+            Some(Mailbox::Other(other)) => {
+                // Note: The mailbox was checked in `ImapUrl::new`.
+                let mailbox = decode_modified_utf7(other.as_ref()).ok_or(std::fmt::Error)?;
+
+                f.write_str(&percent_encode(mailbox.as_bytes(), is_bchar))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> From<ImapUrl<'a>> for String {
+    fn from(value: ImapUrl<'a>) -> Self {
+        value.to_string()
+    }
+}
+
+/// ```abnf
+/// unreserved = ALPHA / DIGIT / "-" / "." / "_" / "~"
+/// ```
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// ```abnf
+/// achar         = uchar / "&" / "=" / "~"
+///
+/// uchar         = unreserved / pct-encoded / sub-delims-sh
+///
+/// sub-delims-sh = "!" / "$" / "'" / "(" / ")" / "*" / "+" / ","
+/// ```
+///
+/// Note: `pct-encoded` is handled by [`percent_decode`].
+fn is_achar(byte: u8) -> bool {
+    is_unreserved(byte)
+        || matches!(
+            byte,
+            b'!' | b'$' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b'&' | b'='
+        )
+}
+
+/// ```abnf
+/// bchar = achar / ":" / "@" / "/"
+/// ```
+fn is_bchar(byte: u8) -> bool {
+    is_achar(byte) || matches!(byte, b':' | b'@' | b'/')
+}
+
+/// ```abnf
+/// host     = IP-literal / IPv4address / reg-name
+///
+/// reg-name = *( unreserved / pct-encoded / sub-delims )
+/// ```
+fn is_host(host: &str) -> bool {
+    if let Some(literal) = host
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return !literal.is_empty()
+            && literal
+                .bytes()
+                .all(|b| b.is_ascii_hexdigit() || matches!(b, b':' | b'.'));
+    }
+
+    !host.is_empty()
+        && percent_decode(host, |b| {
+            is_unreserved(b)
+                || matches!(
+                    b,
+                    b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+                )
+        })
+        .is_some()
+}
+
+fn percent_decode(value: &str, allowed: fn(u8) -> bool) -> Option<String> {
+    let value = value.as_bytes();
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut index = 0;
+
+    while index < value.len() {
+        match value[index] {
+            b'%' => {
+                let hex = value.get(index + 1..index + 3)?;
+                let hex = std::str::from_utf8(hex).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                index += 3;
+            }
+            byte if allowed(byte) => {
+                decoded.push(byte);
+                index += 1;
+            }
+            _ => return None,
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+fn percent_encode(value: &[u8], allowed: fn(u8) -> bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value {
+        if allowed(*byte) {
+            encoded.push(*byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+/// Base64 variant of modified UTF-7, i.e., `,` instead of `/` and no padding.
+const MODIFIED_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &IMAP_MUTF7,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::RequireNone),
+);
+
+/// Converts a UTF-8 mailbox name into modified UTF-7.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc3501#section-5.1.3>.
+fn encode_modified_utf7(value: &str) -> String {
+    fn flush(encoded: &mut String, pending: &mut Vec<u8>) {
+        if !pending.is_empty() {
+            encoded.push('&');
+            encoded.push_str(&MODIFIED_BASE64.encode(&pending));
+            encoded.push('-');
+            pending.clear();
+        }
+    }
+
+    let mut encoded = String::with_capacity(value.len());
+    // UTF-16BE of the characters that are not printable US-ASCII.
+    let mut pending = Vec::new();
+
+    for c in value.chars() {
+        match c {
+            ' '..='~' => {
+                flush(&mut encoded, &mut pending);
+
+                match c {
+                    '&' => encoded.push_str("&-"),
+                    c => encoded.push(c),
+                }
+            }
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    pending.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    flush(&mut encoded, &mut pending);
+
+    encoded
+}
+
+/// Converts a modified UTF-7 mailbox name into UTF-8.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc3501#section-5.1.3>.
+fn decode_modified_utf7(value: &[u8]) -> Option<String> {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some((byte, tail)) = rest.split_first() {
+        match byte {
+            b'&' => {
+                let end = tail.iter().position(|b| *b == b'-')?;
+
+                if end == 0 {
+                    decoded.push('&');
+                } else {
+                    let utf16 = MODIFIED_BASE64.decode(&tail[..end]).ok()?;
+
+                    if utf16.len() % 2 != 0 {
+                        return None;
+                    }
+
+                    let units = utf16
+                        .as_chunks::<2>()
+                        .0
+                        .iter()
+                        .map(|unit| u16::from_be_bytes(*unit));
+
+                    for c in char::decode_utf16(units) {
+                        decoded.push(c.ok()?);
+                    }
+                }
+
+                rest = &tail[end + 1..];
+            }
+            b' '..=b'~' => {
+                decoded.push(*byte as char);
+                rest = tail;
+            }
+            _ => return None,
+        }
+    }
+
+    Some(decoded)
+}
+
+/// Error-related types.
+pub mod error {
+    use thiserror::Error;
+
+    #[derive(Clone, Debug, Eq, Error, Hash, Ord, PartialEq, PartialOrd)]
+    pub enum ImapUrlError {
+        #[error("Must start with `imap://`")]
+        Scheme,
+        #[error("Invalid host")]
+        Host,
+        #[error("Invalid port")]
+        Port,
+        #[error("Invalid user")]
+        User,
+        #[error("Invalid authentication mechanism")]
+        Auth,
+        #[error("Invalid or unsupported mailbox path")]
+        Mailbox,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_imap_url() {
+        let tests = [
+            (
+                "imap://user;AUTH=*@SERVER2/",
+                ImapUrl::new(
+                    "SERVER2",
+                    None,
+                    Some("user".into()),
+                    Some(UrlAuth::Any),
+                    None,
+                )
+                .unwrap(),
+            ),
+            (
+                "imap://user@SERVER2/SHARED/FOO",
+                ImapUrl::new(
+                    "SERVER2",
+                    None,
+                    Some("user".into()),
+                    None,
+                    Some(Mailbox::try_from("SHARED/FOO").unwrap()),
+                )
+                .unwrap(),
+            ),
+            (
+                "imap://;AUTH=GSSAPI@[::1]:1143/INBOX",
+                ImapUrl::new(
+                    "[::1]",
+                    Some(1143),
+                    None,
+                    Some(UrlAuth::Mechanism(
+                        AuthMechanism::try_from("GSSAPI").unwrap(),
+                    )),
+                    Some(Mailbox::Inbox),
+                )
+                .unwrap(),
+            ),
+            (
+                "imap://fred%40example.com@imap.example.org:993/%5BGmail%5D/Sent%20Mail",
+                ImapUrl::new(
+                    "imap.example.org",
+                    Some(993),
+                    Some("fred@example.com".into()),
+                    None,
+                    Some(Mailbox::try_from("[Gmail]/Sent Mail").unwrap()),
+                )
+                .unwrap(),
+            ),
+            (
+                "imap://SERVER2/Entw%C3%BCrfe",
+                ImapUrl::new(
+                    "SERVER2",
+                    None,
+                    None,
+                    None,
+                    Some(Mailbox::try_from("Entw&APw-rfe").unwrap()),
+                )
+                .unwrap(),
+            ),
+            (
+                "imap://SERVER2/Tom%20&%20Jerry/%E5%8F%B0%E5%8C%97",
+                ImapUrl::new(
+                    "SERVER2",
+                    None,
+                    None,
+                    None,
+                    Some(Mailbox::try_from("Tom &- Jerry/&U,BTFw-").unwrap()),
+                )
+                .unwrap(),
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let got = ImapUrl::try_from(test).unwrap();
+            assert_eq!(expected, got);
+            assert_eq!(test, got.to_string());
+        }
+    }
+
+    #[test]
+    fn test_conversion_imap_url_failing() {
+        let tests = [
+            ("http://SERVER2/", ImapUrlError::Scheme),
+            ("imap://", ImapUrlError::Host),
+            ("imap:///INBOX", ImapUrlError::Host),
+            ("imap://SERVER2:/", ImapUrlError::Port),
+            ("imap://SERVER2:99999/", ImapUrlError::Port),
+            ("imap://@SERVER2/", ImapUrlError::User),
+            ("imap://user;UID=1@SERVER2/", ImapUrlError::Auth),
+            ("imap://user;AUTH=@SERVER2/", ImapUrlError::Auth),
+            ("imap://SERVER2/INBOX;UID=1", ImapUrlError::Mailbox),
+            ("imap://SERVER2/INBOX?SUBJECT%20test", ImapUrlError::Mailbox),
+            ("imap://SERVER2/%ZZ", ImapUrlError::Mailbox),
+        ];
+
+        for (test, expected) in tests {
+            assert_eq!(Err(expected), ImapUrl::try_from(test), "{test}");
+        }
+
+        // Not modified UTF-7.
+        for mailbox in ["Entw\u{fc}rfe", "Entw&APw", "&Jjo!-"] {
+            assert_eq!(
+                Err(ImapUrlError::Mailbox),
+                ImapUrl::new(
+                    "SERVER2",
+                    None,
+                    None,
+                    None,
+                    Some(Mailbox::try_from(mailbox).unwrap()),
+                ),
+                "{mailbox}"
+            );
+        }
+    }
+}
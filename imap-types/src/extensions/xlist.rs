@@ -0,0 +1,69 @@
+//! The (legacy) IMAP XLIST command
+//!
+//! XLIST predates the SPECIAL-USE extension ([RFC 6154](https://datatracker.ietf.org/doc/html/rfc6154))
+//! and is still the only way to discover special-use mailboxes on some servers.
+//!
+//! This extends ...
+//!
+//! * [`Capability`](crate::response::Capability) with a new variant:
+//!
+//!     - [`Capability::XList`](crate::response::Capability::XList)
+//!
+//! * [`CommandBody`] with a new variant:
+//!
+//!     - [`CommandBody::XList`]
+//!
+//! * [`Data`](crate::response::Data) with a new variant:
+//!
+//!     - [`Data::XList`](crate::response::Data::XList)
+//!
+//! [`Data::XList`](crate::response::Data::XList) has the same structure as [`Data::List`](crate::response::Data::List).
+//! Special-use attributes, e.g., `\Sent` or `\Trash`, are represented as [`FlagNameAttribute`](crate::flag::FlagNameAttribute)s.
+//! Thus, both responses can be handled in one match arm, e.g., ...
+//!
+//! ```rust
+//! # use imap_types::response::Data;
+//! fn handle(data: Data) {
+//!     match data {
+//!         Data::List {
+//!             items,
+//!             delimiter,
+//!             mailbox,
+//!         }
+//!         | Data::XList {
+//!             items,
+//!             delimiter,
+//!             mailbox,
+//!         } => {
+//!             // ...
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::{
+    command::{error::ListError, CommandBody},
+    mailbox::{ListMailbox, Mailbox},
+};
+
+impl<'a> CommandBody<'a> {
+    /// Construct a XLIST command.
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the XLIST capability.
+    /// </div>
+    pub fn xlist<A, B>(
+        reference: A,
+        mailbox_wildcard: B,
+    ) -> Result<Self, ListError<A::Error, B::Error>>
+    where
+        A: TryInto<Mailbox<'a>>,
+        B: TryInto<ListMailbox<'a>>,
+    {
+        Ok(CommandBody::XList {
+            reference: reference.try_into().map_err(ListError::Reference)?,
+            mailbox_wildcard: mailbox_wildcard.try_into().map_err(ListError::Mailbox)?,
+        })
+    }
+}
@@ -11,6 +11,8 @@ use bounded_static_derive::ToStatic;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "ext_gmail")]
+use crate::extensions::gmail::GmailLabel;
 use crate::{
     body::BodyStructure,
     core::{AString, NString, NString8, Vec1},
@@ -235,6 +237,30 @@ pub enum MessageDataItemName<'a> {
     BinarySize {
         section: Vec<NonZeroU32>,
     },
+
+    #[cfg(feature = "ext_gmail")]
+    /// The Gmail message ID of a message.
+    ///
+    /// ```imap
+    /// X-GM-MSGID
+    /// ```
+    XGmMsgId,
+
+    #[cfg(feature = "ext_gmail")]
+    /// The Gmail thread ID of a message.
+    ///
+    /// ```imap
+    /// X-GM-THRID
+    /// ```
+    XGmThrId,
+
+    #[cfg(feature = "ext_gmail")]
+    /// The Gmail labels of a message.
+    ///
+    /// ```imap
+    /// X-GM-LABELS
+    /// ```
+    XGmLabels,
 }
 
 /// Message data item.
@@ -371,6 +397,30 @@ pub enum MessageDataItem<'a> {
         section: Vec<NonZeroU32>,
         size: u32,
     },
+
+    #[cfg(feature = "ext_gmail")]
+    /// A unique, unchangeable 64-bit number identifying a message across all Gmail folders.
+    ///
+    /// ```imap
+    /// X-GM-MSGID
+    /// ```
+    XGmMsgId(u64),
+
+    #[cfg(feature = "ext_gmail")]
+    /// A 64-bit number identifying the Gmail thread (conversation) a message belongs to.
+    ///
+    /// ```imap
+    /// X-GM-THRID
+    /// ```
+    XGmThrId(u64),
+
+    #[cfg(feature = "ext_gmail")]
+    /// The Gmail labels of a message.
+    ///
+    /// ```imap
+    /// X-GM-LABELS
+    /// ```
+    XGmLabels(Vec<GmailLabel<'a>>),
 }
 
 /// A part specifier is either a part number or one of the following:
@@ -112,6 +112,8 @@
 //! | IMAP4 UIDPLUS extension ([RFC 2359], [RFC 4315])                                                        |
 //! | IMAP4 Binary Content Extension ([RFC 3516])                                                             |
 //! | Internet Message Access Protocol - SORT and THREAD Extensions ([RFC 5256], [RFC 5957])                  |
+//! | IMAP Response Codes ([RFC 5530])                                                                        |
+//! | IMAP4 Multimailbox SEARCH Extension ([RFC 7377])                                                        |
 //!
 //! # Features
 //!
@@ -121,10 +123,12 @@
 //! |-----------------------|------------------------------------------------------------------------------------------------------------------------------|------------|
 //! | starttls              | IMAP4rev1 ([RFC 3501]; section 6.2.1)                                                                                        |            |
 //! | ext_condstore_qresync | IMAP Extensions: Quick Flag Changes Resynchronization (CONDSTORE) and Quick Mailbox Resynchronization (QRESYNC) ([RFC 7162]) | Unfinished |
-//! | ext_id                | IMAP4 ID extension ([RFC 2971])                                                                                              | Unfinished |
-//! | ext_login_referrals   | IMAP4 Login Referrals ([RFC 2221])                                                                                           | Unfinished |
+//! | ext_gmail             | Gmail IMAP Extensions ([X-GM-EXT-1])                                                                                         | Unfinished |
+//! | ext_id                | IMAP4 ID extension ([RFC 2971])                                                                                              |            |
+//! | ext_login_referrals   | IMAP4 Login Referrals ([RFC 2221])                                                                                           |            |
 //! | ext_mailbox_referrals | IMAP4 Mailbox Referrals ([RFC 2193])                                                                                         | Unfinished |
 //! | ext_metadata          | The IMAP METADATA Extension ([RFC 5464])                                                                                     | Unfinished |
+//! | ext_xlist             | Legacy XLIST command (predecessor of SPECIAL-USE, [RFC 6154])                                                                | Unfinished |
 //!
 //! STARTTLS is not an IMAP extension but feature-gated because it [should be avoided](https://nostarttls.secvuln.info/).
 //! For better performance and security, use "implicit TLS", i.e., IMAP-over-TLS on port 993, and don't use STARTTLS at all.
@@ -162,11 +166,15 @@
 //! [RFC 5161]: https://datatracker.ietf.org/doc/html/rfc5161
 //! [RFC 5256]: https://datatracker.ietf.org/doc/html/rfc5256
 //! [RFC 5464]: https://datatracker.ietf.org/doc/html/rfc5464
+//! [RFC 5530]: https://datatracker.ietf.org/doc/html/rfc5530
 //! [RFC 5957]: https://datatracker.ietf.org/doc/html/rfc5957
+//! [RFC 6154]: https://datatracker.ietf.org/doc/html/rfc6154
 //! [RFC 6851]: https://datatracker.ietf.org/doc/html/rfc6851
 //! [RFC 7162]: https://datatracker.ietf.org/doc/html/rfc7162
+//! [RFC 7377]: https://datatracker.ietf.org/doc/html/rfc7377
 //! [RFC 7888]: https://datatracker.ietf.org/doc/html/rfc7888
 //! [RFC 9208]: https://datatracker.ietf.org/doc/html/rfc9208
+//! [X-GM-EXT-1]: https://developers.google.com/gmail/imap/imap-extensions
 
 #![forbid(unsafe_code)]
 #![deny(missing_debug_implementations)]
@@ -194,6 +202,7 @@ pub mod extensions;
 pub mod fetch;
 pub mod flag;
 pub mod mailbox;
+pub mod pipelining;
 pub mod response;
 pub mod search;
 pub mod secret;
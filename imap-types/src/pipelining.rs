@@ -0,0 +1,258 @@
+//! Pipelining of commands.
+//!
+//! "The client MAY send another command without waiting for the completion result response of a command, subject to ambiguity rules.
+//! [...]
+//! If the client sends multiple commands without waiting for a command completion result response, it MUST NOT send a command that uses message sequence numbers while a command that can trigger an EXPUNGE response is in progress." ([RFC 3501, 5.5](https://www.rfc-editor.org/rfc/rfc3501#section-5.5))
+//!
+//! An EXPUNGE response must not be sent while responding to a `FETCH`, `STORE`, or `SEARCH` command.
+//! All other commands (including the `UID` variants) may trigger one. Thus, a command that uses
+//! message sequence numbers must not be pipelined after any of them, e.g.:
+//!
+//! ```text
+//! FETCH + STORE + SEARCH + CHECK   (valid)
+//! FETCH + NOOP + STORE             (invalid, STORE uses sequence numbers while NOOP may expunge)
+//! UID FETCH + FETCH                (invalid, FETCH uses sequence numbers while UID FETCH may expunge)
+//! ```
+//!
+//! Further, no command must be pipelined after `AUTHENTICATE`, `STARTTLS`, `IDLE`, `COMPRESS`, or `LOGOUT`.
+//!
+//! Note: The checks are conservative, e.g., every non-`UID` `SORT` is assumed to use sequence numbers.
+
+use crate::{
+    command::{Command, CommandBody},
+    search::SearchKey,
+};
+
+/// Command that must not be pipelined after a previous command.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PipeliningConflict {
+    /// Index of the conflicting command.
+    pub index: usize,
+    /// Index of the (earliest) previous command it conflicts with.
+    pub previous: usize,
+    /// Reason of the conflict.
+    pub reason: PipeliningConflictReason,
+}
+
+/// Reason of a [`PipeliningConflict`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PipeliningConflictReason {
+    /// The previous command must be completed before another command is sent.
+    ///
+    /// This is the case for `AUTHENTICATE`, `STARTTLS`, `IDLE`, `COMPRESS`, and `LOGOUT`.
+    Exclusive,
+    /// The command uses message sequence numbers while the previous command may trigger an
+    /// EXPUNGE response.
+    SequenceNumbers,
+}
+
+/// Returns all conflicts when the commands are pipelined (in the given order).
+///
+/// The commands can be pipelined safely if no conflict is returned.
+pub fn conflicts(commands: &[Command]) -> Vec<PipeliningConflict> {
+    (0..commands.len())
+        .filter_map(|index| first_conflict(&commands[..index], &commands[index]))
+        .collect()
+}
+
+/// Returns the first conflict when `next` is pipelined after the `outstanding` commands.
+///
+/// Use this to decide whether a command can be sent right away or must wait until (some of) the
+/// outstanding commands are completed.
+pub fn first_conflict(outstanding: &[Command], next: &Command) -> Option<PipeliningConflict> {
+    let uses_sequence_numbers = uses_sequence_numbers(&next.body);
+
+    outstanding
+        .iter()
+        .enumerate()
+        .find_map(|(previous, command)| {
+            let reason = if is_exclusive(&command.body) {
+                PipeliningConflictReason::Exclusive
+            } else if uses_sequence_numbers && may_expunge(&command.body) {
+                PipeliningConflictReason::SequenceNumbers
+            } else {
+                return None;
+            };
+
+            Some(PipeliningConflict {
+                index: outstanding.len(),
+                previous,
+                reason,
+            })
+        })
+}
+
+fn is_exclusive(body: &CommandBody) -> bool {
+    match body {
+        CommandBody::Authenticate { .. }
+        | CommandBody::Idle
+        | CommandBody::Compress { .. }
+        | CommandBody::Logout => true,
+        #[cfg(feature = "starttls")]
+        CommandBody::StartTLS => true,
+        _ => false,
+    }
+}
+
+fn may_expunge(body: &CommandBody) -> bool {
+    !matches!(
+        body,
+        CommandBody::Fetch { uid: false, .. }
+            | CommandBody::Store { uid: false, .. }
+            | CommandBody::Search { uid: false, .. }
+    )
+}
+
+fn uses_sequence_numbers(body: &CommandBody) -> bool {
+    match body {
+        CommandBody::Fetch { uid, .. }
+        | CommandBody::Store { uid, .. }
+        | CommandBody::Copy { uid, .. }
+        | CommandBody::Move { uid, .. } => !uid,
+        #[cfg(feature = "ext_gmail")]
+        CommandBody::StoreXGmLabels { uid, .. } => !uid,
+        CommandBody::Search { criteria, uid, .. } => {
+            !uid || contains_sequence_set(criteria.as_ref())
+        }
+        CommandBody::Sort {
+            search_criteria,
+            uid,
+            ..
+        }
+        | CommandBody::Thread {
+            search_criteria,
+            uid,
+            ..
+        } => !uid || contains_sequence_set(search_criteria.as_ref()),
+        // ESEARCH on the selected mailbox returns message sequence numbers.
+        CommandBody::ESearch {
+            source, criteria, ..
+        } => source.is_none() || contains_sequence_set(criteria.as_ref()),
+        _ => false,
+    }
+}
+
+fn contains_sequence_set(keys: &[SearchKey]) -> bool {
+    keys.iter().any(|key| match key {
+        SearchKey::SequenceSet(_) => true,
+        SearchKey::And(keys) => contains_sequence_set(keys.as_ref()),
+        SearchKey::Not(key) => contains_sequence_set(std::slice::from_ref(key)),
+        SearchKey::Or(left, right) => {
+            contains_sequence_set(std::slice::from_ref(left))
+                || contains_sequence_set(std::slice::from_ref(right))
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::Vec1,
+        fetch::Macro,
+        flag::{StoreResponse, StoreType},
+        mailbox::Mailbox,
+    };
+
+    fn commands(bodies: Vec<CommandBody<'static>>) -> Vec<Command<'static>> {
+        bodies
+            .into_iter()
+            .enumerate()
+            .map(|(index, body)| Command::new(format!("A{index}"), body).unwrap())
+            .collect()
+    }
+
+    fn fetch(uid: bool) -> CommandBody<'static> {
+        CommandBody::fetch("1:*", Macro::Fast, uid).unwrap()
+    }
+
+    fn store(uid: bool) -> CommandBody<'static> {
+        CommandBody::store("1", StoreType::Add, StoreResponse::Silent, vec![], uid).unwrap()
+    }
+
+    fn search(uid: bool, criteria: SearchKey<'static>) -> CommandBody<'static> {
+        CommandBody::Search {
+            charset: None,
+            criteria: Vec1::from(criteria),
+            uid,
+        }
+    }
+
+    fn copy(uid: bool) -> CommandBody<'static> {
+        CommandBody::copy("1", Mailbox::Inbox, uid).unwrap()
+    }
+
+    #[test]
+    fn test_conflicts_rfc3501() {
+        let valid = [
+            vec![
+                fetch(false),
+                store(false),
+                search(false, SearchKey::All),
+                CommandBody::Check,
+            ],
+            vec![fetch(true), store(true), copy(true)],
+            vec![CommandBody::Noop, fetch(true)],
+            vec![fetch(false), CommandBody::Logout],
+        ];
+
+        for test in valid {
+            assert!(conflicts(&commands(test.clone())).is_empty(), "{test:?}");
+        }
+
+        let invalid = [
+            (vec![fetch(false), CommandBody::Noop, store(false)], 2, 1),
+            (vec![store(false), copy(false), fetch(false)], 2, 1),
+            (vec![copy(false), copy(false)], 1, 0),
+            (vec![CommandBody::Check, fetch(false)], 1, 0),
+            (vec![fetch(true), fetch(false)], 1, 0),
+            (
+                vec![
+                    CommandBody::Expunge,
+                    search(
+                        true,
+                        SearchKey::Not(Box::new(SearchKey::SequenceSet("1:5".try_into().unwrap()))),
+                    ),
+                ],
+                1,
+                0,
+            ),
+        ];
+
+        for (test, index, previous) in invalid {
+            assert_eq!(
+                conflicts(&commands(test.clone())),
+                vec![PipeliningConflict {
+                    index,
+                    previous,
+                    reason: PipeliningConflictReason::SequenceNumbers,
+                }],
+                "{test:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_conflicts_exclusive() {
+        let commands = commands(vec![CommandBody::Idle, CommandBody::Noop, fetch(true)]);
+
+        assert_eq!(
+            conflicts(&commands),
+            vec![
+                PipeliningConflict {
+                    index: 1,
+                    previous: 0,
+                    reason: PipeliningConflictReason::Exclusive,
+                },
+                PipeliningConflict {
+                    index: 2,
+                    previous: 0,
+                    reason: PipeliningConflictReason::Exclusive,
+                },
+            ]
+        );
+
+        assert_eq!(first_conflict(&[], &commands[0]), None);
+    }
+}
@@ -14,9 +14,11 @@ use bounded_static_derive::ToStatic;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ext_id")]
-use crate::core::{IString, NString};
+use crate::extensions::id::IdParameters;
 #[cfg(feature = "ext_metadata")]
 use crate::extensions::metadata::{MetadataCode, MetadataResponse};
+#[cfg(any(feature = "ext_login_referrals", feature = "ext_mailbox_referrals"))]
+use crate::extensions::referral::ImapUrl;
 use crate::{
     auth::AuthMechanism,
     core::{impl_try_from, AString, Atom, Charset, QuotedChar, Tag, Text, Vec1},
@@ -24,6 +26,7 @@ use crate::{
     extensions::{
         compress::CompressionAlgorithm,
         enable::CapabilityEnable,
+        multisearch::{SearchCorrelator, SearchReturnData},
         quota::{QuotaGet, Resource},
         sort::SortAlgorithm,
         thread::{Thread, ThreadingAlgorithm},
@@ -124,6 +127,62 @@ pub enum Response<'a> {
     /// indicate the completion result (OK, NO, or BAD status) of a client
     /// command, and have a tag matching the command.
     Status(Status<'a>),
+    /// An untagged response that could not be parsed.
+    ///
+    /// Only produced by lenient decoding, e.g., for unknown vendor extensions.
+    Unparsed(Unparsed<'a>),
+}
+
+/// An (unknown or malformed) untagged response kept as raw bytes.
+///
+/// The raw bytes contain the whole response, i.e., all lines and literals (including the trailing
+/// CRLF).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Eq, Hash, ToStatic)]
+pub struct Unparsed<'a> {
+    raw: Cow<'a, [u8]>,
+}
+
+// We want a more readable `Debug` implementation.
+impl<'a> Debug for Unparsed<'a> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        struct BStr<'a>(&'a Cow<'a, [u8]>);
+
+        impl<'a> Debug for BStr<'a> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "b\"{}\"",
+                    crate::utils::escape_byte_string(self.0.as_ref())
+                )
+            }
+        }
+
+        f.debug_struct("Unparsed")
+            .field("raw", &BStr(&self.raw))
+            .finish()
+    }
+}
+
+impl<'a> Unparsed<'a> {
+    /// Constructs an unparsed response without validation.
+    ///
+    /// # Warning: IMAP conformance
+    ///
+    /// The caller must ensure that `raw` is a complete untagged response (starting with `*`).
+    /// Failing to do so may create invalid/unparsable IMAP messages, or even produce unintended
+    /// protocol flows. Do not call this constructor with untrusted data.
+    pub fn unvalidated<D>(raw: D) -> Self
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        Self { raw: raw.into() }
+    }
+
+    /// Raw bytes of the response.
+    pub fn raw(&self) -> &[u8] {
+        self.raw.as_ref()
+    }
 }
 
 /// Status response.
@@ -427,6 +486,16 @@ pub enum Data<'a> {
 
     Thread(Vec<Thread>),
 
+    /// ESEARCH response (RFC 4731, RFC 7377)
+    ESearch {
+        /// Command (and mailbox) the response belongs to.
+        correlator: Option<SearchCorrelator<'a>>,
+        /// Numbers are UIDs (instead of message sequence numbers).
+        uid: bool,
+        /// Search results.
+        data: Vec<SearchReturnData>,
+    },
+
     /// ### 7.2.6.  FLAGS Response
     ///
     /// * Contents: flag parenthesized list
@@ -559,7 +628,9 @@ pub enum Data<'a> {
     /// ID Response
     Id {
         /// Parameters
-        parameters: Option<Vec<(IString<'a>, NString<'a>)>>,
+        ///
+        /// Note: `NIL` and `()` are both represented as `None`.
+        parameters: Option<IdParameters<'a>>,
     },
 
     #[cfg(feature = "ext_metadata")]
@@ -568,6 +639,19 @@ pub enum Data<'a> {
         mailbox: Mailbox<'a>,
         items: MetadataResponse<'a>,
     },
+
+    #[cfg(feature = "ext_xlist")]
+    /// XLIST response
+    ///
+    /// The data is identical in format to the LIST response.
+    XList {
+        /// Name attributes
+        items: Vec<FlagNameAttribute<'a>>,
+        /// Hierarchy delimiter
+        delimiter: Option<QuotedChar>,
+        /// Name
+        mailbox: Mailbox<'a>,
+    },
 }
 
 impl<'a> Data<'a> {
@@ -832,14 +916,16 @@ pub enum Code<'a> {
     /// message without the \Seen flag set.
     Unseen(NonZeroU32),
 
-    /// IMAP4 Login Referrals (RFC 2221)
-    // TODO(misuse): the imap url is more complicated than that...
+    /// IMAP4 Login Referrals (RFC 2221) and IMAP4 Mailbox Referrals (RFC 2193)
+    ///
+    /// Followed by an IMAP URL that the client should use instead, e.g., to log in or to access
+    /// a mailbox on another server.
     #[cfg(any(feature = "ext_mailbox_referrals", feature = "ext_login_referrals"))]
     #[cfg_attr(
         docsrs,
         doc(cfg(any(feature = "ext_mailbox_referrals", feature = "ext_login_referrals")))
     )]
-    Referral(Cow<'a, str>),
+    Referral(ImapUrl<'a>),
 
     CompressionActive,
 
@@ -877,6 +963,96 @@ pub enum Code<'a> {
 
     UidNotSticky,
 
+    // ----- IMAP Response Codes (RFC 5530) -----
+    /// `UNAVAILABLE`
+    ///
+    /// Temporary failure because a subsystem is down.
+    Unavailable,
+
+    /// `AUTHENTICATIONFAILED`
+    ///
+    /// Authentication failed for some reason on which the server is
+    /// unwilling to elaborate.
+    AuthenticationFailed,
+
+    /// `AUTHORIZATIONFAILED`
+    ///
+    /// Authentication succeeded in using the authentication identity,
+    /// but the server cannot or will not allow the authentication
+    /// identity to act as the requested authorization identity.
+    AuthorizationFailed,
+
+    /// `EXPIRED`
+    ///
+    /// Either authentication succeeded or the server no longer had the
+    /// necessary data; either way, access is no longer permitted using
+    /// that passphrase.
+    Expired,
+
+    /// `PRIVACYREQUIRED`
+    ///
+    /// The operation is not permitted due to a lack of privacy.
+    PrivacyRequired,
+
+    /// `CONTACTADMIN`
+    ///
+    /// The user should contact the system administrator or support desk.
+    ContactAdmin,
+
+    /// `NOPERM`
+    ///
+    /// The access control system (e.g., Access Control List (ACL)) does
+    /// not permit this user to carry out an operation.
+    NoPerm,
+
+    /// `INUSE`
+    ///
+    /// An operation has not been carried out because it involves sawing
+    /// off a branch someone else is sitting on.
+    InUse,
+
+    /// `EXPUNGEISSUED`
+    ///
+    /// Someone else has issued an EXPUNGE for the same mailbox.
+    ExpungeIssued,
+
+    /// `CORRUPTION`
+    ///
+    /// The server discovered that some relevant data (e.g., the mailbox)
+    /// are corrupt.
+    Corruption,
+
+    /// `SERVERBUG`
+    ///
+    /// The server has detected a bug in itself.
+    ServerBug,
+
+    /// `CLIENTBUG`
+    ///
+    /// The server has detected a client bug.
+    ClientBug,
+
+    /// `CANNOT`
+    ///
+    /// The operation violates some invariant of the server and can never
+    /// succeed.
+    Cannot,
+
+    /// `LIMIT`
+    ///
+    /// The operation ran up against an implementation limit of some kind.
+    Limit,
+
+    /// `ALREADYEXISTS`
+    ///
+    /// The operation attempts to create something that already exists.
+    AlreadyExists,
+
+    /// `NONEXISTENT`
+    ///
+    /// The operation attempts to delete something that does not exist.
+    NonExistent,
+
     /// Additional response codes defined by particular client or server
     /// implementations SHOULD be prefixed with an "X" until they are
     /// added to a revision of this protocol.  Client implementations
@@ -1005,6 +1181,11 @@ pub enum Capability<'a> {
     LiteralMinus,
     /// See RFC 6851.
     Move,
+    /// See RFC 7377.
+    MultiSearch,
+    #[cfg(feature = "ext_gmail")]
+    /// Gmail IMAP Extensions.
+    XGmExt1,
     #[cfg(feature = "ext_id")]
     /// See RFC 2971.
     Id,
@@ -1022,6 +1203,9 @@ pub enum Capability<'a> {
     Binary,
     /// UIDPLUS extension (RFC 4351)
     UidPlus,
+    #[cfg(feature = "ext_xlist")]
+    /// Legacy XLIST command.
+    XList,
     /// Other/Unknown
     Other(CapabilityOther<'a>),
 }
@@ -1048,6 +1232,9 @@ impl<'a> Display for Capability<'a> {
             Self::LiteralPlus => write!(f, "LITERAL+"),
             Self::LiteralMinus => write!(f, "LITERAL-"),
             Self::Move => write!(f, "MOVE"),
+            Self::MultiSearch => write!(f, "MULTISEARCH"),
+            #[cfg(feature = "ext_gmail")]
+            Self::XGmExt1 => write!(f, "X-GM-EXT-1"),
             #[cfg(feature = "ext_id")]
             Self::Id => write!(f, "ID"),
             Self::Unselect => write!(f, "UNSELECT"),
@@ -1060,6 +1247,8 @@ impl<'a> Display for Capability<'a> {
             Self::MetadataServer => write!(f, "METADATA-SERVER"),
             Self::Binary => write!(f, "BINARY"),
             Self::UidPlus => write!(f, "UIDPLUS"),
+            #[cfg(feature = "ext_xlist")]
+            Self::XList => write!(f, "XLIST"),
             Self::Other(other) => write!(f, "{}", other.0),
         }
     }
@@ -1114,6 +1303,9 @@ impl<'a> From<Atom<'a>> for Capability<'a> {
             "literal+" => Self::LiteralPlus,
             "literal-" => Self::LiteralMinus,
             "move" => Self::Move,
+            "multisearch" => Self::MultiSearch,
+            #[cfg(feature = "ext_gmail")]
+            "x-gm-ext-1" => Self::XGmExt1,
             #[cfg(feature = "ext_id")]
             "id" => Self::Id,
             "sort" => Self::Sort(None),
@@ -1124,6 +1316,8 @@ impl<'a> From<Atom<'a>> for Capability<'a> {
             "binary" => Self::Binary,
             "unselect" => Self::Unselect,
             "uidplus" => Self::UidPlus,
+            #[cfg(feature = "ext_xlist")]
+            "xlist" => Self::XList,
             _ => {
                 // TODO(efficiency)
                 if let Some((left, right)) = split_once_cow(cow.clone(), "=") {
@@ -158,6 +158,14 @@ pub enum SearchKey<'a> {
 
     /// Messages that do not have the \Seen flag set.
     Unseen,
+
+    #[cfg(feature = "ext_gmail")]
+    /// Messages matching the specified Gmail search query (`X-GM-RAW`).
+    ///
+    /// <div class="warning">
+    /// This extension must only be used when the server advertised support for it sending the X-GM-EXT-1 capability.
+    /// </div>
+    XGmRaw(AString<'a>),
 }
 
 impl<'a> SearchKey<'a> {
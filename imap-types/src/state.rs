@@ -54,7 +54,7 @@ use bounded_static_derive::ToStatic;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{core::Tag, mailbox::Mailbox};
+use crate::{command::CommandBody, core::Tag, mailbox::Mailbox, response::StatusKind};
 
 /// State of the IMAP4rev1 connection.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -91,10 +91,234 @@ pub enum State<'a> {
     IdleSelected(Tag<'a>, Mailbox<'a>),
 }
 
+impl<'a> State<'a> {
+    /// Returns whether a command is valid in this state.
+    ///
+    /// Note: This doesn't check whether a required capability was advertised.
+    /// During `IDLE` (and in [`State::Greeting`] or [`State::Logout`]), no command is valid.
+    pub fn is_valid(&self, body: &CommandBody) -> bool {
+        let (authenticated, selected) = match self {
+            State::NotAuthenticated => (false, false),
+            State::Authenticated => (true, false),
+            State::Selected(_) => (true, true),
+            State::Greeting
+            | State::Logout
+            | State::IdleAuthenticated(_)
+            | State::IdleSelected(_, _) => return false,
+        };
+
+        match body {
+            // Any state
+            CommandBody::Capability | CommandBody::Noop | CommandBody::Logout => true,
+            #[cfg(feature = "ext_id")]
+            CommandBody::Id { .. } => true,
+            // Not authenticated state
+            #[cfg(feature = "starttls")]
+            CommandBody::StartTLS => !authenticated,
+            CommandBody::Authenticate { .. } | CommandBody::Login { .. } => !authenticated,
+            // Authenticated state
+            CommandBody::Enable { .. } => authenticated && !selected,
+            CommandBody::Select { .. }
+            | CommandBody::Examine { .. }
+            | CommandBody::Create { .. }
+            | CommandBody::Delete { .. }
+            | CommandBody::Rename { .. }
+            | CommandBody::Subscribe { .. }
+            | CommandBody::Unsubscribe { .. }
+            | CommandBody::List { .. }
+            | CommandBody::Lsub { .. }
+            | CommandBody::Status { .. }
+            | CommandBody::Append { .. }
+            | CommandBody::Idle
+            | CommandBody::Compress { .. }
+            | CommandBody::GetQuota { .. }
+            | CommandBody::GetQuotaRoot { .. }
+            | CommandBody::SetQuota { .. } => authenticated,
+            #[cfg(feature = "ext_metadata")]
+            CommandBody::SetMetadata { .. } | CommandBody::GetMetadata { .. } => authenticated,
+            #[cfg(feature = "ext_xlist")]
+            CommandBody::XList { .. } => authenticated,
+            // ESEARCH with source mailboxes doesn't require a selected mailbox.
+            CommandBody::ESearch {
+                source: Some(_), ..
+            } => authenticated,
+            // Selected state
+            CommandBody::Check
+            | CommandBody::Close
+            | CommandBody::Unselect
+            | CommandBody::Expunge
+            | CommandBody::ExpungeUid { .. }
+            | CommandBody::Search { .. }
+            | CommandBody::Sort { .. }
+            | CommandBody::Thread { .. }
+            | CommandBody::ESearch { source: None, .. }
+            | CommandBody::Fetch { .. }
+            | CommandBody::Store { .. }
+            | CommandBody::Copy { .. }
+            | CommandBody::Move { .. } => selected,
+            #[cfg(feature = "ext_gmail")]
+            CommandBody::StoreXGmLabels { .. } => selected,
+        }
+    }
+
+    /// Computes the state after a command was completed with a tagged status of the given kind.
+    ///
+    /// * A successful `LOGIN` or `AUTHENTICATE` enters [`State::Authenticated`].
+    /// * A successful `SELECT` or `EXAMINE` enters [`State::Selected`].
+    /// * A failed `SELECT` or `EXAMINE` enters [`State::Authenticated`] (closing the currently selected mailbox, if any).
+    /// * A successful `CLOSE` or `UNSELECT` enters [`State::Authenticated`].
+    /// * A successful `LOGOUT` enters [`State::Logout`].
+    /// * The completion of `IDLE` returns to the state before `IDLE`.
+    ///
+    /// Otherwise, the state doesn't change.
+    pub fn next(&self, body: &CommandBody, kind: StatusKind) -> State<'static> {
+        use crate::IntoStatic;
+
+        match (self, body, kind) {
+            (_, CommandBody::Login { .. } | CommandBody::Authenticate { .. }, StatusKind::Ok) => {
+                State::Authenticated
+            }
+            (
+                _,
+                CommandBody::Select { mailbox, .. } | CommandBody::Examine { mailbox, .. },
+                StatusKind::Ok,
+            ) => State::Selected(mailbox.clone().into_static()),
+            (
+                State::Authenticated | State::Selected(_),
+                CommandBody::Select { .. } | CommandBody::Examine { .. },
+                _,
+            ) => State::Authenticated,
+            (_, CommandBody::Close | CommandBody::Unselect, StatusKind::Ok) => State::Authenticated,
+            (_, CommandBody::Logout, StatusKind::Ok) => State::Logout,
+            (State::IdleAuthenticated(_), CommandBody::Idle, _) => State::Authenticated,
+            (State::IdleSelected(_, mailbox), CommandBody::Idle, _) => {
+                State::Selected(mailbox.clone().into_static())
+            }
+            (state, _, _) => state.clone().into_static(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{core::Tag, mailbox::Mailbox, IntoStatic, ToStatic};
+    use crate::{
+        core::Tag,
+        extensions::enable::{CapabilityEnable, Utf8Kind},
+        fetch::Macro,
+        mailbox::Mailbox,
+        IntoStatic, ToStatic,
+    };
+
+    #[test]
+    fn test_is_valid() {
+        let tests = [
+            (CommandBody::Noop, [false, true, true, true, false]),
+            (
+                CommandBody::login("alice", "pass").unwrap(),
+                [false, true, false, false, false],
+            ),
+            (
+                CommandBody::select("INBOX").unwrap(),
+                [false, false, true, true, false],
+            ),
+            (
+                CommandBody::Enable {
+                    capabilities: vec![CapabilityEnable::Utf8(Utf8Kind::Accept)]
+                        .try_into()
+                        .unwrap(),
+                },
+                [false, false, true, false, false],
+            ),
+            (CommandBody::Close, [false, false, false, true, false]),
+            (
+                CommandBody::fetch("1", Macro::All, false).unwrap(),
+                [false, false, false, true, false],
+            ),
+        ];
+
+        let states = [
+            State::Greeting,
+            State::NotAuthenticated,
+            State::Authenticated,
+            State::Selected(Mailbox::Inbox),
+            State::IdleSelected(Tag::try_from("A").unwrap(), Mailbox::Inbox),
+        ];
+
+        for (body, expected) in tests {
+            for (state, expected) in states.iter().zip(expected) {
+                assert_eq!(state.is_valid(&body), expected, "{state:?} {body:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_next() {
+        let selected = State::Selected(Mailbox::Inbox);
+        let other = Mailbox::try_from("other").unwrap();
+
+        let tests = [
+            (
+                State::NotAuthenticated,
+                CommandBody::login("alice", "pass").unwrap(),
+                StatusKind::Ok,
+                State::Authenticated,
+            ),
+            (
+                State::NotAuthenticated,
+                CommandBody::login("alice", "pass").unwrap(),
+                StatusKind::No,
+                State::NotAuthenticated,
+            ),
+            (
+                State::Authenticated,
+                CommandBody::select("INBOX").unwrap(),
+                StatusKind::Ok,
+                selected.clone(),
+            ),
+            (
+                selected.clone(),
+                CommandBody::examine(other.clone()).unwrap(),
+                StatusKind::Ok,
+                State::Selected(other.clone()),
+            ),
+            (
+                selected.clone(),
+                CommandBody::select(other).unwrap(),
+                StatusKind::No,
+                State::Authenticated,
+            ),
+            (
+                selected.clone(),
+                CommandBody::Close,
+                StatusKind::Ok,
+                State::Authenticated,
+            ),
+            (
+                selected.clone(),
+                CommandBody::Unselect,
+                StatusKind::Ok,
+                State::Authenticated,
+            ),
+            (
+                selected.clone(),
+                CommandBody::Noop,
+                StatusKind::Ok,
+                selected.clone(),
+            ),
+            (
+                State::IdleSelected(Tag::try_from("A").unwrap(), Mailbox::Inbox),
+                CommandBody::Idle,
+                StatusKind::Ok,
+                selected.clone(),
+            ),
+            (selected, CommandBody::Logout, StatusKind::Ok, State::Logout),
+        ];
+
+        for (state, body, kind, expected) in tests {
+            assert_eq!(state.next(&body, kind), expected);
+        }
+    }
 
     #[test]
     fn test_conversion() {